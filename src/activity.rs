@@ -0,0 +1,354 @@
+use tcod::console::*;
+use tcod::input::{self, Event};
+use tcod::pathfinding::AStar;
+use std::{cmp, thread};
+use std::time::Duration;
+use crate::combat::{line_of_fire, sees_invisible};
+use crate::controls::{Action, Command, PlayerAction, safety_prompt};
+use crate::game::{Game, MapNote, MessageCategory, step};
+use crate::items::INVENTORY_WIDTH;
+use crate::map::{Terrain, blocks_movement, is_blocked, map_size, mouse_tile, view_position};
+use crate::object::{EntityId, GameObject};
+use crate::portable::KeyCode;
+use crate::portable::colors::*;
+use crate::ui::{MENU_POLL_MS, Tcod, menu, render_all, text_input, visible_enemies};
+
+pub(crate) const MAX_NOTE_LENGTH: usize = 30;
+
+// NOTICE: Multi-turn activities all stop on the same dangers, see `interruption`
+#[derive(Clone, Debug)]
+pub(crate) struct Activity {
+    kind: ActivityKind,
+    /// Enemies already in view when the activity started don't interrupt it
+    known_enemies: Vec<EntityId>,
+    hp: i32,
+    danger_messages: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ActivityKind {
+    Travel { destination: (i32, i32) },
+    /// `sides` are the open tiles beside the last step, a change means a branch or a room
+    Run { dx: i32, dy: i32, sides: Option<(bool, bool)> },
+    Rest { turns_left: u32 },
+}
+
+impl Activity {
+    pub fn new(kind: ActivityKind, game: &Game, game_objects: &[GameObject]) -> Self {
+        Activity {
+            kind,
+            known_enemies: visible_enemies(game, game_objects).iter().filter_map(|enemy| enemy.id).collect(),
+            hp: game.player.fighter.map_or(0, |fighter| fighter.hp),
+            danger_messages: game.messages.danger_count(),
+        }
+    }
+}
+
+pub(crate) fn click_to_move(target: (i32, i32), game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let (map_width, map_height) = map_size(&game.map);
+    let (x, y) = target;
+    if x < 0 || y < 0 || x >= map_width || y >= map_height || !game.map[(x, y)].explored {
+        return PlayerAction::DidntTakeTurn;
+    }
+
+    let (player_x, player_y) = game.player.position();
+    let (dx, dy) = (x - player_x, y - player_y);
+    let adjacent = cmp::max(dx.abs(), dy.abs()) == 1;
+    let monster_clicked = game_objects
+        .iter()
+        .any(|game_object| game_object.position() == target && game_object.fighter.is_some() && game_object.ai.is_some());
+    if adjacent && monster_clicked {
+        game.activity = None;
+        return step(game, game_objects, Action::Move(dx, dy));
+    }
+
+    game.activity = Some(Activity::new(ActivityKind::Travel { destination: target }, game, game_objects));
+    activity_step(game, game_objects)
+}
+
+/// Why a multi-turn activity has to stop: a new hostile in view, lost hit points or a danger message
+fn interruption(activity: &mut Activity, game: &Game, game_objects: &[GameObject]) -> Option<String> {
+    let locale = &game.config.locale;
+    let new_enemy = visible_enemies(game, game_objects)
+        .into_iter()
+        .find(|enemy| enemy.id.is_some_and(|id| !activity.known_enemies.contains(&id)));
+    if let Some(enemy) = new_enemy {
+        return Some(tr!(locale, "interrupted_enemy", monster = enemy.display_name(locale)));
+    }
+    let hp = game.player.fighter.map_or(0, |fighter| fighter.hp);
+    if hp < activity.hp {
+        return Some(tr!(locale, "interrupted_hurt"));
+    }
+    if game.messages.danger_count() > activity.danger_messages {
+        return Some(tr!(locale, "interrupted_danger"));
+    }
+    activity.hp = hp;
+    None
+}
+
+pub(crate) fn activity_step(game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let mut activity = match game.activity.take() {
+        Some(activity) => activity,
+        None => return PlayerAction::DidntTakeTurn,
+    };
+    if let Some(reason) = interruption(&mut activity, game, game_objects) {
+        game.messages.add(reason, MessageCategory::Danger);
+        return PlayerAction::DidntTakeTurn;
+    }
+    match activity.kind {
+        ActivityKind::Travel { destination } => travel_step(activity, destination, game, game_objects),
+        ActivityKind::Run { dx, dy, sides } => run_step(activity, (dx, dy), sides, game, game_objects),
+        ActivityKind::Rest { turns_left } => rest_step(activity, turns_left, game, game_objects),
+    }
+}
+
+/// The first step of a run is an ordinary move, bumping into a monster or a door included
+pub(crate) fn start_run(dx: i32, dy: i32, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let (x, y) = game.player.position();
+    if !blocks_movement(x + dx, y + dy, game.player.movement(), game, game_objects) {
+        let run = ActivityKind::Run { dx, dy, sides: None };
+        game.activity = Some(Activity::new(run, game, game_objects));
+    }
+    step(game, game_objects, Action::Move(dx, dy))
+}
+
+/// Whether the tiles left and right of `(x, y)`, seen along `(dx, dy)`, can be walked on
+fn run_sides((x, y): (i32, i32), (dx, dy): (i32, i32), game: &Game) -> (bool, bool) {
+    (!game.map.is_blocked_tile(x - dy, y + dx), !game.map.is_blocked_tile(x + dy, y - dx))
+}
+
+/// Running stops on items and stairs, before doors and anything in the way, and where the walls beside it change
+fn run_step(
+    mut activity: Activity,
+    (dx, dy): (i32, i32),
+    sides: Option<(bool, bool)>,
+    game: &mut Game,
+    game_objects: &mut Vec<GameObject>,
+) -> PlayerAction {
+    let (x, y) = game.player.position();
+    let here = run_sides((x, y), (dx, dy), game);
+    let (next_x, next_y) = (x + dx, y + dy);
+    let door_ahead = game
+        .map
+        .get(next_x, next_y)
+        .is_some_and(|tile| matches!(tile.terrain, Terrain::ClosedDoor | Terrain::OpenDoor));
+    let on_something = game_objects
+        .iter()
+        .any(|game_object| game_object.position() == (x, y) && (game_object.item.is_some() || game_object.is_stairs));
+    if sides.is_some_and(|sides| sides != here)
+        || door_ahead
+        || on_something
+        || blocks_movement(next_x, next_y, game.player.movement(), game, game_objects)
+        || safety_prompt(Command::Move(dx, dy), game, game_objects).is_some()
+    {
+        return PlayerAction::DidntTakeTurn;
+    }
+    activity.kind = ActivityKind::Run { dx, dy, sides: Some(here) };
+    game.activity = Some(activity);
+    step(game, game_objects, Action::Move(dx, dy))
+}
+
+/// Resting waits until mana is full, or for a while for those who don't cast
+fn rest_step(mut activity: Activity, turns_left: u32, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let rested = game.player.caster.is_some_and(|caster| caster.mana >= caster.max_mana);
+    if turns_left == 0 || rested {
+        game.messages.add(tr!(game.config.locale, "rest_done"), MessageCategory::System);
+        return PlayerAction::DidntTakeTurn;
+    }
+    activity.kind = ActivityKind::Rest { turns_left: turns_left - 1 };
+    game.activity = Some(activity);
+    step(game, game_objects, Action::Wait)
+}
+
+/// Notes the player's tile, or removes the note already there
+pub(crate) fn edit_note(tcod: &mut Tcod, game: &mut Game) {
+    game.changed_outside_step = true;
+    let (x, y) = game.player.position();
+    if let Some(index) = game.notes.iter().position(|note| (note.x, note.y) == (x, y)) {
+        let note = game.notes.remove(index);
+        game.messages.add(tr!(game.config.locale, "note_removed", note = note.text), MessageCategory::System);
+        return;
+    }
+    if let Some(text) = text_input(&tr!(game.config.locale, "note_header"), MAX_NOTE_LENGTH, 40, &mut tcod.root) {
+        game.messages.add(tr!(game.config.locale, "note_added", note = text), MessageCategory::System);
+        game.notes.push(MapNote { x, y, text });
+    }
+}
+
+/// Stairs, NPCs, campfires and notes the player has already seen, the closest first
+fn landmarks(game: &Game, game_objects: &[GameObject]) -> Vec<((i32, i32), String)> {
+    let explored = |(x, y): (i32, i32)| game.map[(x, y)].explored;
+    let player = game.player.position();
+    let mut landmarks: Vec<((i32, i32), String)> = game_objects
+        .iter()
+        .filter(|game_object| (game_object.is_stairs || game_object.npc.is_some()) && explored(game_object.position()))
+        .map(|game_object| (game_object.position(), game_object.display_name(&game.config.locale)))
+        .collect();
+    for (x, y) in game.map.iter_coords() {
+        if game.map[(x, y)].terrain == Terrain::Campfire && explored((x, y)) {
+            landmarks.push(((x, y), tr!(game.config.locale, "landmark_campfire")));
+        }
+    }
+    landmarks.extend(game.notes.iter().map(|note| ((note.x, note.y), tr!(game.config.locale, "landmark_note", note = note.text))));
+    landmarks.sort_by_key(|&((x, y), _)| cmp::max((x - player.0).abs(), (y - player.1).abs()));
+    landmarks.truncate(9);
+    landmarks
+}
+
+pub(crate) fn landmark_menu(tcod: &mut Tcod, game: &mut Game, game_objects: &[GameObject]) -> Option<(i32, i32)> {
+    let landmarks = landmarks(game, game_objects);
+    if landmarks.is_empty() {
+        game.messages.add(tr!(game.config.locale, "no_landmarks"), MessageCategory::System);
+        return None;
+    }
+    let (player_x, player_y) = game.player.position();
+    let options: Vec<String> = landmarks
+        .iter()
+        .map(|&((x, y), ref name)| {
+            let distance = cmp::max((x - player_x).abs(), (y - player_y).abs());
+            tr!(game.config.locale, "landmark_option", landmark = name, distance = distance)
+        })
+        .collect();
+    let choice = menu(&tr!(game.config.locale, "landmark_header"), &options, INVENTORY_WIDTH, tcod)?;
+    Some(landmarks[choice].0)
+}
+
+/// The steps from the player to `destination` across explored tiles, empty when there is no way there
+fn travel_path(destination: (i32, i32), game: &Game, game_objects: &[GameObject]) -> Vec<(i32, i32)> {
+    let start = game.player.position();
+    let (map_width, map_height) = map_size(&game.map);
+    let map = &game.map;
+    let movement = game.player.movement();
+    let walkable = move |(x, y): (i32, i32)| {
+        map[(x, y)].explored && (map[(x, y)].terrain == Terrain::ClosedDoor || !is_blocked(x, y, movement, map, game_objects))
+    };
+    // NOTICE: Walls, monsters & terrain the player cannot cross are no place to travel to
+    let (x, y) = destination;
+    if x < 0 || y < 0 || x >= map_width || y >= map_height || !walkable(destination) {
+        return vec![];
+    }
+    let mut path = AStar::new_from_callback(
+        map_width,
+        map_height,
+        move |_, to| if walkable(to) { 1.0 } else { 0.0 },
+        1.41,
+    );
+    if path.find(start, destination) {
+        path.iter().collect()
+    } else {
+        vec![]
+    }
+}
+
+/// Paths through tiles out of view or next to a chasm or deep water need to be confirmed
+fn risky_path(path: &[(i32, i32)], game: &Game) -> bool {
+    let (map_width, map_height) = map_size(&game.map);
+    path.iter().any(|&(x, y)| {
+        let hazard_nearby = (-1..=1).any(|dx| {
+            (-1..=1).any(|dy| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx >= 0
+                    && ny >= 0
+                    && nx < map_width
+                    && ny < map_height
+                    && game.map[(nx, ny)].explored
+                    && matches!(game.map[(nx, ny)].terrain, Terrain::Chasm | Terrain::DeepWater)
+            })
+        });
+        hazard_nearby || !game.fov.is_in_fov(x, y)
+    })
+}
+
+/// Highlights the planned path for a frame before travelling, a second click on the destination or Enter confirms risky ones
+pub(crate) fn start_travel(target: (i32, i32), tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let (map_width, map_height) = map_size(&game.map);
+    let (x, y) = target;
+    if x < 0 || y < 0 || x >= map_width || y >= map_height || !game.map[(x, y)].explored {
+        return PlayerAction::DidntTakeTurn;
+    }
+    let path = travel_path(target, game, game_objects);
+    if path.len() > 1 {
+        render_all(tcod, game, game_objects);
+        for (x, y) in path.iter().filter_map(|&tile| view_position(tile, game)) {
+            tcod.root.set_char_background(x, y, tcod.palette.color(DARK_AZURE), BackgroundFlag::Set);
+        }
+        let risky = risky_path(&path, game);
+        if risky {
+            tcod.root.set_default_foreground(LIGHT_YELLOW);
+            tcod.root.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, tr!(game.config.locale, "travel_confirm"));
+        }
+        tcod.root.flush();
+        if risky && !confirm_travel(target, tcod, game) {
+            game.messages.add(tr!(game.config.locale, "cancelled"), MessageCategory::System);
+            return PlayerAction::DidntTakeTurn;
+        }
+    }
+    click_to_move(target, game, game_objects)
+}
+
+/// Draws the line of fire from the player to `target` over the map, red from the first obstruction on
+pub(crate) fn render_line_of_fire(target: (i32, i32), tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let (path, obstruction) = line_of_fire(game.player.position(), target, game, game_objects);
+    let blocked_from = obstruction.unwrap_or(path.len());
+    for (index, &tile) in path.iter().enumerate() {
+        let color = match index.cmp(&blocked_from) {
+            cmp::Ordering::Less => DARK_AZURE,
+            cmp::Ordering::Equal => LIGHT_RED,
+            cmp::Ordering::Greater => DARK_RED,
+        };
+        if let Some((x, y)) = view_position(tile, game) {
+            tcod.root.set_char_background(x, y, tcod.palette.color(color), BackgroundFlag::Set);
+        }
+    }
+    if let Some(&(x, y)) = obstruction.map(|index| &path[index]) {
+        let blocker = game
+            .partner
+            .iter()
+            .chain(game_objects)
+            .find(|game_object| {
+                game_object.position() == (x, y) && game_object.fighter.is_some() && (!game_object.invisible || sees_invisible(game))
+            });
+        let text = match blocker {
+            Some(game_object) if !game.map[(x, y)].blocked => {
+                tr!(game.config.locale, "line_of_fire_blocked", name = game_object.display_name(&game.config.locale))
+            }
+            _ => tr!(game.config.locale, "line_of_fire_wall"),
+        };
+        tcod.root.set_default_foreground(tcod.palette.color(LIGHT_RED));
+        tcod.root.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, text);
+    }
+}
+
+fn confirm_travel(target: (i32, i32), tcod: &mut Tcod, game: &Game) -> bool {
+    while !tcod.root.window_closed() {
+        match input::check_for_event(input::KEY_PRESS | input::MOUSE_PRESS) {
+            Some((_, Event::Key(key))) => return key.code == KeyCode::Enter,
+            Some((_, Event::Mouse(mouse))) if mouse.lbutton_pressed => {
+                return mouse_tile(mouse, game) == Some(target);
+            }
+            Some((_, Event::Mouse(mouse))) if mouse.rbutton_pressed => return false,
+            _ => thread::sleep(Duration::from_millis(MENU_POLL_MS)),
+        }
+    }
+    false
+}
+
+fn travel_step(activity: Activity, destination: (i32, i32), game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let start = game.player.position();
+    if start == destination {
+        return PlayerAction::DidntTakeTurn;
+    }
+
+    match travel_path(destination, game, game_objects).first().copied() {
+        Some((x, y)) => {
+            if (x, y) != destination {
+                game.activity = Some(activity);
+            }
+            step(game, game_objects, Action::Move(x - start.0, y - start.1))
+        }
+        None => {
+            game.messages.add(tr!(game.config.locale, "travel_no_path"), MessageCategory::System);
+            PlayerAction::DidntTakeTurn
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use rand::Rng;
+use crate::game::{Game, MessageCategory};
+use crate::items::new_item;
+use crate::map::{Movement, is_blocked, map_size};
+use crate::monsters::{apply_affixes, new_monster};
+use crate::object::GameObject;
+#[cfg(feature = "window")]
+use crate::game::{Arena, place_partner};
+#[cfg(feature = "window")]
+use crate::map::{initialise_fov, level_rng, make_cave};
+
+const ARENA_FIRST_WAVE: u32 = 3;
+const ARENA_WAVE_GROWTH: u32 = 2;
+const ARENA_WAVE_DELAY: u32 = 5;
+const ARENA_SPAWN_DISTANCE: f32 = 8.0;
+
+#[cfg(feature = "window")]
+pub(crate) fn start_arena(game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    game.map = make_cave(&mut game.player, game_objects, &game.config, &mut level_rng(game.seed, game.dungeon_level));
+    game.spawned_uniques.clear();
+    place_partner(game, game_objects);
+    game.arena = Some(Arena::default());
+    initialise_fov(game, game_objects);
+    spawn_wave(game, game_objects);
+}
+
+pub(crate) fn arena_step(game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let wave_alive = game_objects.iter().any(|game_object| game_object.ai.is_some() && game_object.fighter.is_some());
+    let arena = match game.arena.as_mut() {
+        Some(arena) => arena,
+        None => return,
+    };
+    if wave_alive {
+        return;
+    }
+    if arena.countdown == 0 {
+        // NOTICE: The wave that just fell counts as survived, the reward lands somewhere in the cave
+        arena.waves_survived = arena.wave;
+        arena.countdown = ARENA_WAVE_DELAY;
+        let wave = arena.wave;
+        game.messages.add(tr!(game.config.locale, "arena_wave_cleared", wave = wave, delay = ARENA_WAVE_DELAY), MessageCategory::System);
+        if let Some((x, y)) = arena_spawn_point(0.0, game, game_objects) {
+            let item = new_item(x, y, &game.config, &mut game.rng);
+            game_objects.push(item);
+        }
+        return;
+    }
+    arena.countdown -= 1;
+    if arena.countdown == 0 {
+        spawn_wave(game, game_objects);
+    }
+}
+
+fn spawn_wave(game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let wave = match game.arena.as_mut() {
+        Some(arena) => {
+            arena.wave += 1;
+            arena.wave
+        }
+        None => return,
+    };
+    let first_monster = game_objects.len();
+    for _ in 0..ARENA_FIRST_WAVE + (wave - 1) * ARENA_WAVE_GROWTH {
+        if let Some((x, y)) = arena_spawn_point(ARENA_SPAWN_DISTANCE, game, game_objects) {
+            let monster = new_monster(x, y, &game.config, &mut game.rng);
+            game_objects.push(monster);
+        }
+    }
+    // NOTICE: Later waves roll affixes as if they were that many levels deep
+    apply_affixes(wave, &game.config, &mut game.rng, &mut game_objects[first_monster..]);
+    game.messages.add(tr!(game.config.locale, "arena_wave", wave = wave), MessageCategory::Danger);
+}
+
+fn arena_spawn_point(min_distance: f32, game: &mut Game, game_objects: &[GameObject]) -> Option<(i32, i32)> {
+    let (map_width, map_height) = map_size(&game.map);
+    let player = &game.player;
+    let candidates: Vec<(i32, i32)> = (0..map_width)
+        .flat_map(|x| (0..map_height).map(move |y| (x, y)))
+        .filter(|&(x, y)| !is_blocked(x, y, Movement::default(), &game.map, game_objects) && player.distance(x, y) >= min_distance)
+        .collect();
+    if candidates.is_empty() {
+        None
+    } else {
+        Some(candidates[game.rng.gen_range(0, candidates.len())])
+    }
+}
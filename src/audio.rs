@@ -0,0 +1,15 @@
+/// The window's sound effects and music, played through rodio
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Sound {
+    Hit,
+    Death,
+    PickUp,
+    Stairs,
+    LevelUp,
+}
@@ -0,0 +1,176 @@
+use super::*;
+use rodio::Source;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+use crate::config::Config;
+
+const SFX_DIR: &str = "assets/sfx";
+const MUSIC_DIR: &str = "assets/music";
+const CROSSFADE_SECONDS: f32 = 2.0;
+
+impl Sound {
+    const ALL: [Sound; 5] = [Sound::Hit, Sound::Death, Sound::PickUp, Sound::Stairs, Sound::LevelUp];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Sound::Hit => "hit.wav",
+            Sound::Death => "death.wav",
+            Sound::PickUp => "pickup.wav",
+            Sound::Stairs => "stairs.wav",
+            Sound::LevelUp => "levelup.wav",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MusicTrack {
+    Upper,
+    Caverns,
+    Depths,
+}
+
+impl MusicTrack {
+    pub fn for_level(dungeon_level: u32) -> Self {
+        match dungeon_level {
+            0..=3 => MusicTrack::Upper,
+            4..=6 => MusicTrack::Caverns,
+            _ => MusicTrack::Depths,
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            MusicTrack::Upper => "upper.wav",
+            MusicTrack::Caverns => "caverns.wav",
+            MusicTrack::Depths => "depths.wav",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum VolumeChannel {
+    Effects,
+    Music,
+}
+
+struct Music {
+    track: MusicTrack,
+    sink: Option<rodio::Sink>,
+    fade: f32,
+}
+
+// NOTICE: Without an output device, or with a missing sample, sounds are silently skipped
+pub(crate) struct Audio {
+    device: Option<rodio::Device>,
+    samples: HashMap<Sound, Vec<u8>>,
+    pub(crate) volume: f32,
+    pub(crate) music_volume: f32,
+    pub(crate) muted: bool,
+    music: Option<Music>,
+    fading_music: Vec<Music>,
+}
+
+impl Audio {
+    pub fn new(config: &Config) -> Self {
+        let samples = Sound::ALL
+            .iter()
+            .filter_map(|&sound| {
+                fs::read(Path::new(SFX_DIR).join(sound.file_name()))
+                    .ok()
+                    .map(|bytes| (sound, bytes))
+            })
+            .collect();
+
+        Audio {
+            device: rodio::default_output_device(),
+            samples,
+            volume: config.volume.clamp(0.0, 1.0),
+            music_volume: config.music_volume.clamp(0.0, 1.0),
+            muted: config.muted,
+            music: None,
+            fading_music: vec![],
+        }
+    }
+
+    pub fn play(&self, sound: Sound) {
+        if self.muted {
+            return;
+        }
+        if let (Some(device), Some(bytes)) = (self.device.as_ref(), self.samples.get(&sound)) {
+            if let Ok(source) = rodio::Decoder::new(Cursor::new(bytes.clone())) {
+                let sink = rodio::Sink::new(device);
+                sink.set_volume(self.volume);
+                sink.append(source);
+                sink.detach();
+            }
+        }
+    }
+
+    pub fn volume(&self, channel: VolumeChannel) -> f32 {
+        match channel {
+            VolumeChannel::Effects => self.volume,
+            VolumeChannel::Music => self.music_volume,
+        }
+    }
+
+    pub fn change_volume(&mut self, channel: VolumeChannel, delta: f32) {
+        let volume = match channel {
+            VolumeChannel::Effects => &mut self.volume,
+            VolumeChannel::Music => &mut self.music_volume,
+        };
+        *volume = (*volume + delta).clamp(0.0, 1.0);
+        self.update_music(0.0);
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.update_music(0.0);
+    }
+
+    /// Starts looping `track`, crossfading from the current one, unless it is already playing
+    pub fn play_music(&mut self, track: MusicTrack) {
+        if self.music.as_ref().map(|music| music.track) == Some(track) {
+            return;
+        }
+        if let Some(music) = self.music.take() {
+            self.fading_music.push(music);
+        }
+
+        let sink = self.device.as_ref().and_then(|device| {
+            let file = File::open(Path::new(MUSIC_DIR).join(track.file_name())).ok()?;
+            let source = rodio::Decoder::new(BufReader::new(file)).ok()?;
+            let sink = rodio::Sink::new(device);
+            sink.set_volume(0.0);
+            sink.append(source.repeat_infinite());
+            Some(sink)
+        });
+        self.music = Some(Music { track, sink, fade: 0.0 });
+    }
+
+    pub fn stop_music(&mut self) {
+        self.music = None;
+        self.fading_music.clear();
+    }
+
+    /// Moves crossfades forward by `elapsed` seconds, fully faded out tracks are dropped
+    pub fn update_music(&mut self, elapsed: f32) {
+        let step = elapsed / CROSSFADE_SECONDS;
+        let volume = if self.muted { 0.0 } else { self.music_volume };
+
+        if let Some(ref mut music) = self.music {
+            music.fade = (music.fade + step).min(1.0);
+        }
+        for music in self.fading_music.iter_mut() {
+            music.fade = (music.fade - step).max(0.0);
+        }
+        self.fading_music.retain(|music| music.fade > 0.0);
+
+        for music in self.music.iter().chain(self.fading_music.iter()) {
+            if let Some(ref sink) = music.sink {
+                sink.set_volume(volume * music.fade);
+            }
+        }
+    }
+}
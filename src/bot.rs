@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::{iter, process};
+use serde::Serialize;
+use crate::character::Class;
+use crate::combat::{dispatch_events, sees_invisible};
+use crate::config::{Config, Locale};
+use crate::controls::{Action, PlayerAction};
+use crate::game::{Game, Message, assign_entity_ids, new_game, step};
+use crate::map::map_size;
+use crate::object::GameObject;
+use crate::save::export_lines;
+use crate::tutorial::advance_tutorial;
+use crate::ui::MSG_HEIGHT;
+
+/// What the player knows of the game, as handed to bots and exported each turn
+#[derive(Debug, Serialize)]
+pub(crate) struct Observation {
+    turn: u32,
+    dungeon_level: u32,
+    alive: bool,
+    player: ObservedObject,
+    mana: Option<(i32, i32)>,
+    /// Item names in inventory order, the indices `UseItem` & co take
+    inventory: Vec<String>,
+    /// Fighters, items & features in view, or always visible once explored
+    objects: Vec<ObservedObject>,
+    /// The explored map as text, one string per row, see `export_lines`
+    tiles: Vec<String>,
+    messages: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ObservedObject {
+    name: String,
+    glyph: char,
+    x: i32,
+    y: i32,
+    hp: Option<(i32, i32)>,
+}
+
+impl ObservedObject {
+    pub fn new(game_object: &GameObject, locale: &Locale) -> Self {
+        ObservedObject {
+            name: game_object.display_name(locale),
+            glyph: game_object.char,
+            x: game_object.x,
+            y: game_object.y,
+            hp: game_object.fighter.map(|fighter| (fighter.hp, fighter.max_hp)),
+        }
+    }
+}
+
+impl Observation {
+    pub fn new(game: &Game, game_objects: &[GameObject]) -> Self {
+        let see_invisible = sees_invisible(game);
+        let objects = game
+            .partner
+            .iter()
+            .chain(game_objects.iter())
+            .filter(|game_object| {
+                let visible = game.fov.is_in_fov(game_object.x, game_object.y) && (!game_object.invisible || see_invisible);
+                visible || (game_object.always_visible && game.map[(game_object.x, game_object.y)].explored)
+            })
+            .map(|game_object| ObservedObject::new(game_object, &game.config.locale))
+            .collect();
+        let messages: Vec<String> = game.messages.iter().rev().take(MSG_HEIGHT).map(Message::text).collect();
+        Observation {
+            turn: game.turn,
+            dungeon_level: game.dungeon_level,
+            alive: game.player.is_alive,
+            player: ObservedObject::new(&game.player, &game.config.locale),
+            mana: game.player.caster.map(|caster| (caster.mana, caster.max_mana)),
+            inventory: game.inventory.iter().map(|item| item.display_name(&game.config.locale)).collect(),
+            objects,
+            tiles: export_lines(game, game_objects),
+            messages: messages.into_iter().rev().collect(),
+        }
+    }
+}
+
+/// What a program playing the game gets to do, look at what the player knows and act on it
+trait GameInterface {
+    fn observe(&self) -> Observation;
+    fn act(&mut self, action: Action) -> PlayerAction;
+}
+
+/// A game running without a window, for bots
+struct Session {
+    game: Game,
+    game_objects: Vec<GameObject>,
+}
+
+impl GameInterface for Session {
+    fn observe(&self) -> Observation {
+        Observation::new(&self.game, &self.game_objects)
+    }
+
+    // NOTICE: Runs a frame of `play_game` minus the front end, perks offered along the way stay pending
+    fn act(&mut self, action: Action) -> PlayerAction {
+        let (game, game_objects) = (&mut self.game, &mut self.game_objects);
+        assign_entity_ids(game, game_objects);
+        let player_action = step(game, game_objects, action);
+        dispatch_events(game, game_objects);
+        advance_tutorial(game, game_objects);
+        game.taken_actions.clear();
+        game.sounds.clear();
+        game.projectiles.clear();
+        player_action
+    }
+}
+
+/// Lets the program at `path` play a new game, sending it an `Observation` per line and reading back an
+/// `Action` per line, e.g. `{"Move":[1,0]}`, `"Wait"` or `{"UseItem":0}`
+pub(crate) fn run_bot(path: &str, config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut bot = process::Command::new(path)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()?;
+    let mut bot_input = bot.stdin.take().ok_or("the bot's input could not be opened")?;
+    let mut bot_output = BufReader::new(bot.stdout.take().ok_or("the bot's output could not be opened")?).lines();
+
+    let (game, game_objects) = new_game("bot", Class::default(), config);
+    let mut session = Session { game, game_objects };
+    while session.game.player.is_alive {
+        writeln!(bot_input, "{}", serde_json::to_string(&session.observe())?)?;
+        let line = match bot_output.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        match serde_json::from_str::<Action>(&line) {
+            Ok(action) => {
+                session.act(action);
+            }
+            Err(error) => eprintln!("Invalid action from the bot, {:?}: {}", line, error),
+        }
+    }
+    // NOTICE: The bot may well have quit already, the last observation only tells it how the run ended
+    writeln!(bot_input, "{}", serde_json::to_string(&session.observe())?).ok();
+    drop(bot_input);
+    bot.wait()?;
+
+    let game = &session.game;
+    println!("Seed: {}", game.seed);
+    println!("Turns: {}, dungeon level: {}, score: {}", game.turn, game.dungeon_level, game.stats.score());
+    Ok(())
+}
+
+pub(crate) fn print_map(game: &Game, game_objects: &[GameObject]) {
+    println!("Seed: {}", game.seed);
+    println!("Dungeon level: {}", game.dungeon_level);
+    let (map_width, map_height) = map_size(&game.map);
+    for y in 0..map_height {
+        let line: String = (0..map_width)
+            .map(|x| {
+                let game_object = iter::once(&game.player)
+                    .chain(game.partner.iter())
+                    .chain(game_objects.iter())
+                    .filter(|game_object| game_object.position() == (x, y))
+                    .max_by_key(|game_object| game_object.blocks);
+                match game_object {
+                    Some(game_object) => game_object.char,
+                    None if game.map[(x, y)].blocked => '#',
+                    None => game.map[(x, y)].terrain.glyph().unwrap_or('.'),
+                }
+            })
+            .collect();
+        println!("{}", line);
+    }
+}
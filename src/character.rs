@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::combat::{DeathCallback, Fighter};
+use crate::config::{Config, Locale, Name};
+use crate::game::{Game, MessageCategory};
+use crate::items::{Item, new_consumable, new_weapon};
+use crate::monsters::Caster;
+use crate::object::GameObject;
+use crate::portable::colors::*;
+
+/// Perk choices, class names and the statistics screen's lines, for both front ends
+#[cfg(any(feature = "window", feature = "terminal"))]
+mod frontend;
+/// The window's perk, class and character screens, and the co-op partner's hero
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(any(feature = "window", feature = "terminal"))]
+pub(crate) use self::frontend::*;
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+const MAGE_MANA: i32 = 12;
+const MAX_SKILL_LEVEL: i32 = 5;
+const SKILL_PRACTICE: i32 = 10;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Stats {
+    pub(crate) kills: BTreeMap<String, u32>,
+    pub(crate) damage_dealt: i32,
+    pub(crate) damage_taken: i32,
+    pub(crate) items_used: u32,
+    pub(crate) tiles_explored: u32,
+    pub(crate) deepest_level: u32,
+}
+
+impl Stats {
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.deepest_level * 100 + self.total_kills() * 10 + self.tiles_explored / 10
+    }
+}
+
+// NOTICE: Skills grow with practice, or with the point earned on each character level
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Skill {
+    Melee,
+    Evasion,
+    Stealth,
+    Lore,
+}
+
+impl Skill {
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            Skill::Melee => tr!(locale, "skill_melee"),
+            Skill::Evasion => tr!(locale, "skill_evasion"),
+            Skill::Stealth => tr!(locale, "skill_stealth"),
+            Skill::Lore => tr!(locale, "skill_lore"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+struct SkillProgress {
+    level: i32,
+    practice: i32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Skills {
+    melee: SkillProgress,
+    evasion: SkillProgress,
+    stealth: SkillProgress,
+    lore: SkillProgress,
+    pub(crate) points: u32,
+}
+
+impl Skills {
+    fn progress(&self, skill: Skill) -> &SkillProgress {
+        match skill {
+            Skill::Melee => &self.melee,
+            Skill::Evasion => &self.evasion,
+            Skill::Stealth => &self.stealth,
+            Skill::Lore => &self.lore,
+        }
+    }
+
+    fn progress_mut(&mut self, skill: Skill) -> &mut SkillProgress {
+        match skill {
+            Skill::Melee => &mut self.melee,
+            Skill::Evasion => &mut self.evasion,
+            Skill::Stealth => &mut self.stealth,
+            Skill::Lore => &mut self.lore,
+        }
+    }
+
+    pub fn level(&self, skill: Skill) -> i32 {
+        self.progress(skill).level
+    }
+
+    /// Returns whether the skill gained a level
+    fn raise(&mut self, skill: Skill) -> bool {
+        let progress = self.progress_mut(skill);
+        if progress.level >= MAX_SKILL_LEVEL {
+            return false;
+        }
+        progress.level += 1;
+        progress.practice = 0;
+        true
+    }
+
+    /// Returns whether the practice was enough to gain a level
+    fn practice(&mut self, skill: Skill, amount: i32) -> bool {
+        let progress = self.progress_mut(skill);
+        if progress.level >= MAX_SKILL_LEVEL {
+            return false;
+        }
+        progress.practice += amount;
+        progress.practice >= practice_needed(progress.level) && self.raise(skill)
+    }
+}
+
+// NOTICE: Perks are offered every few character levels, the player keeps one of a random handful of their class's perks
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Perk {
+    Tough,
+    Quick,
+    Scavenger,
+    Medic,
+    Brawler,
+    ThickSkin,
+    KeenNose,
+}
+
+impl Perk {
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            Perk::Tough => tr!(locale, "perk_tough"),
+            Perk::Quick => tr!(locale, "perk_quick"),
+            Perk::Scavenger => tr!(locale, "perk_scavenger"),
+            Perk::Medic => tr!(locale, "perk_medic"),
+            Perk::Brawler => tr!(locale, "perk_brawler"),
+            Perk::ThickSkin => tr!(locale, "perk_thick_skin"),
+            Perk::KeenNose => tr!(locale, "perk_keen_nose"),
+        }
+    }
+
+    pub fn description(self, locale: &Locale) -> String {
+        match self {
+            Perk::Tough => tr!(locale, "perk_tough_description"),
+            Perk::Quick => tr!(locale, "perk_quick_description"),
+            Perk::Scavenger => tr!(locale, "perk_scavenger_description"),
+            Perk::Medic => tr!(locale, "perk_medic_description"),
+            Perk::Brawler => tr!(locale, "perk_brawler_description"),
+            Perk::ThickSkin => tr!(locale, "perk_thick_skin_description"),
+            Perk::KeenNose => tr!(locale, "perk_keen_nose_description"),
+        }
+    }
+}
+
+fn practice_needed(level: i32) -> i32 {
+    SKILL_PRACTICE * (level + 1)
+}
+
+pub(crate) fn train(skill: Skill, amount: i32, game: &mut Game) {
+    if game.skills.practice(skill, amount) {
+        let level = game.skills.level(skill);
+        game.messages.add(
+            tr!(game.config.locale, "skill_improved", skill = skill.name(&game.config.locale), level = level),
+            MessageCategory::System,
+        );
+    }
+}
+
+// NOTICE: Character classes, picked when starting a new game
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Class {
+    #[default]
+    Fighter,
+    Rogue,
+    Mage,
+}
+
+impl Class {
+    /// Starting hit points, defense and power
+    fn stats(self) -> (i32, i32, i32) {
+        match self {
+            Class::Fighter => (40, 2, 6),
+            Class::Rogue => (30, 1, 5),
+            Class::Mage => (24, 0, 3),
+        }
+    }
+
+    pub(crate) fn starting_skills(self) -> Skills {
+        let mut skills = Skills::default();
+        skills.raise(match self {
+            Class::Fighter => Skill::Melee,
+            Class::Rogue => Skill::Stealth,
+            Class::Mage => Skill::Lore,
+        });
+        skills
+    }
+
+    pub(crate) fn starting_inventory(self, config: &Config) -> Vec<GameObject> {
+        let (weapon, items): (Option<&str>, &[Item]) = match self {
+            Class::Fighter => (Some("sword"), &[Item::Heal]),
+            Class::Rogue => (Some("dagger"), &[Item::Heal, Item::Heal]),
+            Class::Mage => (None, &[Item::Heal, Item::ScrollOfLightning, Item::ScrollOfLightning]),
+        };
+        let mut inventory: Vec<GameObject> = items.iter().map(|&item| new_consumable(0, 0, item)).collect();
+        if let Some(weapon) = weapon.and_then(|name| config.content.weapon(name)) {
+            let mut weapon = new_weapon(0, 0, weapon);
+            if let Some(ref mut equipment) = weapon.equipment {
+                equipment.equipped = true;
+            }
+            inventory.insert(0, weapon);
+        }
+        inventory
+    }
+}
+
+pub(crate) fn apply_class(class: Class, player: &mut GameObject) {
+    let (hp, defense, power) = class.stats();
+    player.fighter = Some(Fighter {
+        max_hp: hp,
+        hp,
+        defense,
+        power,
+        xp: 0,
+        on_death: DeathCallback::Player,
+    });
+    if class == Class::Mage {
+        player.caster = Some(Caster {
+            mana: MAGE_MANA,
+            max_mana: MAGE_MANA,
+        });
+    }
+}
+
+pub fn new_player() -> GameObject {
+    let mut player = GameObject::new(25, 23, '@', WHITE, "player", true);
+    player.description = Name::key("player_description");
+    player.is_alive = true;
+    player.fighter = Some(Fighter {
+        max_hp: 30,
+        hp: 30,
+        defense: 2,
+        power: 5,
+        xp: 0,
+        on_death: DeathCallback::Player,
+    });
+    player
+}
@@ -0,0 +1,80 @@
+use super::*;
+use rand::Rng;
+
+const PERK_CHOICES: usize = 3;
+
+impl Stats {
+    pub fn lines(&self, turn: u32, locale: &Locale) -> Vec<String> {
+        let mut lines = vec![
+            tr!(locale, "stats_score", score = self.score()),
+            tr!(locale, "stats_turns", turns = turn),
+            tr!(locale, "stats_deepest_level", level = self.deepest_level),
+            tr!(locale, "stats_tiles_explored", tiles = self.tiles_explored),
+            tr!(locale, "stats_damage_dealt", damage = self.damage_dealt),
+            tr!(locale, "stats_damage_taken", damage = self.damage_taken),
+            tr!(locale, "stats_items_used", items = self.items_used),
+            tr!(locale, "stats_kills", kills = self.total_kills()),
+        ];
+        for (kind, count) in &self.kills {
+            lines.push(format!("  {}: {}", tr!(locale, kind), count));
+        }
+        lines
+    }
+}
+
+impl Class {
+    pub(crate) const ALL: [Class; 3] = [Class::Fighter, Class::Rogue, Class::Mage];
+
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            Class::Fighter => tr!(locale, "class_fighter"),
+            Class::Rogue => tr!(locale, "class_rogue"),
+            Class::Mage => tr!(locale, "class_mage"),
+        }
+    }
+
+    pub fn description(self, locale: &Locale) -> String {
+        match self {
+            Class::Fighter => tr!(locale, "class_fighter_description"),
+            Class::Rogue => tr!(locale, "class_rogue_description"),
+            Class::Mage => tr!(locale, "class_mage_description"),
+        }
+    }
+
+    /// The perks offered to the class as it levels up
+    fn perks(self) -> &'static [Perk] {
+        match self {
+            Class::Fighter => &[Perk::Tough, Perk::Brawler, Perk::ThickSkin, Perk::Medic, Perk::Quick],
+            Class::Rogue => &[Perk::Quick, Perk::Scavenger, Perk::KeenNose, Perk::Brawler, Perk::ThickSkin],
+            Class::Mage => &[Perk::Medic, Perk::Scavenger, Perk::KeenNose, Perk::Quick, Perk::Tough],
+        }
+    }
+}
+
+/// Draws the perks to choose the next pending one from.
+pub(crate) fn offer_perks(game: &mut Game) -> Vec<Perk> {
+    let mut offered: Vec<Perk> = game.class.perks().iter().copied().filter(|&perk| !game.player.has_perk(perk)).collect();
+    while offered.len() > PERK_CHOICES {
+        offered.remove(game.rng.gen_range(0, offered.len()));
+    }
+    if offered.is_empty() {
+        game.pending_perks = 0;
+    }
+    offered
+}
+
+pub(crate) fn take_perk(perk: Perk, game: &mut Game) {
+    game.pending_perks -= 1;
+    game.changed_outside_step = true;
+    let locale = &game.config.locale;
+    let player = &mut game.player;
+    player.perks.push(perk);
+    if perk == Perk::Tough {
+        if let Some(ref mut fighter) = player.fighter {
+            let bonus = fighter.max_hp / 5;
+            fighter.max_hp += bonus;
+            fighter.hp += bonus;
+        }
+    }
+    game.messages.add(tr!(locale, "perk_taken", perk = perk.name(locale)), MessageCategory::System);
+}
@@ -0,0 +1,92 @@
+use super::*;
+use crate::combat::{experience_level, level_up_xp};
+use crate::ui::{Tcod, menu};
+
+impl Skill {
+    const ALL: [Skill; 4] = [Skill::Melee, Skill::Evasion, Skill::Stealth, Skill::Lore];
+}
+
+pub(crate) fn choose_perk(tcod: &mut Tcod, game: &mut Game) {
+    let offered = offer_perks(game);
+    if offered.is_empty() {
+        return;
+    }
+
+    let locale = &game.config.locale;
+    let options: Vec<String> = offered
+        .iter()
+        .map(|perk| format!("{}: {}", perk.name(locale), perk.description(locale)))
+        .collect();
+    // NOTICE: Closing the window leaves the perk pending, it is offered again when the game is loaded
+    while !tcod.root.window_closed() {
+        if let Some(choice) = menu(&tr!(locale, "choose_perk"), &options, 60, tcod) {
+            take_perk(offered[choice], game);
+            return;
+        }
+    }
+}
+
+pub(crate) fn character_screen(tcod: &mut Tcod, game: &mut Game) {
+    let fighter = match game.player.fighter {
+        Some(fighter) => fighter,
+        None => return,
+    };
+    let locale = &game.config.locale;
+    loop {
+        let perks: Vec<String> = game.player.perks.iter().map(|perk| perk.name(locale)).collect();
+        let (level, xp) = experience_level(fighter.xp);
+        let header = format!(
+            "{}\n\n{}\n{}\n{}\n{}\n\n{}\n",
+            tr!(locale, "character_title", name = game.player_name, class = game.class.name(locale)),
+            tr!(locale, "character_level", level = level, xp = xp, needed = level_up_xp(level)),
+            tr!(locale, "character_stats", hp = fighter.hp, max_hp = fighter.max_hp, power = fighter.power, defense = fighter.defense),
+            tr!(locale, "character_perks", perks = perks.join(", ")),
+            tr!(locale, "skill_points", points = game.skills.points),
+            tr!(locale, "skills"),
+        );
+        let options: Vec<String> = Skill::ALL
+            .iter()
+            .map(|&skill| {
+                let progress = game.skills.progress(skill);
+                if progress.level >= MAX_SKILL_LEVEL {
+                    tr!(locale, "skill_mastered", skill = skill.name(locale), level = progress.level)
+                } else {
+                    tr!(
+                        locale,
+                        "skill_line",
+                        skill = skill.name(locale),
+                        level = progress.level,
+                        practice = progress.practice,
+                        needed = practice_needed(progress.level),
+                    )
+                }
+            })
+            .collect();
+        let skill = match menu(&header, &options, 50, tcod) {
+            Some(index) => Skill::ALL[index],
+            None => return,
+        };
+        if game.skills.points > 0 && game.skills.raise(skill) {
+            game.skills.points -= 1;
+        }
+    }
+}
+
+pub(crate) fn class_menu(tcod: &mut Tcod, locale: &Locale) -> Option<Class> {
+    let options: Vec<String> = Class::ALL
+        .iter()
+        .map(|class| format!("{}: {}", class.name(locale), class.description(locale)))
+        .collect();
+    menu(&tr!(locale, "choose_class"), &options, 60, tcod).map(|index| Class::ALL[index])
+}
+
+pub(crate) fn new_partner() -> GameObject {
+    let mut partner = new_player();
+    partner.name = Name::key("partner");
+    partner.description = Name::key("partner_description");
+    partner.color = LIGHT_BLUE;
+    if let Some(ref mut fighter) = partner.fighter {
+        fighter.on_death = DeathCallback::Partner;
+    }
+    partner
+}
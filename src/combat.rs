@@ -0,0 +1,854 @@
+use std::{cmp, mem};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::audio::Sound;
+use crate::config::{Ability, Config, Name};
+use crate::game::{Game, Message, MessageCategory, with_partner, with_player};
+use crate::items::{Item, carried_weight, carry_capacity, new_component, new_weapon};
+use crate::map::{
+    Decal, Terrain, blocks_movement, leave_decal, move_game_object_by, move_player_by, new_key, set_terrain,
+};
+use crate::monsters::in_line_of_sight;
+use crate::object::{GameObject, Npc};
+use crate::portable::Line;
+use crate::portable::colors::*;
+use crate::ui::warn_low_hp;
+#[cfg(any(feature = "window", feature = "terminal"))]
+use crate::config::Locale;
+
+const HP_HISTORY_LENGTH: usize = 500;
+const BURN_DAMAGE: i32 = 1;
+const POISON_DAMAGE: i32 = 1;
+pub(crate) const COMBAT_NOISE_RADIUS: f32 = 6.0;
+// NOTICE: How far monsters see their kin fall, their own sight rather than the player's torch
+const FRENZY_SIGHT_RADIUS: f32 = 8.0;
+const LEVEL_UP_BASE: i32 = 200;
+const LEVEL_UP_FACTOR: i32 = 150;
+const PERK_LEVEL_INTERVAL: u32 = 3;
+const COMPONENT_DROP_CHANCE: f32 = 0.35;
+const FALL_DAMAGE: i32 = 4;
+const DROWN_DAMAGE: i32 = 2;
+
+// NOTICE: How much of each blow the log reports, every attack message goes through `combat_message`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombatVerbosity {
+    Terse,
+    #[default]
+    Normal,
+    Detailed,
+}
+
+pub(crate) struct Blow<'a> {
+    pub(crate) attacker: &'a str,
+    pub(crate) target: &'a str,
+    pub(crate) power: i32,
+    pub(crate) defense: i32,
+    pub(crate) damage: i32,
+    pub(crate) backstab: bool,
+}
+
+pub(crate) fn combat_message(blow: &Blow, config: &Config) -> String {
+    let locale = &config.locale;
+    let (attacker, target, damage) = (blow.attacker, blow.target, blow.damage);
+    let message = match (config.combat_verbosity, damage > 0) {
+        (CombatVerbosity::Terse, _) => tr!(locale, "attack_terse", attacker = attacker, target = target, damage = damage),
+        (_, true) => tr!(locale, "attack_hit", attacker = attacker, target = target, damage = damage),
+        (_, false) => tr!(locale, "attack_no_effect", attacker = attacker, target = target),
+    };
+    if config.combat_verbosity != CombatVerbosity::Detailed {
+        return message;
+    }
+    let key = if blow.backstab && damage > 0 { "attack_breakdown_backstab" } else { "attack_breakdown" };
+    let breakdown = tr!(locale, key, power = blow.power, defense = blow.defense, damage = damage);
+    format!("{} {}", message, breakdown)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Fighter {
+    pub(crate) max_hp: i32,
+    pub(crate) hp: i32,
+    pub(crate) defense: i32,
+    pub(crate) power: i32,
+    /// The player's experience points, or what a monster is worth when it dies
+    #[serde(default)]
+    pub(crate) xp: i32,
+    pub(crate) on_death: DeathCallback,
+} 
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum DeathCallback {
+    Player,
+    Monster,
+    Partner,
+}
+
+impl DeathCallback {
+    pub(crate) fn callback(self, game_object: &mut GameObject, game: &mut Game) {
+        use DeathCallback::*;
+        let callback: fn(&mut GameObject, &mut Game) = match self {
+            Player => player_death,
+            Monster => monster_death,
+            Partner => partner_death,
+        };
+        callback(game_object, game);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Ai {
+    Basic,
+    Ranged,
+    Caster,
+    Confused {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    },
+    Frozen {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    }
+}
+
+// NOTICE: Invisible monsters are only drawn to a player drinking see invisible or wearing a ring of detection
+pub(crate) fn sees_invisible(game: &Game) -> bool {
+    let potion = game.player
+        .statuses
+        .iter()
+        .any(|status| matches!(status, StatusEffect::SeeInvisible { .. }));
+    let ring = game.inventory.iter().any(|item| {
+        item.item == Some(Item::RingOfDetection) && item.equipment.is_some_and(|equipment| equipment.equipped)
+    });
+    potion || ring
+}
+
+// NOTICE: Lasting effects ticking once per turn
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum StatusEffect {
+    Burning { turns: i32 },
+    Poisoned { turns: i32 },
+    SeeInvisible { turns: i32 },
+    Levitating { turns: i32 },
+}
+
+#[cfg(any(feature = "window", feature = "terminal"))]
+impl StatusEffect {
+    pub fn description(self, locale: &Locale) -> String {
+        match self {
+            StatusEffect::Burning { turns } => tr!(locale, "tooltip_burning", turns = turns),
+            StatusEffect::Poisoned { turns } => tr!(locale, "tooltip_poisoned", turns = turns),
+            StatusEffect::SeeInvisible { turns } => tr!(locale, "tooltip_see_invisible", turns = turns),
+            StatusEffect::Levitating { turns } => tr!(locale, "tooltip_levitating", turns = turns),
+        }
+    }
+
+    /// The short colored tag shown in the panel
+    pub fn tag(self, locale: &Locale) -> (String, Color) {
+        match self {
+            StatusEffect::Burning { .. } => (tr!(locale, "tag_burning"), ORANGE),
+            StatusEffect::Poisoned { .. } => (tr!(locale, "tag_poisoned"), LIGHT_GREEN),
+            StatusEffect::SeeInvisible { .. } => (tr!(locale, "tag_see_invisible"), LIGHT_MAGENTA),
+            StatusEffect::Levitating { .. } => (tr!(locale, "tag_levitating"), LIGHT_CYAN),
+        }
+    }
+}
+
+pub(crate) fn tick_status_effects(game: &mut Game, game_objects: &mut [GameObject]) {
+    if game.player.is_alive {
+        with_player(game, |player, game| tick_statuses(player, true, game));
+    }
+    with_partner(game, |partner, game| tick_statuses(partner, false, game));
+    for game_object in game_objects.iter_mut() {
+        tick_statuses(game_object, false, game);
+    }
+}
+
+fn tick_statuses(game_object: &mut GameObject, is_player: bool, game: &mut Game) {
+    if game_object.statuses.is_empty() {
+        return;
+    }
+    let statuses = std::mem::take(&mut game_object.statuses);
+    let mut remaining = vec![];
+    for status in statuses {
+        match status {
+            StatusEffect::Burning { turns } => {
+                game.messages.add(
+                    tr!(game.config.locale, "burns", target = game_object.display_name(&game.config.locale), damage = BURN_DAMAGE),
+                    MessageCategory::Combat,
+                );
+                if is_player {
+                    game.killer = Some(tr!(game.config.locale, "killer_fire"));
+                }
+                game_object.take_damage(BURN_DAMAGE, game);
+                leave_decal(&mut game.map, game_object.position(), Decal::Scorch);
+                if turns > 1 && game_object.fighter.is_some() {
+                    remaining.push(StatusEffect::Burning { turns: turns - 1 });
+                }
+            }
+            StatusEffect::Levitating { turns } => {
+                if turns > 1 {
+                    remaining.push(StatusEffect::Levitating { turns: turns - 1 });
+                } else if is_player {
+                    game.messages.add(tr!(game.config.locale, "levitation_fades"), MessageCategory::System);
+                }
+            }
+            StatusEffect::SeeInvisible { turns } => {
+                if turns > 1 {
+                    remaining.push(StatusEffect::SeeInvisible { turns: turns - 1 });
+                } else if is_player {
+                    game.messages.add(tr!(game.config.locale, "see_invisible_fades"), MessageCategory::System);
+                }
+            }
+            StatusEffect::Poisoned { turns } => {
+                game.messages.add(
+                    tr!(
+                        game.config.locale,
+                        "poison_damage",
+                        target = game_object.display_name(&game.config.locale),
+                        damage = POISON_DAMAGE
+                    ),
+                    MessageCategory::Combat,
+                );
+                if is_player {
+                    game.killer = Some(tr!(game.config.locale, "killer_poison"));
+                }
+                game_object.take_damage(POISON_DAMAGE, game);
+                if turns > 1 && game_object.fighter.is_some() {
+                    remaining.push(StatusEffect::Poisoned { turns: turns - 1 });
+                }
+            }
+        }
+    }
+    game_object.statuses = remaining;
+}
+
+// NOTICE: Innate monster abilities, triggered on their turn and when a monster dies, uniques drop their loot then
+#[derive(Debug)]
+pub(crate) struct Death {
+    name: Name,
+    /// The unique's or species' locale key, kills are counted by it
+    pub(crate) kind: String,
+    player: bool,
+    xp: i32,
+    unique: bool,
+    species: String,
+    position: (i32, i32),
+    drop: Option<String>,
+    key: bool,
+}
+
+// NOTICE: What the core rules report happened, `dispatch_events` hands it to messages, statistics, audio & AI
+#[derive(Debug)]
+pub(crate) enum GameEvent {
+    EntityDied(Death),
+    DamageDealt { amount: i32, to_player: bool },
+    ItemPickedUp { name: String, weight: i32 },
+    LevelChanged { level: u32 },
+    /// The player reached the character level `level`
+    LevelUp { level: u32 },
+    NoiseEmitted { position: (i32, i32), radius: f32 },
+}
+
+impl GameEvent {
+    pub fn sound(&self) -> Option<Sound> {
+        match self {
+            GameEvent::EntityDied(_) => Some(Sound::Death),
+            GameEvent::DamageDealt { .. } => None,
+            GameEvent::ItemPickedUp { .. } => Some(Sound::PickUp),
+            GameEvent::LevelChanged { .. } => Some(Sound::Stairs),
+            GameEvent::LevelUp { .. } => Some(Sound::LevelUp),
+            GameEvent::NoiseEmitted { .. } => Some(Sound::Hit),
+        }
+    }
+}
+
+pub(crate) fn dispatch_events(game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    // NOTICE: Handling an event can raise more, a kill's experience may bring a level up
+    while !game.events.is_empty() {
+        let events = std::mem::take(&mut game.events);
+        for event in events {
+            log_event(&event, game);
+            record_event(&event, game);
+            game.sounds.extend(event.sound());
+            match event {
+                GameEvent::EntityDied(death) if !death.player => leave_remains(&death, game, game_objects),
+                GameEvent::NoiseEmitted { position, radius } => hear_noise(position, radius, game_objects),
+                _ => {}
+            }
+        }
+    }
+    warn_low_hp(game);
+}
+
+fn log_event(event: &GameEvent, game: &mut Game) {
+    let locale = &game.config.locale;
+    match event {
+        GameEvent::EntityDied(death) if death.player => {
+            game.messages.add(tr!(locale, "player_died"), MessageCategory::Danger);
+        }
+        GameEvent::EntityDied(death) => {
+            let message = if death.unique { "unique_died" } else { "monster_died" };
+            game.messages.add(tr!(locale, message, monster = death.name.translate(locale)), MessageCategory::Combat);
+        }
+        GameEvent::ItemPickedUp { name, weight } => {
+            game.messages.add(tr!(locale, "picked_up", item = name), MessageCategory::Item);
+            let carried = carried_weight(&game.inventory, &game.config);
+            let capacity = carry_capacity(&game.player);
+            if carried > capacity && carried - weight <= capacity {
+                game.messages.add(tr!(locale, "encumbered"), MessageCategory::Danger);
+            }
+        }
+        GameEvent::LevelUp { level } => {
+            game.messages.add(tr!(locale, "level_up", level = level), MessageCategory::System);
+            game.messages.add(tr!(locale, "skill_point_gained"), MessageCategory::System);
+        }
+        GameEvent::DamageDealt { .. } | GameEvent::LevelChanged { .. } | GameEvent::NoiseEmitted { .. } => {}
+    }
+}
+
+fn record_event(event: &GameEvent, game: &mut Game) {
+    match *event {
+        GameEvent::EntityDied(ref death) if !death.player => {
+            *game.stats.kills.entry(death.kind.clone()).or_insert(0) += 1;
+            gain_xp(death.xp, game);
+        }
+        GameEvent::DamageDealt { amount, to_player: true } => game.stats.damage_taken += amount,
+        GameEvent::DamageDealt { amount, to_player: false } => game.stats.damage_dealt += amount,
+        GameEvent::LevelChanged { level } => game.stats.deepest_level = cmp::max(game.stats.deepest_level, level),
+        GameEvent::LevelUp { level } => {
+            game.skills.points += 1;
+            if (level - 1).is_multiple_of(PERK_LEVEL_INTERVAL) {
+                game.pending_perks += 1;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Experience points needed to go from `level` to the next character level
+pub(crate) fn level_up_xp(level: u32) -> i32 {
+    LEVEL_UP_BASE + level as i32 * LEVEL_UP_FACTOR
+}
+
+/// The character level `xp` experience points reach, and the points earned towards the next one
+pub(crate) fn experience_level(xp: i32) -> (u32, i32) {
+    let (mut level, mut left) = (1, xp);
+    while left >= level_up_xp(level) {
+        left -= level_up_xp(level);
+        level += 1;
+    }
+    (level, left)
+}
+
+fn gain_xp(amount: i32, game: &mut Game) {
+    if let Some(fighter) = game.player.fighter.as_mut() {
+        let (before, _) = experience_level(fighter.xp);
+        fighter.xp += amount;
+        let (after, _) = experience_level(fighter.xp);
+        game.events.extend((before + 1..=after).map(|level| GameEvent::LevelUp { level }));
+    }
+}
+
+fn leave_remains(death: &Death, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let drop = death.drop.as_ref().and_then(|name| game.config.content.weapon(name));
+    if let Some(weapon) = drop {
+        let (x, y) = death.position;
+        game_objects.push(new_weapon(x, y, weapon));
+    }
+    let components = game.config.content.components(&death.species);
+    if !components.is_empty() && game.rng.gen::<f32>() < COMPONENT_DROP_CHANCE {
+        let component = components[game.rng.gen_range(0, components.len())];
+        let (x, y) = death.position;
+        game_objects.push(new_component(x, y, component));
+    }
+    if death.key {
+        let (x, y) = death.position;
+        game_objects.push(new_key(x, y));
+        game.messages.add(tr!(game.config.locale, "key_dropped"), MessageCategory::Item);
+    }
+
+    for game_object in game_objects.iter_mut() {
+        if game_object.ai.is_none() || game_object.species != death.species {
+            continue;
+        }
+        let position = game_object.position();
+        let in_view = game_object.distance(death.position.0, death.position.1) <= FRENZY_SIGHT_RADIUS
+            && in_line_of_sight(position, death.position, &game.map);
+        if !in_view {
+            continue;
+        }
+        for ability in game_object.abilities(&game.config.content) {
+            if let Ability::Frenzy { power } = ability {
+                if let Some(fighter) = game_object.fighter.as_mut() {
+                    fighter.power += power;
+                }
+                if game.fov.is_in_fov(position.0, position.1) {
+                    game.messages.add(
+                        tr!(game.config.locale, "frenzy", monster = game_object.display_name(&game.config.locale)),
+                        MessageCategory::Danger,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Monsters in earshot that haven't spotted the player yet come to check the noise out
+fn hear_noise(position: (i32, i32), radius: f32, game_objects: &mut [GameObject]) {
+    for game_object in game_objects.iter_mut().filter(|o| o.ai.is_some() && o.last_seen_player.is_none()) {
+        if game_object.distance(position.0, position.1) <= radius {
+            game_object.heard_noise = Some(position);
+        }
+    }
+}
+
+pub(crate) fn terrain_damage(game: &mut Game, game_objects: &mut [GameObject]) {
+    with_player(game, terrain_damage_to);
+    with_partner(game, terrain_damage_to);
+    for game_object in game_objects.iter_mut() {
+        terrain_damage_to(game_object, game);
+    }
+}
+
+fn terrain_damage_to(game_object: &mut GameObject, game: &mut Game) {
+    if game_object.fighter.is_none() {
+        return;
+    }
+    let terrain = game.map[(game_object.x, game_object.y)].terrain;
+    if game_object.movement().can_cross(terrain) {
+        return;
+    }
+    let (key, damage) = match terrain {
+        Terrain::Chasm => ("falling", FALL_DAMAGE),
+        Terrain::DeepWater => ("drowning", DROWN_DAMAGE),
+        _ => return,
+    };
+    game.messages.add(
+        tr!(game.config.locale, key, target = game_object.display_name(&game.config.locale), damage = damage),
+        MessageCategory::Danger,
+    );
+    if game_object.fighter.is_some_and(|fighter| fighter.on_death == DeathCallback::Player) {
+        game.killer = Some(tr!(game.config.locale, &format!("killer_{}", key)));
+    }
+    game_object.take_damage(damage, game);
+}
+
+// NOTICE: Ranged attacks, shared by thrown items and monster projectiles
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum DamageType {
+    Physical,
+    Acid,
+    Fire,
+}
+
+impl DamageType {
+    pub fn name_key(self) -> &'static str {
+        match self {
+            DamageType::Physical => "damage_physical",
+            DamageType::Acid => "damage_acid",
+            DamageType::Fire => "damage_fire",
+        }
+    }
+
+    pub fn projectile_key(self) -> &'static str {
+        match self {
+            DamageType::Physical => "arrow",
+            DamageType::Acid => "acid_spit",
+            DamageType::Fire => "firebolt",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            DamageType::Physical => LIGHT_GREY,
+            DamageType::Acid => LIME,
+            DamageType::Fire => FLAME,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RangedAttack {
+    pub(crate) range: i32,
+    pub(crate) damage: i32,
+    pub(crate) damage_type: DamageType,
+}
+
+// NOTICE: Only the window animates projectiles, the other front ends drop them after each turn
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "window"), allow(dead_code))]
+pub(crate) struct Projectile {
+    pub(crate) path: Vec<(i32, i32)>,
+    pub(crate) glyph: char,
+    pub(crate) color: Color,
+}
+
+/// The player, the co-op partner or an index in the object list, for who acts, gets attacked or got hit by a projectile
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Target {
+    Player,
+    Partner,
+    Object(usize),
+}
+
+impl Target {
+    pub(crate) fn game_object<'a>(self, game: &'a Game, game_objects: &'a [GameObject]) -> &'a GameObject {
+        match self {
+            Target::Player => &game.player,
+            Target::Partner => game.partner.as_ref().expect("targeted a partner outside of a co-op game"),
+            Target::Object(id) => &game_objects[id],
+        }
+    }
+}
+
+/// Flies from `from` towards `to`, stopping before walls and on the first fighter in the way
+pub(crate) fn fire_projectile(
+    from: (i32, i32),
+    to: (i32, i32),
+    glyph: char,
+    color: Color,
+    game: &mut Game,
+    game_objects: &[GameObject],
+) -> ((i32, i32), Option<Target>) {
+    let mut path = vec![];
+    let mut hit = None;
+    for (x, y) in Line::new(from, to) {
+        if game.map[(x, y)].blocked {
+            break;
+        }
+        path.push((x, y));
+        hit = if game.player.position() == (x, y) && game.player.fighter.is_some() {
+            Some(Target::Player)
+        } else if game.partner.as_ref().is_some_and(|partner| partner.position() == (x, y) && partner.fighter.is_some()) {
+            Some(Target::Partner)
+        } else {
+            game_objects
+                .iter()
+                .position(|game_object| game_object.position() == (x, y) && game_object.fighter.is_some())
+                .map(Target::Object)
+        };
+        if hit.is_some() || (x, y) == to {
+            break;
+        }
+    }
+    let landing = path.last().cloned().unwrap_or(from);
+    game.projectiles.push(Projectile { path, glyph, color });
+    (landing, hit)
+}
+
+/// The cells a projectile thrown from `from` at `to` crosses, with the index of the first wall or visible fighter in its way
+#[cfg(feature = "window")]
+pub(crate) fn line_of_fire(from: (i32, i32), to: (i32, i32), game: &Game, game_objects: &[GameObject]) -> (Vec<(i32, i32)>, Option<usize>) {
+    let see_invisible = sees_invisible(game);
+    let path: Vec<(i32, i32)> = Line::new(from, to).collect();
+    let obstruction = path.iter().position(|&(x, y)| {
+        let fighter_in_way = (x, y) != to
+            && (game.partner.as_ref().is_some_and(|partner| partner.position() == (x, y) && partner.fighter.is_some())
+                || game_objects.iter().any(|game_object| {
+                    game_object.position() == (x, y)
+                        && game_object.fighter.is_some()
+                        && (!game_object.invisible || see_invisible)
+                }));
+        game.map[(x, y)].blocked || fighter_in_way
+    });
+    (path, obstruction)
+}
+
+pub(crate) fn ranged_hit(
+    target: Target,
+    projectile: &str,
+    damage: i32,
+    damage_type: DamageType,
+    game: &mut Game,
+    game_objects: &mut [GameObject],
+) {
+    let category = if target == Target::Player { MessageCategory::Danger } else { MessageCategory::Combat };
+    game.messages.add(
+        tr!(
+            game.config.locale,
+            "projectile_hit",
+            projectile = projectile,
+            target = target.game_object(game, game_objects).display_name(&game.config.locale),
+            damage = damage,
+            damage_type = tr!(game.config.locale, damage_type.name_key()),
+        ),
+        category,
+    );
+    let position = target.game_object(game, game_objects).position();
+    game.events.push(GameEvent::NoiseEmitted { position, radius: COMBAT_NOISE_RADIUS });
+    match target {
+        Target::Player => {
+            game.killer = Some(projectile.to_string());
+            with_player(game, |player, game| player.take_damage(damage, game));
+        }
+        Target::Partner => {
+            with_partner(game, |partner, game| partner.take_damage(damage, game));
+        }
+        Target::Object(id) => game_objects[id].take_damage(damage, game),
+    }
+}
+
+pub(crate) fn player_move_or_attack(dx: i32, dy: i32, game: &mut Game, game_objects: &mut [GameObject]) {
+    let x = game.player.x + dx;
+    let y = game.player.y + dy;
+
+    let target_id = game_objects
+        .iter()
+        .position(|game_object| game_object.fighter.is_some() && game_object.position() == (x, y));
+    let npc_id = game_objects
+        .iter()
+        .position(|game_object| game_object.npc.is_some() && game_object.position() == (x, y));
+    if let Some(npc_id) = npc_id {
+        visit_npc(npc_id, game, game_objects);
+        return;
+    }
+
+    let terrain = game.map.get(x, y).map(|tile| tile.terrain);
+    match target_id {
+        Some(target_id) => melee(Target::Player, Target::Object(target_id), game, game_objects),
+        None if terrain == Some(Terrain::LockedGate) => {
+            let key = game.inventory.iter().position(|item| item.item == Some(Item::Key));
+            match key {
+                Some(key) => {
+                    game.inventory.remove(key);
+                    set_terrain(x, y, Terrain::UnlockedGate, game);
+                    game.messages.add(tr!(game.config.locale, "gate_unlocked"), MessageCategory::Item);
+                }
+                None => game.messages.add(tr!(game.config.locale, "gate_locked"), MessageCategory::System),
+            }
+        }
+        None if terrain == Some(Terrain::GlassWall) => {
+            set_terrain(x, y, Terrain::Rubble, game);
+            game.messages.add(tr!(game.config.locale, "glass_shattered"), MessageCategory::Danger);
+        }
+        None if terrain == Some(Terrain::ClosedDoor) => {
+            set_terrain(x, y, Terrain::OpenDoor, game);
+            game.messages.add(tr!(game.config.locale, "door_opened_by_player"), MessageCategory::System);
+        }
+        None => move_player_by(dx, dy, game, game_objects),
+    }
+}
+
+/// Bumping for the partner, which opens doors but leaves gates, NPCs & stairs to the player
+pub(crate) fn partner_move_or_attack(dx: i32, dy: i32, game: &mut Game, game_objects: &mut [GameObject]) {
+    let (x, y, movement) = match game.partner {
+        Some(ref partner) => (partner.x + dx, partner.y + dy, partner.movement()),
+        None => return,
+    };
+    let target_id = game_objects
+        .iter()
+        .position(|game_object| game_object.fighter.is_some() && game_object.position() == (x, y));
+    match target_id {
+        Some(target_id) => melee(Target::Partner, Target::Object(target_id), game, game_objects),
+        None if game.map.get(x, y).is_some_and(|tile| tile.terrain == Terrain::ClosedDoor) => set_terrain(x, y, Terrain::OpenDoor, game),
+        None if !blocks_movement(x, y, movement, game, game_objects) => {
+            if let Some(ref mut partner) = game.partner {
+                partner.set_position(x, y);
+            }
+        }
+        None => {}
+    }
+}
+
+/// Monsters only ever fight the heroes, and the player's knockback weapons push them back
+pub(crate) fn melee(attacker: Target, defender: Target, game: &mut Game, game_objects: &mut [GameObject]) {
+    let knockback = match (attacker, defender) {
+        (Target::Player, Target::Object(id)) => with_player(game, |player, game| player.attack(&mut game_objects[id], game)),
+        (Target::Object(id), Target::Player) => with_player(game, |player, game| game_objects[id].attack(player, game)),
+        (Target::Partner, Target::Object(id)) => with_partner(game, |partner, game| partner.attack(&mut game_objects[id], game)).flatten(),
+        (Target::Object(id), Target::Partner) => with_partner(game, |partner, game| game_objects[id].attack(partner, game)).flatten(),
+        _ => None,
+    };
+    if let (Some((dx, dy)), Target::Object(id)) = (knockback, defender) {
+        let position = game_objects[id].position();
+        move_game_object_by(id, dx, dy, game, game_objects);
+        if game_objects[id].position() != position {
+            game.messages.add(
+                tr!(game.config.locale, "knocked_back", target = game_objects[id].display_name(&game.config.locale)),
+                MessageCategory::Combat,
+            );
+        }
+    }
+}
+
+fn visit_npc(npc_id: usize, game: &mut Game, game_objects: &mut [GameObject]) {
+    match game_objects[npc_id].npc {
+        Some(Npc::Blacksmith { served: true }) => {
+            game.messages.add(tr!(game.config.locale, "blacksmith_done"), MessageCategory::System);
+        }
+        Some(Npc::Blacksmith { served: false }) => {
+            let mut repaired = 0;
+            for equipment in game.inventory.iter_mut().filter_map(|item| item.equipment.as_mut()) {
+                if equipment.durability < equipment.max_durability {
+                    equipment.durability = equipment.max_durability;
+                    repaired += 1;
+                }
+            }
+            if repaired == 0 {
+                game.messages.add(tr!(game.config.locale, "blacksmith_idle"), MessageCategory::System);
+            } else {
+                game_objects[npc_id].npc = Some(Npc::Blacksmith { served: true });
+                game.messages.add(tr!(game.config.locale, "blacksmith_repaired", count = repaired), MessageCategory::Item);
+            }
+        }
+        None => {}
+    }
+}
+
+pub(crate) fn record_hp(game: &mut Game, hp: i32) {
+    game.hp_history.push(cmp::max(hp, 0));
+    if game.hp_history.len() > HP_HISTORY_LENGTH {
+        game.hp_history.remove(0);
+    }
+}
+
+fn player_death(player: &mut GameObject, game: &mut Game) {
+    record_hp(game, 0);
+    game.final_blow = game.messages.iter().next_back().map(Message::text);
+    game.events.push(GameEvent::EntityDied(Death {
+        name: player.name.clone(),
+        kind: player.species.clone(),
+        player: true,
+        xp: 0,
+        unique: false,
+        species: player.species.clone(),
+        position: player.position(),
+        drop: None,
+        key: false,
+    }));
+
+    player.char = '%';
+    player.color = DARK_RED;
+}
+
+// NOTICE: The partner's death ends neither the game nor counts as a kill, its client just watches from then on
+fn partner_death(partner: &mut GameObject, game: &mut Game) {
+    game.messages.add(
+        tr!(game.config.locale, "partner_died", partner = partner.display_name(&game.config.locale)),
+        MessageCategory::Danger,
+    );
+    partner.char = '%';
+    partner.color = DARK_RED;
+    partner.blocks = false;
+}
+
+fn monster_death(monster: &mut GameObject, game: &mut Game) {
+    game.events.push(GameEvent::EntityDied(Death {
+        name: monster.name.clone(),
+        kind: match monster.name {
+            Name::Key { ref key, .. } if monster.unique.is_some() => key.clone(),
+            _ => monster.species.clone(),
+        },
+        player: false,
+        xp: monster.fighter.map_or(0, |fighter| fighter.xp),
+        unique: monster.unique.is_some(),
+        species: monster.species.clone(),
+        position: monster.position(),
+        drop: monster.unique.as_ref().map(|unique| unique.drop.clone()),
+        key: monster.carries_key,
+    }));
+    monster.char = '%';
+    monster.color = DARK_RED;
+    monster.blocks = false;
+    monster.fighter = None;
+    monster.ai = None;
+    monster.name = Name::key("remains").with("monster", mem::take(&mut monster.name));
+    monster.description = Name::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::open_room;
+
+    #[test]
+    fn blows_deal_power_minus_defense() {
+        let (mut game, mut game_objects) = open_room((3, 2));
+        game_objects[0].fighter = Some(Fighter { max_hp: 10, hp: 10, defense: 1, power: 4, xp: 0, on_death: DeathCallback::Monster });
+        if let Some(ref mut fighter) = game.player.fighter {
+            fighter.defense = 1;
+        }
+        let hp = game.player.fighter.unwrap().hp;
+
+        with_player(&mut game, |player, game| game_objects[0].attack(player, game));
+        assert_eq!(game.player.fighter.unwrap().hp, hp - 3);
+    }
+
+    #[test]
+    fn monsters_die_at_zero_hp() {
+        let (mut game, mut game_objects) = open_room((3, 2));
+        let hp = game_objects[0].fighter.unwrap().hp;
+
+        game_objects[0].take_damage(hp - 1, &mut game);
+        assert!(game_objects[0].is_alive);
+        game_objects[0].take_damage(1, &mut game);
+        assert!(!game_objects[0].is_alive);
+        assert!(game_objects[0].fighter.is_none() && game_objects[0].ai.is_none());
+        assert!(matches!(game.events.last(), Some(GameEvent::EntityDied(death)) if death.kind == "orc"));
+    }
+
+    #[test]
+    fn the_player_dies_once_to_two_statuses() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        if let Some(ref mut fighter) = game.player.fighter {
+            fighter.hp = 1;
+        }
+        game.player.statuses = vec![StatusEffect::Burning { turns: 3 }, StatusEffect::Poisoned { turns: 3 }];
+
+        tick_status_effects(&mut game, &mut game_objects);
+        tick_status_effects(&mut game, &mut game_objects);
+        assert!(!game.player.is_alive);
+        let deaths = game.events.iter().filter(|event| matches!(event, GameEvent::EntityDied(death) if death.player)).count();
+        assert_eq!(deaths, 1);
+    }
+
+    /// Experience points needed to go from level 1 to `level`
+    fn xp_for(level: u32) -> i32 {
+        (1..level).map(level_up_xp).sum()
+    }
+
+    #[test]
+    fn levels_start_at_their_threshold() {
+        assert_eq!(experience_level(0), (1, 0));
+        assert_eq!(experience_level(level_up_xp(1) - 1), (1, level_up_xp(1) - 1));
+        assert_eq!(experience_level(level_up_xp(1)), (2, 0));
+        assert_eq!(experience_level(xp_for(4) + 5), (4, 5));
+        assert!(level_up_xp(2) > level_up_xp(1));
+    }
+
+    #[test]
+    fn one_award_can_bring_several_levels() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        game.player.fighter.as_mut().unwrap().xp = 0;
+        let points = game.skills.points;
+
+        gain_xp(xp_for(3) + 1, &mut game);
+        let level_ups: Vec<_> = game
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::LevelUp { level } => Some(*level),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(level_ups, vec![2, 3]);
+        dispatch_events(&mut game, &mut game_objects);
+        assert_eq!(game.skills.points, points + 2);
+    }
+
+    #[test]
+    fn perks_come_every_few_levels() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        game.player.fighter.as_mut().unwrap().xp = 0;
+
+        gain_xp(xp_for(PERK_LEVEL_INTERVAL + 1) - 1, &mut game);
+        dispatch_events(&mut game, &mut game_objects);
+        assert_eq!(game.pending_perks, 0);
+        gain_xp(1, &mut game);
+        dispatch_events(&mut game, &mut game_objects);
+        assert_eq!(game.pending_perks, 1);
+        gain_xp(xp_for(2 * PERK_LEVEL_INTERVAL + 1) - xp_for(PERK_LEVEL_INTERVAL + 1), &mut game);
+        dispatch_events(&mut game, &mut game_objects);
+        assert_eq!(game.pending_perks, 2);
+    }
+}
@@ -0,0 +1,625 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::combat::CombatVerbosity;
+use crate::controls::{MovementKeys, SafetyPrompt};
+use crate::items::FULL_SATIETY;
+use crate::map::{MAP_HEIGHT, MAP_WIDTH, Movement, ROOM_MAX_SIZE};
+use crate::portable::colors::*;
+use crate::ui::PaletteKind;
+
+/// Reloading the content files while the window runs, and rarity names for its screens
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+// NOTICE: Player-tweakable settings, every field falls back on these defaults
+const LIMIT_FPS: i32 = 24;
+const MAX_ROOM_MONSTERS: i32 = 3;
+const MAX_ROOM_ITEMS: i32 = 3;
+const KEY_REPEAT_DELAY_MS: u64 = 250;
+const KEY_REPEAT_RATE: u32 = 12;
+const HEAL_AMOUNT: i32 = 4;
+const LIGHTNING_DAMAGE: i32 = 40;
+const POTION_WEIGHT: i32 = 2;
+const ITEM_WEIGHT: i32 = 1;
+const WEAPON_DURABILITY: i32 = 60;
+const TORCH_RADIUS: i32 = 10;
+const AUTOSAVE_INTERVAL: u32 = 50;
+
+pub(crate) const CONFIG_FILE: &str = "config.toml";
+const LANG_DIR: &str = "assets/lang";
+const DEFAULT_LANGUAGE: &str = "en";
+const DEFAULT_STRINGS: &str = include_str!("../assets/lang/en.toml");
+const CONTENT_DIR: &str = "assets/content";
+const DEFAULT_WEAPONS: &str = include_str!("../assets/content/weapons.toml");
+const DEFAULT_MONSTERS: &str = include_str!("../assets/content/monsters.toml");
+const DEFAULT_UNIQUES: &str = include_str!("../assets/content/uniques.toml");
+const DEFAULT_AFFIXES: &str = include_str!("../assets/content/affixes.toml");
+const DEFAULT_RECIPES: &str = include_str!("../assets/content/recipes.toml");
+const DEFAULT_ARMOR: &str = include_str!("../assets/content/armor.toml");
+const DEFAULT_VOLUME: f32 = 0.7;
+const DEFAULT_MUSIC_VOLUME: f32 = 0.5;
+pub(crate) const DEFAULT_FONT: &str = "arial10x10";
+
+#[derive(Clone, Debug)]
+pub struct Locale {
+    strings: HashMap<String, String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            strings: toml::from_str(DEFAULT_STRINGS).expect("Built-in english strings are invalid"),
+        }
+    }
+}
+
+impl Locale {
+    pub fn load(language: &str) -> Result<Self, Box<dyn Error>> {
+        let mut locale = Locale::default();
+        let contents = fs::read_to_string(Path::new(LANG_DIR).join(format!("{}.toml", language)))?;
+        let strings: HashMap<String, String> = toml::from_str(&contents)?;
+        locale.strings.extend(strings);
+        Ok(locale)
+    }
+
+    pub fn get(&self, key: &str, arguments: &[(&str, String)]) -> String {
+        let mut text = self.strings.get(key).cloned().unwrap_or_else(|| key.to_string());
+        for (name, value) in arguments {
+            // NOTICE: `{de name}` puts French's "de" before the value, elided before a vowel: "restes d'orque"
+            let elided = if value.starts_with(|c: char| "aeiouyàâéèêîïôAEIOUYÀÂÉÈÊÎÏÔ".contains(c)) {
+                format!("d'{}", value)
+            } else {
+                format!("de {}", value)
+            };
+            text = text.replace(&format!("{{de {}}}", name), &elided);
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
+
+// NOTICE: Names & descriptions keep their locale keys, they are translated in the language active when shown
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Name {
+    Key { key: String, arguments: Vec<(String, Name)> },
+    /// Shown as is, like numbers & generated artifact names
+    Text(String),
+    /// Joined with commas
+    List(Vec<Name>),
+}
+
+impl Default for Name {
+    fn default() -> Self {
+        Name::Text(String::new())
+    }
+}
+
+impl Name {
+    pub fn key(key: &str) -> Self {
+        Name::Key { key: key.into(), arguments: vec![] }
+    }
+
+    /// Fills the `{argument}` placeholder of a key's text with `value`
+    pub fn with(mut self, argument: &str, value: Name) -> Self {
+        if let Name::Key { ref mut arguments, .. } = self {
+            arguments.push((argument.into(), value));
+        }
+        self
+    }
+
+    pub fn translate(&self, locale: &Locale) -> String {
+        match self {
+            Name::Key { key, arguments } => {
+                let arguments: Vec<(&str, String)> =
+                    arguments.iter().map(|(name, value)| (name.as_str(), value.translate(locale))).collect();
+                locale.get(key, &arguments)
+            }
+            Name::Text(text) => text.clone(),
+            Name::List(names) => names.iter().map(|name| name.translate(locale)).collect::<Vec<_>>().join(", "),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnHit {
+    Flaming,
+    Vampiric,
+    Knockback,
+}
+
+// NOTICE: The rarer the tier the less often an item drops, artifacts are only ever rolled from other items
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Artifact,
+}
+
+impl Rarity {
+    pub fn weight(self) -> u32 {
+        match self {
+            Rarity::Common => 60,
+            Rarity::Uncommon => 25,
+            Rarity::Rare => 10,
+            Rarity::Artifact => 0,
+        }
+    }
+
+    /// Common items keep the color of their definition
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Rarity::Common => None,
+            Rarity::Uncommon => Some(LIGHT_GREEN),
+            Rarity::Rare => Some(LIGHT_BLUE),
+            Rarity::Artifact => Some(ORANGE),
+        }
+    }
+}
+
+pub(crate) fn pick_by_rarity<R: Rng>(rarities: &[Rarity], rng: &mut R) -> Option<usize> {
+    let total_weight: u32 = rarities.iter().map(|rarity| rarity.weight()).sum();
+    if total_weight == 0 {
+        return None;
+    }
+    let mut roll = rng.gen_range(0, total_weight);
+    rarities.iter().position(|rarity| {
+        if roll < rarity.weight() {
+            true
+        } else {
+            roll -= rarity.weight();
+            false
+        }
+    })
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WeaponDefinition {
+    pub(crate) name: String,
+    pub(crate) glyph: char,
+    pub(crate) color: Color,
+    pub(crate) power_bonus: i32,
+    pub(crate) weight: i32,
+    #[serde(default)]
+    pub(crate) on_hit: Option<OnHit>,
+    #[serde(default = "default_durability")]
+    pub(crate) durability: i32,
+    #[serde(default)]
+    pub(crate) rarity: Rarity,
+}
+
+fn default_durability() -> i32 {
+    WEAPON_DURABILITY
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ArmorDefinition {
+    pub(crate) name: String,
+    pub(crate) glyph: char,
+    pub(crate) color: Color,
+    pub(crate) defense_bonus: i32,
+    pub(crate) weight: i32,
+    #[serde(default = "default_durability")]
+    pub(crate) durability: i32,
+    #[serde(default)]
+    pub(crate) rarity: Rarity,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Ability {
+    Regeneration { amount: i32 },
+    Frenzy { power: i32 },
+    Venomous { turns: i32 },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MonsterDefinition {
+    species: String,
+    #[serde(default)]
+    abilities: Vec<Ability>,
+    #[serde(default)]
+    movement: Movement,
+    #[serde(default)]
+    doors: DoorHandling,
+    #[serde(default)]
+    components: Vec<Component>,
+}
+
+/// What a monster does when a closed door is in its way
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DoorHandling {
+    #[default]
+    Stopped,
+    Open,
+    Smash,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    Herb,
+    Bone,
+    Shard,
+}
+
+impl Component {
+    pub fn key(self) -> &'static str {
+        match self {
+            Component::Herb => "herb",
+            Component::Bone => "bone",
+            Component::Shard => "shard",
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        tr!(locale, self.key())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CraftResult {
+    HealingPotion,
+    PotionOfSeeInvisible,
+    PotionOfLevitation,
+    Darts,
+    Sharpen,
+}
+
+impl CraftResult {
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            CraftResult::HealingPotion => tr!(locale, "healing_potion"),
+            CraftResult::PotionOfSeeInvisible => tr!(locale, "potion_of_see_invisible"),
+            CraftResult::PotionOfLevitation => tr!(locale, "potion_of_levitation"),
+            CraftResult::Darts => tr!(locale, "darts"),
+            CraftResult::Sharpen => tr!(locale, "sharpen"),
+        }
+    }
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+pub(crate) fn full_satiety() -> i32 {
+    FULL_SATIETY
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Ingredient {
+    pub(crate) component: Component,
+    pub(crate) count: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RecipeDefinition {
+    pub(crate) result: CraftResult,
+    #[serde(default = "default_count")]
+    pub(crate) count: u32,
+    pub(crate) ingredients: Vec<Ingredient>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UniqueDefinition {
+    pub(crate) name: String,
+    pub(crate) species: String,
+    pub(crate) glyph: char,
+    pub(crate) color: Color,
+    pub(crate) max_hp: i32,
+    pub(crate) defense: i32,
+    pub(crate) power: i32,
+    pub(crate) min_level: u32,
+    pub(crate) drop: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct LevelWeight {
+    level: u32,
+    weight: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AffixDefinition {
+    pub(crate) name: String,
+    pub(crate) species: Vec<String>,
+    pub(crate) max_hp_bonus: i32,
+    pub(crate) power_bonus: i32,
+    pub(crate) defense_bonus: i32,
+    #[serde(default)]
+    pub(crate) ability: Option<Ability>,
+    weights: Vec<LevelWeight>,
+}
+
+impl AffixDefinition {
+    pub fn weight(&self, level: u32) -> u32 {
+        self.weights
+            .iter()
+            .rev()
+            .find(|weight| weight.level <= level)
+            .map_or(0, |weight| weight.weight)
+    }
+}
+
+#[derive(Deserialize)]
+struct WeaponsFile {
+    weapons: Vec<WeaponDefinition>,
+}
+
+#[derive(Deserialize)]
+struct MonstersFile {
+    monsters: Vec<MonsterDefinition>,
+}
+
+#[derive(Deserialize)]
+struct UniquesFile {
+    uniques: Vec<UniqueDefinition>,
+}
+
+#[derive(Deserialize)]
+struct AffixesFile {
+    affixes: Vec<AffixDefinition>,
+}
+
+#[derive(Deserialize)]
+struct ArmorFile {
+    armor: Vec<ArmorDefinition>,
+}
+
+#[derive(Deserialize)]
+struct RecipesFile {
+    recipes: Vec<RecipeDefinition>,
+}
+
+// NOTICE: Game content definitions, the files in assets/content override the built-in ones
+#[derive(Clone, Debug)]
+pub struct Content {
+    pub(crate) weapons: Vec<WeaponDefinition>,
+    monsters: Vec<MonsterDefinition>,
+    pub(crate) uniques: Vec<UniqueDefinition>,
+    pub(crate) affixes: Vec<AffixDefinition>,
+    pub(crate) recipes: Vec<RecipeDefinition>,
+    pub(crate) armor: Vec<ArmorDefinition>,
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Content::parse(DEFAULT_WEAPONS, DEFAULT_MONSTERS, DEFAULT_UNIQUES, DEFAULT_AFFIXES, DEFAULT_RECIPES, DEFAULT_ARMOR)
+            .expect("Built-in content is invalid")
+    }
+}
+
+impl Content {
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let weapons = fs::read_to_string(Path::new(CONTENT_DIR).join("weapons.toml"))?;
+        let monsters = fs::read_to_string(Path::new(CONTENT_DIR).join("monsters.toml"))?;
+        let uniques = fs::read_to_string(Path::new(CONTENT_DIR).join("uniques.toml"))?;
+        let affixes = fs::read_to_string(Path::new(CONTENT_DIR).join("affixes.toml"))?;
+        let recipes = fs::read_to_string(Path::new(CONTENT_DIR).join("recipes.toml"))?;
+        let armor = fs::read_to_string(Path::new(CONTENT_DIR).join("armor.toml"))?;
+        Ok(Content::parse(&weapons, &monsters, &uniques, &affixes, &recipes, &armor)?)
+    }
+
+    fn parse(weapons: &str, monsters: &str, uniques: &str, affixes: &str, recipes: &str, armor: &str) -> Result<Self, toml::de::Error> {
+        let weapons: WeaponsFile = toml::from_str(weapons)?;
+        let monsters: MonstersFile = toml::from_str(monsters)?;
+        let uniques: UniquesFile = toml::from_str(uniques)?;
+        let affixes: AffixesFile = toml::from_str(affixes)?;
+        let recipes: RecipesFile = toml::from_str(recipes)?;
+        let armor: ArmorFile = toml::from_str(armor)?;
+        Ok(Content {
+            weapons: weapons.weapons,
+            monsters: monsters.monsters,
+            uniques: uniques.uniques,
+            affixes: affixes.affixes,
+            recipes: recipes.recipes,
+            armor: armor.armor,
+        })
+    }
+
+    pub fn weapon(&self, name: &str) -> Option<&WeaponDefinition> {
+        self.weapons.iter().find(|weapon| weapon.name == name)
+    }
+
+    pub fn movement(&self, species: &str) -> Movement {
+        self.monsters
+            .iter()
+            .find(|monster| monster.species == species)
+            .map_or_else(Movement::default, |monster| monster.movement)
+    }
+
+    pub fn door_handling(&self, species: &str) -> DoorHandling {
+        self.monsters
+            .iter()
+            .find(|monster| monster.species == species)
+            .map_or(DoorHandling::Stopped, |monster| monster.doors)
+    }
+
+    pub fn abilities(&self, species: &str) -> &[Ability] {
+        self.monsters
+            .iter()
+            .find(|monster| monster.species == species)
+            .map_or(&[], |monster| &monster.abilities)
+    }
+
+    pub fn components(&self, species: &str) -> &[Component] {
+        self.monsters
+            .iter()
+            .find(|monster| monster.species == species)
+            .map_or(&[], |monster| &monster.components)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// Switches the display to the resolution closest to the window's size
+    Fullscreen,
+    /// Covers the screen at the desktop's own resolution, without changing display modes
+    Borderless,
+}
+
+impl WindowMode {
+    pub fn next(self) -> Self {
+        match self {
+            WindowMode::Windowed => WindowMode::Fullscreen,
+            WindowMode::Fullscreen => WindowMode::Borderless,
+            WindowMode::Borderless => WindowMode::Windowed,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            WindowMode::Windowed => tr!(locale, "window_mode_windowed"),
+            WindowMode::Fullscreen => tr!(locale, "window_mode_fullscreen"),
+            WindowMode::Borderless => tr!(locale, "window_mode_borderless"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub fps_limit: i32,
+    /// Lets the display pace the frames instead of `fps_limit`
+    pub vsync: bool,
+    pub movement_keys: MovementKeys,
+    /// How long a movement key is held before it repeats, and how many times a second it then does
+    pub key_repeat_delay_ms: u64,
+    pub key_repeat_rate: u32,
+    pub window_mode: WindowMode,
+    /// A font of assets/fonts, without its .png extension
+    pub font: String,
+    /// Every glyph is drawn this many times larger, for high-DPI screens
+    pub render_scale: i32,
+    pub torch_radius: i32,
+    pub max_room_monsters: i32,
+    pub max_room_items: i32,
+    pub heal_amount: i32,
+    pub lightning_damage: i32,
+    /// What a healing potion and any other item but equipment weigh, equipment weighs what its content file says
+    pub potion_weight: i32,
+    pub item_weight: i32,
+    /// Turns between autosaves, 0 only saves on level changes
+    pub autosave_interval: u32,
+    pub map_width: i32,
+    pub map_height: i32,
+    pub seed: Option<u64>,
+    pub wizard: bool,
+    pub language: String,
+    pub accessibility: bool,
+    pub narrator_command: Option<String>,
+    pub palette: PaletteKind,
+    pub volume: f32,
+    pub music_volume: f32,
+    pub muted: bool,
+    pub monster_health_bars: bool,
+    /// Safety prompts turned off with "don't ask again"
+    pub skipped_prompts: Vec<SafetyPrompt>,
+    pub screen_motion: bool,
+    /// Tiles in view dim with their distance to the torch and campfires instead of being evenly lit
+    pub light_falloff: bool,
+    pub combat_verbosity: CombatVerbosity,
+    /// Opt-in, finished runs are sent to this http:// endpoint and its best runs shown with the scores
+    pub leaderboard_url: Option<String>,
+    #[serde(skip)]
+    pub locale: Locale,
+    #[serde(skip)]
+    pub content: Content,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            fps_limit: LIMIT_FPS,
+            vsync: false,
+            movement_keys: MovementKeys::default(),
+            key_repeat_delay_ms: KEY_REPEAT_DELAY_MS,
+            key_repeat_rate: KEY_REPEAT_RATE,
+            window_mode: WindowMode::default(),
+            font: DEFAULT_FONT.into(),
+            render_scale: 1,
+            torch_radius: TORCH_RADIUS,
+            max_room_monsters: MAX_ROOM_MONSTERS,
+            max_room_items: MAX_ROOM_ITEMS,
+            heal_amount: HEAL_AMOUNT,
+            lightning_damage: LIGHTNING_DAMAGE,
+            potion_weight: POTION_WEIGHT,
+            item_weight: ITEM_WEIGHT,
+            autosave_interval: AUTOSAVE_INTERVAL,
+            map_width: MAP_WIDTH,
+            map_height: MAP_HEIGHT,
+            seed: None,
+            wizard: false,
+            language: DEFAULT_LANGUAGE.into(),
+            accessibility: false,
+            narrator_command: None,
+            palette: PaletteKind::Default,
+            volume: DEFAULT_VOLUME,
+            music_volume: DEFAULT_MUSIC_VOLUME,
+            muted: false,
+            monster_health_bars: true,
+            screen_motion: true,
+            light_falloff: true,
+            skipped_prompts: vec![],
+            combat_verbosity: CombatVerbosity::default(),
+            leaderboard_url: None,
+            locale: Locale::default(),
+            content: Content::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn check_map_size(&self) -> Result<(), String> {
+        let min_size = ROOM_MAX_SIZE + 2;
+        if self.map_width < min_size || self.map_width > MAP_WIDTH
+            || self.map_height < min_size || self.map_height > MAP_HEIGHT
+        {
+            return Err(format!(
+                "Map size {}x{} is out of bounds, it must be between {}x{} and {}x{}.",
+                self.map_width, self.map_height, min_size, min_size, MAP_WIDTH, MAP_HEIGHT
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn load_config(path: &str) -> Config {
+    match fs::read_to_string(path) {
+        Ok(contents) => toml::from_str(&contents).unwrap_or_else(|error| {
+            eprintln!("Invalid {}, falling back on default settings: {}", path, error);
+            Config::default()
+        }),
+        Err(_) => Config::default(),
+    }
+}
+
+pub(crate) fn load_content(config: &mut Config) {
+    config.content = Content::load().unwrap_or_else(|error| {
+        eprintln!("Could not load content files, falling back on built-in content: {}", error);
+        Content::default()
+    });
+}
+
+pub(crate) fn load_locale(config: &mut Config) {
+    config.locale = Locale::load(&config.language).unwrap_or_else(|error| {
+        eprintln!("Could not load language {}, falling back on english: {}", config.language, error);
+        Locale::default()
+    });
+}
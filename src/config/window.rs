@@ -0,0 +1,40 @@
+use super::*;
+use std::time::SystemTime;
+use crate::game::{Game, MessageCategory};
+
+const CONTENT_FILES: [&str; 6] = ["weapons.toml", "monsters.toml", "uniques.toml", "affixes.toml", "recipes.toml", "armor.toml"];
+
+impl Rarity {
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            Rarity::Common => tr!(locale, "rarity_common"),
+            Rarity::Uncommon => tr!(locale, "rarity_uncommon"),
+            Rarity::Rare => tr!(locale, "rarity_rare"),
+            Rarity::Artifact => tr!(locale, "rarity_artifact"),
+        }
+    }
+}
+
+/// The latest modification time among the content files, polled in wizard mode to reload them
+pub(crate) fn content_modified() -> Option<SystemTime> {
+    CONTENT_FILES
+        .iter()
+        .filter_map(|file| fs::metadata(Path::new(CONTENT_DIR).join(file)).and_then(|metadata| metadata.modified()).ok())
+        .max()
+}
+
+// NOTICE: Species data is looked up when monsters act, so reloaded definitions apply to everything spawned from then on
+pub(crate) fn reload_changed_content(last_modified: &mut Option<SystemTime>, game: &mut Game) {
+    let modified = content_modified();
+    if modified == *last_modified {
+        return;
+    }
+    *last_modified = modified;
+    match Content::load() {
+        Ok(content) => {
+            game.config.content = content;
+            game.messages.add(tr!(game.config.locale, "wizard_content_reloaded"), MessageCategory::System);
+        }
+        Err(error) => game.messages.add(tr!(game.config.locale, "wizard_content_invalid", error = error), MessageCategory::Danger),
+    }
+}
@@ -0,0 +1,296 @@
+use std::iter;
+use serde::{Deserialize, Serialize};
+use crate::character::Skill;
+use crate::combat::{DamageType, Target, fire_projectile, ranged_hit, sees_invisible};
+use crate::config::Locale;
+use crate::game::{Game, MessageCategory};
+use crate::items::Item;
+use crate::map::Terrain;
+use crate::monsters::Spell;
+use crate::object::GameObject;
+use crate::portable::KeyCode;
+
+/// The window's keyboard, mouse and gamepad input, its prompts and context menu
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+const DART_DAMAGE: i32 = 4;
+const THROW_DAMAGE: i32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PlayerAction {
+    TookTurn,
+    DidntTakeTurn,
+    /// Only a front end quits, the game itself never asks to
+    #[cfg(any(feature = "window", feature = "terminal"))]
+    Exit,
+}
+
+// NOTICE: Everything an actor can do to the game state, the player's input and monster AI both decide on one, see `execute_action`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Action {
+    /// The player bumps into whatever is in the way, monsters open or smash doors
+    Move(i32, i32),
+    Attack(Target),
+    PickUp,
+    UseItem(usize),
+    UseItemOn(usize, usize),
+    Craft(usize),
+    Descend,
+    Wait,
+    Look(i32, i32),
+    ThrowAt(usize, i32, i32),
+    CloseDoor,
+    CastSpell,
+    /// A monster's ranged attack at a position
+    Fire(i32, i32),
+    /// A monster's spell, the player's own magic goes through `CastSpell`
+    Cast(Spell),
+}
+
+// NOTICE: Keyboard commands, the help screen is generated from the same table handle_keys reads
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Command {
+    Move(i32, i32),
+    PickUp,
+    Inventory,
+    Wait,
+    CloseDoor,
+    CastSpell,
+    TakeStairs,
+    Statistics,
+    Character,
+    Craft,
+    SortInventory,
+    Quickslot(usize),
+    AssignQuickslot,
+    TravelToLandmark,
+    Run(i32, i32),
+    RunPrompt,
+    Rest,
+    Note,
+    FullMap,
+    MessageLog,
+    ExportMap,
+    Screenshot,
+    Help,
+    ToggleFullscreen,
+    DebugOverlay,
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum KeyBinding {
+    Code(KeyCode),
+    Alt(KeyCode),
+    Shift(KeyCode),
+    Text(&'static str),
+}
+
+impl KeyBinding {
+    pub fn label(self, locale: &Locale) -> String {
+        let code_label = |code| match code {
+            KeyCode::Up => tr!(locale, "key_up"),
+            KeyCode::Down => tr!(locale, "key_down"),
+            KeyCode::Left => tr!(locale, "key_left"),
+            KeyCode::Right => tr!(locale, "key_right"),
+            KeyCode::Enter => tr!(locale, "key_enter"),
+            KeyCode::Escape => tr!(locale, "key_escape"),
+            code => format!("{:?}", code),
+        };
+        match self {
+            KeyBinding::Code(code) => code_label(code),
+            KeyBinding::Alt(code) => tr!(locale, "key_alt", key = code_label(code)),
+            KeyBinding::Shift(code) => tr!(locale, "key_shift", key = code_label(code)),
+            KeyBinding::Text(text) => text.to_string(),
+        }
+    }
+}
+
+const KEY_BINDINGS: &[(KeyBinding, Command)] = &[
+    (KeyBinding::Code(KeyCode::Up), Command::Move(0, -1)),
+    (KeyBinding::Code(KeyCode::Down), Command::Move(0, 1)),
+    (KeyBinding::Code(KeyCode::Left), Command::Move(-1, 0)),
+    (KeyBinding::Code(KeyCode::Right), Command::Move(1, 0)),
+    (KeyBinding::Shift(KeyCode::Up), Command::Run(0, -1)),
+    (KeyBinding::Shift(KeyCode::Down), Command::Run(0, 1)),
+    (KeyBinding::Shift(KeyCode::Left), Command::Run(-1, 0)),
+    (KeyBinding::Shift(KeyCode::Right), Command::Run(1, 0)),
+    (KeyBinding::Text("/"), Command::RunPrompt),
+    (KeyBinding::Text("g"), Command::PickUp),
+    (KeyBinding::Text("i"), Command::Inventory),
+    (KeyBinding::Text("."), Command::Wait),
+    (KeyBinding::Text("c"), Command::CloseDoor),
+    (KeyBinding::Text("z"), Command::CastSpell),
+    (KeyBinding::Text("<"), Command::TakeStairs),
+    (KeyBinding::Text("#"), Command::Statistics),
+    (KeyBinding::Text("@"), Command::Character),
+    (KeyBinding::Text("m"), Command::Craft),
+    (KeyBinding::Text("s"), Command::SortInventory),
+    (KeyBinding::Text("1"), Command::Quickslot(0)),
+    (KeyBinding::Text("2"), Command::Quickslot(1)),
+    (KeyBinding::Text("3"), Command::Quickslot(2)),
+    (KeyBinding::Text("4"), Command::Quickslot(3)),
+    (KeyBinding::Text("5"), Command::Quickslot(4)),
+    (KeyBinding::Text("q"), Command::AssignQuickslot),
+    (KeyBinding::Text("G"), Command::TravelToLandmark),
+    (KeyBinding::Text("R"), Command::Rest),
+    (KeyBinding::Text("N"), Command::Note),
+    (KeyBinding::Text("M"), Command::FullMap),
+    (KeyBinding::Text("l"), Command::MessageLog),
+    (KeyBinding::Text("X"), Command::ExportMap),
+    (KeyBinding::Code(KeyCode::F12), Command::Screenshot),
+    (KeyBinding::Text("?"), Command::Help),
+    (KeyBinding::Alt(KeyCode::Enter), Command::ToggleFullscreen),
+    (KeyBinding::Code(KeyCode::F3), Command::DebugOverlay),
+    (KeyBinding::Code(KeyCode::Escape), Command::Exit),
+];
+
+// NOTICE: Movement presets, their keys come before `KEY_BINDINGS` and the commands they push off a key move to
+// another one; the arrows keep working whatever the preset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementKeys {
+    #[default]
+    Arrows,
+    Wasd,
+    Vi,
+}
+
+const WASD_BINDINGS: &[(KeyBinding, Command)] = &[
+    (KeyBinding::Text("w"), Command::Move(0, -1)),
+    (KeyBinding::Text("a"), Command::Move(-1, 0)),
+    (KeyBinding::Text("s"), Command::Move(0, 1)),
+    (KeyBinding::Text("d"), Command::Move(1, 0)),
+    (KeyBinding::Text("q"), Command::Move(-1, -1)),
+    (KeyBinding::Text("e"), Command::Move(1, -1)),
+    (KeyBinding::Text("z"), Command::Move(-1, 1)),
+    (KeyBinding::Text("c"), Command::Move(1, 1)),
+    (KeyBinding::Text("x"), Command::CloseDoor),
+    (KeyBinding::Text("f"), Command::CastSpell),
+    (KeyBinding::Text("o"), Command::SortInventory),
+    (KeyBinding::Text("Q"), Command::AssignQuickslot),
+];
+
+const VI_BINDINGS: &[(KeyBinding, Command)] = &[
+    (KeyBinding::Text("k"), Command::Move(0, -1)),
+    (KeyBinding::Text("h"), Command::Move(-1, 0)),
+    (KeyBinding::Text("j"), Command::Move(0, 1)),
+    (KeyBinding::Text("l"), Command::Move(1, 0)),
+    (KeyBinding::Text("y"), Command::Move(-1, -1)),
+    (KeyBinding::Text("u"), Command::Move(1, -1)),
+    (KeyBinding::Text("b"), Command::Move(-1, 1)),
+    (KeyBinding::Text("n"), Command::Move(1, 1)),
+    (KeyBinding::Text("L"), Command::MessageLog),
+];
+
+impl MovementKeys {
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            MovementKeys::Arrows => tr!(locale, "movement_keys_arrows"),
+            MovementKeys::Wasd => tr!(locale, "movement_keys_wasd"),
+            MovementKeys::Vi => tr!(locale, "movement_keys_vi"),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            MovementKeys::Arrows => MovementKeys::Wasd,
+            MovementKeys::Wasd => MovementKeys::Vi,
+            MovementKeys::Vi => MovementKeys::Arrows,
+        }
+    }
+
+    fn bindings(self) -> &'static [(KeyBinding, Command)] {
+        match self {
+            MovementKeys::Arrows => &[],
+            MovementKeys::Wasd => WASD_BINDINGS,
+            MovementKeys::Vi => VI_BINDINGS,
+        }
+    }
+}
+
+/// The bindings in effect, the preset's first and then every one of `KEY_BINDINGS` whose key it leaves free
+pub(crate) fn key_bindings(movement_keys: MovementKeys) -> impl Iterator<Item = &'static (KeyBinding, Command)> {
+    let preset = movement_keys.bindings();
+    preset
+        .iter()
+        .chain(KEY_BINDINGS.iter().filter(move |(binding, _)| preset.iter().all(|(taken, _)| taken != binding)))
+}
+
+// NOTICE: Prompts before obviously bad moves, "don't ask again" turns one kind off for good
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyPrompt {
+    /// Stepping over a chasm or deep water only levitation lets the player cross
+    Hazard,
+    /// Taking the stairs with a monster next to the player
+    Descend,
+}
+
+pub(crate) fn look_at(x: i32, y: i32, game: &mut Game, game_objects: &[GameObject]) {
+    let visible = game.fov.is_in_fov(x, y);
+    let see_invisible = sees_invisible(game);
+    let seen: Vec<_> = iter::once(&game.player)
+        .chain(game.partner.iter())
+        .chain(game_objects.iter())
+        .filter(|game_object| game_object.position() == (x, y) && (visible || game_object.always_visible))
+        .filter(|game_object| !game_object.invisible || see_invisible)
+        .collect();
+
+    if seen.is_empty() {
+        let tile = game.map[(x, y)];
+        let key = match tile.terrain {
+            Terrain::Floor if tile.block_sight => "look_wall",
+            Terrain::Floor => "look_floor",
+            Terrain::Chasm => "look_chasm",
+            Terrain::DeepWater => "look_water",
+            Terrain::ClosedDoor => "look_closed_door",
+            Terrain::OpenDoor => "look_open_door",
+            Terrain::Rubble => "look_rubble",
+            Terrain::LockedGate => "look_locked_gate",
+            Terrain::UnlockedGate => "look_unlocked_gate",
+            Terrain::GlassWall => "look_glass_wall",
+            Terrain::Campfire => "look_campfire",
+        };
+        game.messages.add(tr!(game.config.locale, key), MessageCategory::System);
+    }
+    for game_object in seen {
+        let message = match game_object.fighter {
+            Some(fighter) if game.skills.level(Skill::Lore) > 0 => tr!(
+                game.config.locale,
+                "look_fighter_lore",
+                name = game_object.display_name(&game.config.locale),
+                hp = fighter.hp,
+                max_hp = fighter.max_hp,
+                power = fighter.power,
+                defense = fighter.defense,
+            ),
+            Some(fighter) => tr!(game.config.locale, "look_fighter", name = game_object.display_name(&game.config.locale), hp = fighter.hp, max_hp = fighter.max_hp),
+            None => tr!(game.config.locale, "look_object", name = game_object.display_name(&game.config.locale)),
+        };
+        game.messages.add(message, MessageCategory::System);
+    }
+}
+
+pub(crate) fn throw_item(inventory_index: usize, target: (i32, i32), game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let mut item = game.inventory.remove(inventory_index);
+    if let Some(ref mut equipment) = item.equipment {
+        equipment.equipped = false;
+    }
+    let start = game.player.position();
+
+    let (landing, hit) = fire_projectile(start, target, item.char, item.color, game, game_objects);
+    item.set_position(landing.0, landing.1);
+    match hit {
+        Some(target) => {
+            let damage = if item.item == Some(Item::Dart) { DART_DAMAGE } else { THROW_DAMAGE };
+            ranged_hit(target, &item.display_name(&game.config.locale), damage, DamageType::Physical, game, game_objects)
+        }
+        None => game.messages.add(tr!(game.config.locale, "throw_miss", item = item.display_name(&game.config.locale)), MessageCategory::Item),
+    }
+    game_objects.push(item);
+}
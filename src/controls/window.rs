@@ -0,0 +1,597 @@
+use super::*;
+use tcod::console::*;
+use tcod::input::{self, Event, Key};
+use std::cmp;
+use std::time::{Duration, Instant};
+use crate::activity::{
+    Activity, ActivityKind, activity_step, click_to_move, edit_note, landmark_menu, render_line_of_fire, start_run,
+    start_travel,
+};
+use crate::character::character_screen;
+use crate::combat::StatusEffect;
+use crate::config::{CONFIG_FILE, WindowMode};
+use crate::game::step;
+use crate::items::{
+    assign_quickslot, crafting_menu, cycle_inventory_sort, inventory_menu, quickslot_item, use_item_from_menu,
+};
+use crate::map::{map_size, mouse_tile, view_position};
+use crate::menu::{reopen_window, save_config_value};
+use crate::narration::direction_key;
+use crate::portable::colors::*;
+use crate::save::{export_map, save_screenshot};
+use crate::tutorial::finish_tutorial;
+use crate::ui::{Tcod, full_map, help_screen, menu, menu_at, message_log, msgbox, render_all};
+use crate::wizard::wizard;
+
+const CONTEXT_MENU_WIDTH: i32 = 16;
+const REST_TURNS: u32 = 50;
+const ACTIVITY_STEP_MS: u64 = 40;
+/// A held key whose release never came through stops repeating after this long without the OS repeating it
+const KEY_HELD_TIMEOUT_MS: u64 = 1000;
+const THROW_RANGE: i32 = 8;
+
+// NOTICE: Gamepad input, read through gilrs next to the tcod keyboard events
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PadInput {
+    Direction(i32, i32),
+    South,
+    East,
+    West,
+    North,
+    Start,
+}
+
+/// What the face buttons do on the map, the directions move and menus take South to confirm & East to cancel
+pub(crate) const PAD_BINDINGS: &[(PadInput, Command)] = &[
+    (PadInput::South, Command::PickUp),
+    (PadInput::West, Command::Inventory),
+    (PadInput::North, Command::Wait),
+    (PadInput::East, Command::TakeStairs),
+    (PadInput::Start, Command::Exit),
+];
+
+impl PadInput {
+    pub fn command(self) -> Option<Command> {
+        match self {
+            PadInput::Direction(dx, dy) => Some(Command::Move(dx, dy)),
+            button => PAD_BINDINGS.iter().find(|&&(input, _)| input == button).map(|&(_, command)| command),
+        }
+    }
+
+    pub fn label(self, locale: &Locale) -> String {
+        match self {
+            PadInput::Direction(..) => tr!(locale, "pad_direction"),
+            PadInput::South => tr!(locale, "pad_south"),
+            PadInput::East => tr!(locale, "pad_east"),
+            PadInput::West => tr!(locale, "pad_west"),
+            PadInput::North => tr!(locale, "pad_north"),
+            PadInput::Start => tr!(locale, "pad_start"),
+        }
+    }
+}
+
+pub(crate) struct Gamepad {
+    gilrs: gilrs::Gilrs,
+    stick: (i32, i32),
+}
+
+impl Gamepad {
+    pub fn new() -> Option<Self> {
+        gilrs::Gilrs::new().ok().map(|gilrs| Gamepad { gilrs, stick: (0, 0) })
+    }
+
+    /// Returns the next meaningful input, the stick only counts when it is pushed towards a new direction
+    pub fn poll(&mut self) -> Option<PadInput> {
+        use gilrs::{Axis, Button, EventType};
+
+        while let Some(event) = self.gilrs.next_event() {
+            let gamepad = self.gilrs.gamepad(event.id);
+            match event.event {
+                EventType::ButtonPressed(Button::DPadUp, _)
+                | EventType::ButtonPressed(Button::DPadDown, _)
+                | EventType::ButtonPressed(Button::DPadLeft, _)
+                | EventType::ButtonPressed(Button::DPadRight, _) => {
+                    let held = |button| if gamepad.is_pressed(button) { 1 } else { 0 };
+                    let dx = held(Button::DPadRight) - held(Button::DPadLeft);
+                    let dy = held(Button::DPadDown) - held(Button::DPadUp);
+                    return Some(PadInput::Direction(dx, dy));
+                }
+                EventType::AxisChanged(Axis::LeftStickX, _, _) | EventType::AxisChanged(Axis::LeftStickY, _, _) => {
+                    let tilt = |value: f32| {
+                        if value > STICK_THRESHOLD {
+                            1
+                        } else if value < -STICK_THRESHOLD {
+                            -1
+                        } else {
+                            0
+                        }
+                    };
+                    let stick = (tilt(gamepad.value(Axis::LeftStickX)), -tilt(gamepad.value(Axis::LeftStickY)));
+                    let previous = self.stick;
+                    self.stick = stick;
+                    if stick != (0, 0) && stick != previous {
+                        return Some(PadInput::Direction(stick.0, stick.1));
+                    }
+                }
+                EventType::ButtonPressed(Button::South, _) => return Some(PadInput::South),
+                EventType::ButtonPressed(Button::East, _) => return Some(PadInput::East),
+                EventType::ButtonPressed(Button::West, _) => return Some(PadInput::West),
+                EventType::ButtonPressed(Button::North, _) => return Some(PadInput::North),
+                EventType::ButtonPressed(Button::Start, _) => return Some(PadInput::Start),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl Command {
+    pub fn description(self, locale: &Locale) -> String {
+        match self {
+            Command::Move(dx, dy) => tr!(locale, "help_move", direction = tr!(locale, direction_key(dx, dy))),
+            Command::PickUp => tr!(locale, "help_pick_up"),
+            Command::Inventory => tr!(locale, "help_inventory"),
+            Command::Wait => tr!(locale, "help_wait"),
+            Command::CloseDoor => tr!(locale, "help_close_door"),
+            Command::CastSpell => tr!(locale, "help_cast_spell"),
+            Command::TakeStairs => tr!(locale, "help_take_stairs"),
+            Command::Statistics => tr!(locale, "help_statistics"),
+            Command::Character => tr!(locale, "help_character"),
+            Command::Craft => tr!(locale, "help_craft"),
+            Command::SortInventory => tr!(locale, "help_sort_inventory"),
+            Command::Quickslot(slot) => tr!(locale, "help_quickslot", slot = slot + 1),
+            Command::AssignQuickslot => tr!(locale, "help_assign_quickslot"),
+            Command::TravelToLandmark => tr!(locale, "help_travel_to_landmark"),
+            Command::Run(dx, dy) => tr!(locale, "help_run", direction = tr!(locale, direction_key(dx, dy))),
+            Command::RunPrompt => tr!(locale, "help_run_prompt"),
+            Command::Rest => tr!(locale, "help_rest"),
+            Command::Note => tr!(locale, "help_note"),
+            Command::FullMap => tr!(locale, "help_full_map"),
+            Command::MessageLog => tr!(locale, "help_message_log"),
+            Command::ExportMap => tr!(locale, "help_export_map"),
+            Command::Screenshot => tr!(locale, "help_screenshot"),
+            Command::Help => tr!(locale, "help_help"),
+            Command::ToggleFullscreen => tr!(locale, "help_fullscreen"),
+            Command::DebugOverlay => tr!(locale, "help_debug_overlay"),
+            Command::Exit => tr!(locale, "help_exit"),
+        }
+    }
+}
+
+impl KeyBinding {
+    pub fn matches(self, key: Key) -> bool {
+        match self {
+            KeyBinding::Code(code) => key.code == code && !key.shift,
+            KeyBinding::Alt(code) => key.code == code && key.alt,
+            KeyBinding::Shift(code) => key.code == code && key.shift,
+            KeyBinding::Text(text) => key.code == KeyCode::Text && key.text() == text,
+        }
+    }
+}
+
+pub(crate) const SKIP_TUTORIAL_KEY: KeyBinding = KeyBinding::Code(KeyCode::Tab);
+
+pub(crate) fn find_command(key: Key, movement_keys: MovementKeys) -> Option<Command> {
+    key_bindings(movement_keys)
+        .find(|(binding, _)| binding.matches(key))
+        .map(|&(_, command)| command)
+}
+
+// NOTICE: Cheats for testing content, only bound when the game runs with --wizard
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum WizardCommand {
+    RevealMap,
+    Teleport,
+    Spawn,
+    SetHp,
+    JumpToDepth,
+    Undo,
+    Console,
+}
+
+impl WizardCommand {
+    pub fn description(self, locale: &Locale) -> String {
+        match self {
+            WizardCommand::RevealMap => tr!(locale, "help_wizard_reveal"),
+            WizardCommand::Teleport => tr!(locale, "help_wizard_teleport"),
+            WizardCommand::Spawn => tr!(locale, "help_wizard_spawn"),
+            WizardCommand::SetHp => tr!(locale, "help_wizard_set_hp"),
+            WizardCommand::JumpToDepth => tr!(locale, "help_wizard_depth"),
+            WizardCommand::Undo => tr!(locale, "help_wizard_undo"),
+            WizardCommand::Console => tr!(locale, "help_wizard_console"),
+        }
+    }
+}
+
+pub(crate) const WIZARD_BINDINGS: &[(KeyBinding, WizardCommand)] = &[
+    (KeyBinding::Code(KeyCode::F5), WizardCommand::RevealMap),
+    (KeyBinding::Code(KeyCode::F6), WizardCommand::Teleport),
+    (KeyBinding::Code(KeyCode::F7), WizardCommand::Spawn),
+    (KeyBinding::Code(KeyCode::F8), WizardCommand::SetHp),
+    (KeyBinding::Code(KeyCode::F9), WizardCommand::JumpToDepth),
+    (KeyBinding::Code(KeyCode::F10), WizardCommand::Undo),
+    (KeyBinding::Text("`"), WizardCommand::Console),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ContextAction {
+    Look,
+    Attack,
+    WalkHere,
+    PickUp,
+    ThrowAt,
+}
+
+/// Reads the next key press or mouse event into `tcod`, returns whether there was one
+// NOTICE: All pending events are read each frame and their key presses queued, one is handled per frame so none
+// are lost while the game animates; held movement keys repeat at the configured pace rather than the OS's
+pub(crate) fn poll_input(tcod: &mut Tcod) -> bool {
+    let now = Instant::now();
+    let mut received = false;
+    while let Some((_, event)) = input::check_for_event(input::MOUSE | input::KEY) {
+        received = true;
+        match event {
+            Event::Mouse(mouse) => tcod.mouse = mouse,
+            Event::Key(key) if !key.pressed => {
+                if tcod.held_key.is_some_and(|held| is_release_of(key, held.key)) {
+                    tcod.held_key = None;
+                }
+            }
+            // NOTICE: libtcod reports printable keys twice, as a Char key and then as Text, the bindings use the Text one
+            Event::Key(key) if key.code == KeyCode::Char => {}
+            Event::Key(key) => match tcod.held_key {
+                Some(ref mut held) if same_key(held.key, key) => held.last_seen = now,
+                _ => {
+                    let command = find_command(key, tcod.movement_keys);
+                    let repeats = matches!(command, Some(Command::Move(..)) | Some(Command::Wait));
+                    let held = HeldKey { key, next_repeat: now + tcod.key_repeat_delay, last_seen: now, repeats };
+                    // NOTICE: Run keys are held too, so that their OS repeats don't cut the run short
+                    tcod.held_key = Some(held).filter(|_| repeats || matches!(command, Some(Command::Run(..))));
+                    tcod.input_queue.push_back(key);
+                }
+            },
+        }
+    }
+
+    let held_timeout = Duration::from_millis(KEY_HELD_TIMEOUT_MS);
+    if tcod.held_key.is_some_and(|held| now.duration_since(held.last_seen) > held_timeout) {
+        tcod.held_key = None;
+    }
+    // NOTICE: Repeats wait for the queue to empty, a slow turn never piles them up
+    if let Some(mut held) = tcod.held_key.filter(|held| held.repeats && now >= held.next_repeat && tcod.input_queue.is_empty()) {
+        held.next_repeat = now + tcod.key_repeat_interval;
+        tcod.input_queue.push_back(held.key);
+        tcod.held_key = Some(held);
+    }
+    tcod.key = tcod.input_queue.pop_front().unwrap_or_default();
+    received || tcod.key.code != KeyCode::NoKey
+}
+
+/// A movement key held down, see `poll_input`
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct HeldKey {
+    key: Key,
+    next_repeat: Instant,
+    /// When the key was pressed or last repeated by the OS
+    last_seen: Instant,
+    repeats: bool,
+}
+
+fn same_key(a: Key, b: Key) -> bool {
+    a.code == b.code && a.text() == b.text() && (a.shift, a.ctrl, a.alt) == (b.shift, b.ctrl, b.alt)
+}
+
+/// Releases of printable keys come as a Char key, without the shift their Text press had
+fn is_release_of(release: Key, held: Key) -> bool {
+    match held.code {
+        KeyCode::Text => {
+            let first = held.text().chars().next();
+            release.code == KeyCode::Char && first.is_some_and(|c| c.eq_ignore_ascii_case(&release.printable))
+        }
+        code => release.code == code,
+    }
+}
+
+pub(crate) fn handle_keys(tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    use PlayerAction::*;
+
+    let player_alive = game.player.is_alive;
+
+    if tcod.mouse.lbutton_pressed && player_alive {
+        tcod.mouse.lbutton_pressed = false;
+        return match mouse_tile(tcod.mouse, game) {
+            Some(target) => start_travel(target, tcod, game, game_objects),
+            None => PlayerAction::DidntTakeTurn,
+        };
+    }
+
+    if tcod.mouse.rbutton_pressed && player_alive {
+        tcod.mouse.rbutton_pressed = false;
+        game.activity = None;
+        return match mouse_tile(tcod.mouse, game) {
+            Some(target) => context_menu(target, tcod, game, game_objects),
+            None => PlayerAction::DidntTakeTurn,
+        };
+    }
+
+    let pad_command = tcod.gamepad.as_mut().and_then(Gamepad::poll).and_then(PadInput::command);
+    if pad_command.is_some() {
+        game.activity = None;
+    }
+
+    if tcod.key.code != KeyCode::NoKey {
+        game.activity = None;
+    } else if game.activity.is_some() && player_alive {
+        if tcod.activity_stepped.elapsed() < Duration::from_millis(ACTIVITY_STEP_MS) {
+            return DidntTakeTurn;
+        }
+        tcod.activity_stepped = Instant::now();
+        return activity_step(game, game_objects);
+    }
+
+    if SKIP_TUTORIAL_KEY.matches(tcod.key) && game.tutorial.is_some() && player_alive {
+        finish_tutorial("tutorial_skipped", game, game_objects);
+        return DidntTakeTurn;
+    }
+
+    if game.config.wizard && player_alive {
+        let wizard_command = WIZARD_BINDINGS.iter().find(|(binding, _)| binding.matches(tcod.key));
+        if let Some(&(_, wizard_command)) = wizard_command {
+            wizard(wizard_command, tcod, game, game_objects);
+            return DidntTakeTurn;
+        }
+    }
+
+    let command = pad_command.or_else(|| find_command(tcod.key, game.config.movement_keys));
+    if player_alive && command.is_some_and(|command| !confirm_safe(command, tcod, game, game_objects)) {
+        game.messages.add(tr!(game.config.locale, "cancelled"), MessageCategory::System);
+        return DidntTakeTurn;
+    }
+
+    match (command, player_alive) {
+        (Some(Command::Move(dx, dy)), true) => step(game, game_objects, Action::Move(dx, dy)),
+        (Some(Command::PickUp), true) => step(game, game_objects, Action::PickUp),
+        (Some(Command::Inventory), true) => use_item_from_menu(tcod, game, game_objects),
+        (Some(Command::Wait), true) => step(game, game_objects, Action::Wait),
+        (Some(Command::CloseDoor), true) => step(game, game_objects, Action::CloseDoor),
+        (Some(Command::CastSpell), true) => step(game, game_objects, Action::CastSpell),
+        (Some(Command::TakeStairs), true) => step(game, game_objects, Action::Descend),
+        (Some(Command::Statistics), _) => {
+            let stats = game.stats.lines(game.turn, &game.config.locale).join("\n");
+            let title = tr!(game.config.locale, "statistics");
+            msgbox(&format!("{}\n\n{}\n", title, stats), 40, tcod);
+            DidntTakeTurn
+        }
+        (Some(Command::Craft), true) => match crafting_menu(tcod, game) {
+            Some(recipe_index) => step(game, game_objects, Action::Craft(recipe_index)),
+            None => DidntTakeTurn,
+        },
+        (Some(Command::Character), _) => {
+            character_screen(tcod, game);
+            DidntTakeTurn
+        }
+        (Some(Command::Quickslot(slot)), true) => match quickslot_item(slot, game) {
+            Some(inventory_index) => step(game, game_objects, Action::UseItem(inventory_index)),
+            None => {
+                game.messages.add(tr!(game.config.locale, "quickslot_empty", slot = slot + 1), MessageCategory::System);
+                DidntTakeTurn
+            }
+        },
+        (Some(Command::Run(dx, dy)), true) => start_run(dx, dy, game, game_objects),
+        (Some(Command::RunPrompt), true) => match ask_direction(tcod, game, game_objects) {
+            Some((dx, dy)) if confirm_safe(Command::Run(dx, dy), tcod, game, game_objects) => start_run(dx, dy, game, game_objects),
+            _ => DidntTakeTurn,
+        },
+        (Some(Command::Rest), true) => {
+            game.activity = Some(Activity::new(ActivityKind::Rest { turns_left: REST_TURNS }, game, game_objects));
+            activity_step(game, game_objects)
+        }
+        (Some(Command::Note), true) => {
+            edit_note(tcod, game);
+            DidntTakeTurn
+        }
+        (Some(Command::AssignQuickslot), true) => {
+            assign_quickslot(tcod, game);
+            DidntTakeTurn
+        }
+        (Some(Command::TravelToLandmark), true) => match landmark_menu(tcod, game, game_objects) {
+            Some(destination) => start_travel(destination, tcod, game, game_objects),
+            None => DidntTakeTurn,
+        },
+        (Some(Command::SortInventory), _) => {
+            cycle_inventory_sort(game);
+            DidntTakeTurn
+        }
+        (Some(Command::FullMap), _) => {
+            full_map(tcod, game, game_objects);
+            DidntTakeTurn
+        }
+        (Some(Command::MessageLog), _) => {
+            message_log(tcod, game);
+            DidntTakeTurn
+        }
+        (Some(Command::ExportMap), _) => {
+            let options = [tr!(game.config.locale, "export_text"), tr!(game.config.locale, "export_text_and_png")];
+            if let Some(choice) = menu(&tr!(game.config.locale, "export_header"), &options, 40, tcod) {
+                match export_map(choice == 1, game, game_objects, &tcod.palette) {
+                    Ok(path) => game.messages.add(tr!(game.config.locale, "export_written", path = path.display()), MessageCategory::System),
+                    Err(error) => game.messages.add(tr!(game.config.locale, "export_failed", error = error), MessageCategory::Danger),
+                }
+            }
+            DidntTakeTurn
+        }
+        (Some(Command::Screenshot), _) => {
+            match save_screenshot() {
+                Ok(path) => game.messages.add(tr!(game.config.locale, "screenshot_saved", path = path.display()), MessageCategory::System),
+                Err(error) => game.messages.add(tr!(game.config.locale, "screenshot_failed", error = error), MessageCategory::Danger),
+            }
+            DidntTakeTurn
+        }
+        (Some(Command::Help), _) => {
+            help_screen(tcod, &game.config.locale, game.config.wizard);
+            DidntTakeTurn
+        }
+        (Some(Command::ToggleFullscreen), _) => {
+            let mode = if tcod.window_mode == WindowMode::Windowed { tcod.fullscreen_mode } else { WindowMode::Windowed };
+            reopen_window(tcod, tcod.font.clone(), tcod.render_scale, mode, &game.config.locale);
+            DidntTakeTurn
+        }
+        (Some(Command::DebugOverlay), _) => {
+            tcod.debug_overlay = !tcod.debug_overlay;
+            DidntTakeTurn
+        }
+        (Some(Command::Exit), _) => Exit,
+        _ => DidntTakeTurn,
+    }
+}
+
+/// The question to ask before `command`, when it is one of the bad moves
+pub(crate) fn safety_prompt(command: Command, game: &Game, game_objects: &[GameObject]) -> Option<(SafetyPrompt, String)> {
+    let locale = &game.config.locale;
+    let (x, y) = game.player.position();
+    match command {
+        Command::Move(dx, dy) | Command::Run(dx, dy) => {
+            let terrain = game.map.get(x + dx, y + dy)?.terrain;
+            let occupied = game_objects.iter().any(|game_object| game_object.blocks && game_object.position() == (x + dx, y + dy));
+            if occupied || game.player.movement.can_cross(terrain) || !game.player.movement().can_cross(terrain) {
+                return None;
+            }
+            let turns = game.player.statuses.iter().find_map(|status| match *status {
+                StatusEffect::Levitating { turns } => Some(turns),
+                _ => None,
+            });
+            let key = if terrain == Terrain::Chasm { "safety_chasm" } else { "safety_water" };
+            Some((SafetyPrompt::Hazard, tr!(locale, key, turns = turns.unwrap_or(0))))
+        }
+        Command::TakeStairs => {
+            let on_stairs = game_objects.iter().any(|game_object| game_object.is_stairs && game_object.position() == (x, y));
+            let enemy = game_objects.iter().find(|game_object| {
+                game_object.is_alive
+                    && game_object.fighter.is_some()
+                    && game_object.ai.is_some()
+                    && game.fov.is_in_fov(game_object.x, game_object.y)
+                    && cmp::max((game_object.x - x).abs(), (game_object.y - y).abs()) == 1
+            })?;
+            on_stairs.then(|| (SafetyPrompt::Descend, tr!(locale, "safety_descend", monster = enemy.display_name(locale))))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `command` can go ahead, asking first when it is a bad move whose prompt is still on
+fn confirm_safe(command: Command, tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) -> bool {
+    let (prompt, question) = match safety_prompt(command, game, game_objects) {
+        Some((prompt, question)) if !tcod.skipped_prompts.contains(&prompt) => (prompt, question),
+        _ => return true,
+    };
+    // NOTICE: A held movement key would otherwise ask again as soon as the answer is no
+    tcod.held_key = None;
+    let locale = &game.config.locale;
+    let options = [tr!(locale, "yes"), tr!(locale, "no"), tr!(locale, "safety_never_ask")];
+    match menu(&question, &options, 40, tcod) {
+        Some(0) => true,
+        Some(2) => {
+            tcod.skipped_prompts.push(prompt);
+            if let Err(error) = save_config_value(CONFIG_FILE, "skipped_prompts", &tcod.skipped_prompts) {
+                msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Asks for a movement key over the map, Escape gives up
+fn ask_direction(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) -> Option<(i32, i32)> {
+    render_all(tcod, game, game_objects);
+    tcod.root.set_default_foreground(LIGHT_YELLOW);
+    tcod.root.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, tr!(game.config.locale, "run_prompt"));
+    tcod.root.flush();
+    while !tcod.root.window_closed() {
+        poll_input(tcod);
+        match find_command(tcod.key, tcod.movement_keys) {
+            Some(Command::Move(dx, dy)) | Some(Command::Run(dx, dy)) => return Some((dx, dy)),
+            Some(Command::Exit) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn context_menu(target: (i32, i32), tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let (map_width, map_height) = map_size(&game.map);
+    let (x, y) = target;
+    if x < 0 || y < 0 || x >= map_width || y >= map_height || !game.map[(x, y)].explored {
+        return PlayerAction::DidntTakeTurn;
+    }
+
+    let player = &game.player;
+    let visible = game.fov.is_in_fov(x, y);
+    let on_player = player.position() == target;
+    let distance = cmp::max((x - player.x).abs(), (y - player.y).abs());
+    let see_invisible = sees_invisible(game);
+    let has_monster = visible
+        && game_objects.iter().any(|game_object| {
+            game_object.position() == target
+                && game_object.fighter.is_some()
+                && game_object.ai.is_some()
+                && (!game_object.invisible || see_invisible)
+        });
+    let has_item = game_objects
+        .iter()
+        .any(|game_object| game_object.position() == target && game_object.item.is_some());
+
+    let mut actions = vec![ContextAction::Look];
+    if has_monster {
+        actions.push(ContextAction::Attack);
+    }
+    if !on_player && !game.map[(x, y)].blocked {
+        actions.push(ContextAction::WalkHere);
+    }
+    if on_player && has_item {
+        actions.push(ContextAction::PickUp);
+    }
+    if visible && !on_player && distance <= THROW_RANGE && !game.inventory.is_empty() {
+        actions.push(ContextAction::ThrowAt);
+    }
+
+    let labels: Vec<String> = actions
+        .iter()
+        .map(|action| match action {
+            ContextAction::Look => tr!(game.config.locale, "context_look"),
+            ContextAction::Attack => tr!(game.config.locale, "context_attack"),
+            ContextAction::WalkHere => tr!(game.config.locale, "context_walk_here"),
+            ContextAction::PickUp => tr!(game.config.locale, "context_pick_up"),
+            ContextAction::ThrowAt => tr!(game.config.locale, "context_throw_at"),
+        })
+        .collect();
+
+    if actions.contains(&ContextAction::ThrowAt) {
+        render_all(tcod, game, game_objects);
+        render_line_of_fire(target, tcod, game, game_objects);
+    }
+    let (screen_x, screen_y) = view_position(target, game).unwrap_or(target);
+    let choice = menu_at("", &labels, CONTEXT_MENU_WIDTH, Some((screen_x + 1, screen_y + 1)), tcod);
+    match choice.map(|index| actions[index]) {
+        Some(ContextAction::Look) => step(game, game_objects, Action::Look(x, y)),
+        Some(ContextAction::Attack) => click_to_move(target, game, game_objects),
+        Some(ContextAction::WalkHere) => start_travel(target, tcod, game, game_objects),
+        Some(ContextAction::PickUp) => step(game, game_objects, Action::PickUp),
+        Some(ContextAction::ThrowAt) => {
+            let inventory_index = inventory_menu(
+                &game.inventory,
+                &tr!(game.config.locale, "throw_header"),
+                game.inventory_sort,
+                &game.config.locale,
+                tcod,
+            );
+            match inventory_index {
+                Some(inventory_index) => step(game, game_objects, Action::ThrowAt(inventory_index, x, y)),
+                None => PlayerAction::DidntTakeTurn,
+            }
+        }
+        None => PlayerAction::DidntTakeTurn,
+    }
+}
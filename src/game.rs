@@ -0,0 +1,656 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use rand::{Rng, XorShiftRng};
+use serde::{Deserialize, Serialize};
+use crate::arena::arena_step;
+use crate::audio::Sound;
+use crate::character::{Class, Perk, Skills, Stats, apply_class, new_player};
+use crate::combat::{
+    GameEvent, Projectile, Target, dispatch_events, melee, partner_move_or_attack, player_move_or_attack,
+    record_hp, terrain_damage, tick_status_effects,
+};
+use crate::config::{Component, Config, full_satiety};
+use crate::controls::{Action, PlayerAction, look_at, throw_item};
+use crate::items::{
+    FULL_SATIETY, Item, QUICKSLOT_COUNT, cast_magic_missile, craft, is_encumbered, pick_item_up, tick_food, tick_hunger, use_item,
+};
+use crate::map::{
+    Map, blocks_movement, close_door, compute_fov, initialise_fov, level_rng, make_map, monster_move_by,
+    new_fov_map, seeded_rng,
+};
+use crate::monsters::{
+    ai_take_turn, announce_uniques, apply_affixes, cast_spell, monster_fire, place_unique, turn_abilities,
+};
+use crate::object::{EntityId, GameObject};
+use crate::portable::FovMap;
+use crate::portable::colors::*;
+use crate::tutorial::{Tutorial, finish_tutorial, tutorial_on_last_step};
+#[cfg(any(feature = "window", feature = "terminal"))]
+use crate::items::InventorySort;
+#[cfg(any(feature = "window", feature = "terminal"))]
+use crate::net::Pending;
+#[cfg(feature = "window")]
+use std::collections::VecDeque;
+#[cfg(feature = "window")]
+use crate::activity::Activity;
+
+/// Message colors, record keeping for runs that end in death
+#[cfg(any(feature = "window", feature = "terminal"))]
+mod frontend;
+/// The window's game loop, projectile animation, co-op turns and the message log's counters
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(any(feature = "window", feature = "terminal"))]
+pub(crate) use self::frontend::*;
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+pub(crate) const ENCUMBERED_MOVE_COST: u32 = 2;
+const MANA_REGEN_TURNS: u32 = 3;
+const QUICK_CHANCE: f32 = 0.1;
+
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub(crate) player_name: String,
+    /// Kept apart from the monsters and items, older saves have it first in the object list instead
+    #[serde(default)]
+    pub(crate) player: GameObject,
+    /// The second hero of a co-op game, played from another client through `Tcod::coop`
+    #[serde(default)]
+    pub(crate) partner: Option<GameObject>,
+    /// The cost of the host's last action while the partner has yet to act, the monsters wait for both
+    #[serde(skip)]
+    pub(crate) partner_turn: Option<u32>,
+    pub(crate) map: Map,
+    pub(crate) messages: Messages,
+    pub(crate) inventory: Vec<GameObject>,
+    #[serde(default)]
+    pub(crate) class: Class,
+    #[serde(default)]
+    pub(crate) skills: Skills,
+    #[serde(default)]
+    pub(crate) pending_perks: u32,
+    /// Turns until the player starves, under zero while starving, saves from before the hunger clock start with a full belly
+    #[serde(default = "full_satiety")]
+    pub(crate) satiety: i32,
+    #[serde(default)]
+    pub(crate) components: BTreeMap<Component, u32>,
+    #[serde(default)]
+    pub(crate) quickslots: [Option<Item>; QUICKSLOT_COUNT],
+    pub(crate) dungeon_level: u32,
+    pub(crate) turn: u32,
+    #[serde(default)]
+    pub(crate) stats: Stats,
+    #[serde(skip, default = "new_fov_map")]
+    pub(crate) fov: FovMap,
+    #[serde(skip)]
+    pub(crate) config: Config,
+    pub(crate) seed: u64,
+    pub(crate) rng: GameRng,
+    #[serde(skip)]
+    pub(crate) sounds: Vec<Sound>,
+    #[cfg(feature = "window")]
+    #[serde(skip)]
+    pub(crate) activity: Option<Activity>,
+    #[cfg(any(feature = "window", feature = "terminal"))]
+    #[serde(skip)]
+    pub(crate) inventory_sort: InventorySort,
+    #[serde(skip)]
+    pub(crate) projectiles: Vec<Projectile>,
+    #[serde(skip)]
+    pub(crate) events: Vec<GameEvent>,
+    /// Milliseconds to shake the map for, the front end takes it after each turn like `sounds`
+    #[serde(skip)]
+    pub(crate) shake: u32,
+    #[serde(default)]
+    pub(crate) spawned_uniques: Vec<String>,
+    #[serde(default)]
+    pub(crate) remembered: Vec<RememberedObject>,
+    #[serde(skip)]
+    pub(crate) lights: Vec<Light>,
+    #[serde(default)]
+    pub(crate) notes: Vec<MapNote>,
+    #[serde(default)]
+    pub(crate) past_levels: Vec<PastLevel>,
+    /// The player's hit points at the end of each of the last turns, for the death recap
+    #[serde(default)]
+    pub(crate) hp_history: Vec<i32>,
+    /// How many of `LOW_HP_WARNINGS` the player's hit points are under, each warns once on the way down
+    #[serde(default)]
+    pub(crate) hp_warnings: usize,
+    #[serde(skip)]
+    pub(crate) killer: Option<String>,
+    #[serde(skip)]
+    pub(crate) final_blow: Option<String>,
+    /// The finished run on its way to the online leaderboard, the death recap tells how it went
+    #[cfg(any(feature = "window", feature = "terminal"))]
+    #[serde(skip)]
+    pub(crate) leaderboard_submission: Option<Pending<Result<(), String>>>,
+    /// The date of the daily run this game belongs to, daily runs share their seed and cannot be reloaded
+    #[serde(default)]
+    pub(crate) daily: Option<String>,
+    #[serde(default)]
+    pub(crate) arena: Option<Arena>,
+    #[serde(default)]
+    pub(crate) tutorial: Option<Tutorial>,
+    #[serde(skip)]
+    pub(crate) metrics: Metrics,
+    /// The game saved after each of the last turns, newest last, only kept in wizard mode for undoing
+    #[cfg(feature = "window")]
+    #[serde(skip)]
+    pub(crate) snapshots: VecDeque<String>,
+    /// Actions run through `step` since the front end last took them, for the replay recorder
+    #[serde(skip)]
+    pub(crate) taken_actions: Vec<Action>,
+    /// Set by perks, wizard commands & the like that change the game outside of `step`, the replay recorder takes it
+    #[cfg(any(feature = "window", feature = "terminal"))]
+    #[serde(skip)]
+    pub(crate) changed_outside_step: bool,
+    #[serde(default)]
+    next_entity_id: u64,
+}
+
+/// Engine counters for the debug overlay, never saved
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    pub(crate) fov_recomputes: u32,
+    /// Time the monsters spent deciding and acting during the last turn
+    pub(crate) ai_time: Duration,
+}
+
+/// The endless horde mode, a new wave is sent in a few turns after the last one was cleared
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Arena {
+    pub(crate) wave: u32,
+    pub(crate) waves_survived: u32,
+    pub(crate) countdown: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct MapNote {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) text: String,
+}
+
+// NOTICE: Items, stairs & corpses as they were last seen, drawn dimmed on explored tiles out of view
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RememberedObject {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) char: char,
+    pub(crate) color: Color,
+}
+
+/// A level left by the stairs, kept as it was explored so the full map can still show it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct PastLevel {
+    pub(crate) dungeon_level: u32,
+    pub(crate) map: Map,
+    pub(crate) notes: Vec<MapNote>,
+    pub(crate) remembered: Vec<RememberedObject>,
+}
+
+/// Where light comes from and how far it reaches, found again with the field of view
+// NOTICE: Only the renderers shade tiles with it, the bot & headless modes leave it unread
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(any(feature = "window", feature = "terminal")), allow(dead_code))]
+pub(crate) struct Light {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) radius: f32,
+}
+
+/// The game's RNG, saved as its seed and the number of values drawn so a loaded game rolls on as if it was never saved
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "RngState", into = "RngState")]
+pub(crate) struct GameRng {
+    seed: u64,
+    draws: u64,
+    rng: XorShiftRng,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RngState {
+    seed: u64,
+    draws: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng { seed, draws: 0, rng: seeded_rng(seed) }
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.draws += 1;
+        self.rng.next_u32()
+    }
+}
+
+impl From<RngState> for GameRng {
+    fn from(state: RngState) -> Self {
+        let mut game_rng = GameRng::new(state.seed);
+        for _ in 0..state.draws {
+            game_rng.next_u32();
+        }
+        game_rng
+    }
+}
+
+impl From<GameRng> for RngState {
+    fn from(game_rng: GameRng) -> Self {
+        RngState { seed: game_rng.seed, draws: game_rng.draws }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum MessageCategory {
+    Combat,
+    Item,
+    System,
+    Danger,
+}
+
+impl MessageCategory {
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Message {
+    text: String,
+    pub(crate) category: MessageCategory,
+    count: u32,
+}
+
+impl Message {
+    pub fn text(&self) -> String {
+        if self.count > 1 {
+            format!("{} (x{})", self.text, self.count)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Messages {
+    messages: Vec<Message>,
+    /// Every message ever added, merged repeats included
+    added: u64,
+}
+
+impl Messages {
+    pub fn new () -> Self {
+        Self { messages: vec![], added: 0 }
+    }
+
+    /// Consecutive duplicates are merged into the last message, bumping its counter
+    pub fn add<T: Into<String>>(&mut self, message: T, category: MessageCategory) {
+        let text = message.into();
+        self.added += 1;
+        if let Some(last) = self.messages.last_mut() {
+            if last.text == text && last.category == category {
+                last.count += 1;
+                return;
+            }
+        }
+        self.messages.push(Message { text, category, count: 1 });
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.messages.iter()
+    }
+}
+
+/// Lends the player out of `game` to code that needs both mutably, the player reads as a default object meanwhile
+pub(crate) fn with_player<T>(game: &mut Game, f: impl FnOnce(&mut GameObject, &mut Game) -> T) -> T {
+    let mut player = std::mem::take(&mut game.player);
+    let result = f(&mut player, game);
+    game.player = player;
+    result
+}
+
+/// Same as `with_player` for the co-op partner, None when there is no partner
+pub(crate) fn with_partner<T>(game: &mut Game, f: impl FnOnce(&mut GameObject, &mut Game) -> T) -> Option<T> {
+    let mut partner = game.partner.take()?;
+    let result = f(&mut partner, game);
+    game.partner = Some(partner);
+    Some(result)
+}
+
+/// Resolves what any actor decided to do, so the player and the monsters play by the same rules
+pub(crate) fn execute_action(actor: Target, action: Action, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    use PlayerAction::*;
+
+    if !actor.game_object(game, game_objects).is_alive {
+        return DidntTakeTurn;
+    }
+    match (actor, action) {
+        (Target::Player, Action::Move(dx, dy)) => {
+            player_move_or_attack(dx, dy, game, game_objects);
+            TookTurn
+        }
+        (Target::Partner, Action::Move(dx, dy)) => {
+            partner_move_or_attack(dx.signum(), dy.signum(), game, game_objects);
+            TookTurn
+        }
+        (Target::Object(id), Action::Move(dx, dy)) => {
+            monster_move_by(id, dx, dy, game, game_objects);
+            TookTurn
+        }
+        (_, Action::Attack(target)) => {
+            melee(actor, target, game, game_objects);
+            TookTurn
+        }
+        (_, Action::Wait) => TookTurn,
+        (Target::Object(id), Action::Fire(x, y)) => {
+            monster_fire(id, (x, y), game, game_objects);
+            TookTurn
+        }
+        (Target::Object(id), Action::Cast(spell)) => {
+            cast_spell(spell, id, game, game_objects);
+            TookTurn
+        }
+        // NOTICE: Items, stairs & the rest are the player's alone
+        (Target::Object(_), _) | (Target::Partner, _) => DidntTakeTurn,
+        (Target::Player, action) => resolve_player_action(action, game, game_objects),
+    }
+}
+
+fn resolve_player_action(action: Action, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    use PlayerAction::*;
+
+    match action {
+        Action::PickUp => {
+            let item_id = game_objects
+                .iter()
+                .position(|game_object| game_object.position() == game.player.position() && game_object.item.is_some());
+            if let Some(item_id) = item_id {
+                pick_item_up(item_id, game, game_objects);
+            }
+            DidntTakeTurn
+        }
+        Action::Craft(recipe_index) => {
+            if craft(recipe_index, game) { TookTurn } else { DidntTakeTurn }
+        }
+        Action::UseItemOn(inventory_index, target_index) => {
+            use_item(inventory_index, Some(target_index), game, game_objects);
+            DidntTakeTurn
+        }
+        Action::UseItem(inventory_index) => {
+            use_item(inventory_index, None, game, game_objects);
+            DidntTakeTurn
+        }
+        Action::Descend => {
+            let player_on_stairs = game_objects
+                .iter()
+                .any(|game_object| game_object.position() == game.player.position() && game_object.is_stairs);
+            if player_on_stairs && game.tutorial.is_some() {
+                if tutorial_on_last_step(game) {
+                    finish_tutorial("tutorial_complete", game, game_objects);
+                } else {
+                    game.messages.add(tr!(game.config.locale, "tutorial_not_yet"), MessageCategory::System);
+                }
+            } else if player_on_stairs {
+                next_level(game, game_objects);
+            }
+            DidntTakeTurn
+        }
+        Action::CloseDoor => {
+            if close_door(game, game_objects) { TookTurn } else { DidntTakeTurn }
+        }
+        Action::CastSpell => {
+            if cast_magic_missile(game, game_objects) { TookTurn } else { DidntTakeTurn }
+        }
+        Action::Look(x, y) => {
+            look_at(x, y, game, game_objects);
+            DidntTakeTurn
+        }
+        Action::ThrowAt(inventory_index, x, y) => {
+            throw_item(inventory_index, (x, y), game, game_objects);
+            TookTurn
+        }
+        Action::Move(..) | Action::Attack(_) | Action::Wait | Action::Fire(..) | Action::Cast(_) => DidntTakeTurn,
+    }
+}
+
+pub(crate) fn step(game: &mut Game, game_objects: &mut Vec<GameObject>, action: Action) -> PlayerAction {
+    use PlayerAction::*;
+
+    game.taken_actions.push(action);
+    let start = game.player.position();
+    let player_action = execute_action(Target::Player, action, game, game_objects);
+    // NOTICE: Monsters get one turn per turn the player's action costs
+    let mut turn_cost = 1;
+    if matches!(action, Action::Move(..)) && game.player.position() != start && is_encumbered(game) {
+        turn_cost = ENCUMBERED_MOVE_COST;
+    }
+
+    if game.player.is_alive && player_action == TookTurn {
+        game.turn += 1;
+        record_hp(game, game.player.fighter.map_or(0, |fighter| fighter.hp));
+        if game.player.has_perk(Perk::Quick) && game.rng.gen::<f32>() < QUICK_CHANCE {
+            turn_cost -= 1;
+        }
+        tick_food(game, game_objects);
+        tick_hunger(game);
+        if game.turn.is_multiple_of(MANA_REGEN_TURNS) {
+            if let Some(ref mut caster) = game.player.caster {
+                caster.mana = cmp::min(caster.mana + 1, caster.max_mana);
+            }
+        }
+        compute_fov(game, game_objects);
+        dispatch_events(game, game_objects);
+        // NOTICE: In co-op the monsters wait until the partner acted too, see `partner_step`
+        if game.partner.as_ref().is_some_and(|partner| partner.is_alive) {
+            game.partner_turn = Some(turn_cost);
+        } else {
+            monsters_turn(turn_cost, game, game_objects);
+        }
+    }
+    dispatch_events(game, game_objects);
+
+    player_action
+}
+
+pub(crate) fn monsters_turn(turn_cost: u32, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let ai_start = Instant::now();
+    // NOTICE: Statuses run on game turns, a slow move gives the monsters extra turns but burns only once
+    tick_status_effects(game, game_objects);
+    for _ in 0..turn_cost {
+        terrain_damage(game, game_objects);
+        dispatch_events(game, game_objects);
+        for id in 0..game_objects.len() {
+            if game_objects[id].ai.is_some() && game.player.is_alive {
+                turn_abilities(id, game, game_objects);
+                ai_take_turn(id, game, game_objects);
+                dispatch_events(game, game_objects);
+            }
+        }
+    }
+    game.metrics.ai_time = ai_start.elapsed();
+    announce_uniques(game, game_objects);
+    if game.arena.is_some() && game.player.is_alive {
+        arena_step(game, game_objects);
+    }
+}
+
+pub(crate) fn next_level(game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let level = game.dungeon_level + 1;
+    go_to_level(level, game, game_objects);
+}
+
+/// Leaves the current level for a fresh one at the given depth, which need not be the next one down
+pub(crate) fn go_to_level(level: u32, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    game.messages.add(
+        tr!(game.config.locale, "rest"),
+        MessageCategory::System,
+    );
+    let heal_hp = game.player.fighter.map_or(0, |f| f.max_hp / 2);
+    game.player.heal(heal_hp);
+
+    game.messages.add(
+        tr!(game.config.locale, "descend"),
+        MessageCategory::System,
+    );
+    #[cfg(feature = "window")]
+    {
+        game.activity = None;
+    }
+    leave_level(game);
+    game.dungeon_level = level;
+    game.events.push(GameEvent::LevelChanged { level: game.dungeon_level });
+    let mut rng = level_rng(game.seed, game.dungeon_level);
+    game.map = make_map(&mut game.player, game_objects, &game.config, &mut rng);
+    place_partner(game, game_objects);
+    populate_level(game, game_objects, &mut rng);
+    initialise_fov(game, game_objects);
+    announce_uniques(game, game_objects);
+}
+
+/// Rolls the affixes and the unique of the monsters of a freshly generated level, with the RNG it was generated with
+pub(crate) fn populate_level(game: &mut Game, game_objects: &mut [GameObject], rng: &mut XorShiftRng) {
+    apply_affixes(game.dungeon_level, &game.config, rng, game_objects);
+    place_unique(game, game_objects, rng);
+}
+
+/// Moves the current map, its notes and remembered objects to the past levels before the next one replaces them
+fn leave_level(game: &mut Game) {
+    game.past_levels.push(PastLevel {
+        dungeon_level: game.dungeon_level,
+        map: game.map.clone(),
+        notes: std::mem::take(&mut game.notes),
+        remembered: std::mem::take(&mut game.remembered),
+    });
+}
+
+/// Puts the partner next to the player, or on the player's own tile when there is no room around
+pub(crate) fn place_partner(game: &mut Game, game_objects: &[GameObject]) {
+    let mut partner = match game.partner.take() {
+        Some(partner) => partner,
+        None => return,
+    };
+    let (x, y) = game.player.position();
+    let free = (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+        .find(|&(x, y)| !blocks_movement(x, y, partner.movement(), game, game_objects));
+    let (x, y) = free.unwrap_or((x, y));
+    partner.set_position(x, y);
+    game.partner = Some(partner);
+}
+
+pub(crate) fn new_game(player_name: &str, class: Class, config: &Config) -> (Game, Vec<GameObject>) {
+    let mut player = new_player();
+    apply_class(class, &mut player);
+    let mut game_objects = vec![];
+
+    let seed = config.seed.unwrap_or_else(rand::random);
+    let mut level_rng = level_rng(seed, 1);
+
+    let mut game = Game {
+        player_name: player_name.into(),
+        map: make_map(&mut player, &mut game_objects, config, &mut level_rng),
+        player,
+        partner: None,
+        partner_turn: None,
+        messages: Messages::new(),
+        inventory: class.starting_inventory(config),
+        class,
+        skills: class.starting_skills(),
+        pending_perks: 0,
+        satiety: FULL_SATIETY,
+        components: BTreeMap::new(),
+        dungeon_level: 1,
+        turn: 0,
+        stats: Stats {
+            deepest_level: 1,
+            ..Default::default()
+        },
+        fov: new_fov_map(),
+        config: config.clone(),
+        seed,
+        rng: GameRng::new(seed),
+        sounds: vec![],
+        #[cfg(feature = "window")]
+        activity: None,
+        shake: 0,
+        remembered: vec![],
+        lights: vec![],
+        notes: vec![],
+        past_levels: vec![],
+        hp_history: vec![],
+        hp_warnings: 0,
+        killer: None,
+        final_blow: None,
+        #[cfg(any(feature = "window", feature = "terminal"))]
+        leaderboard_submission: None,
+        daily: None,
+        arena: None,
+        tutorial: None,
+        metrics: Metrics::default(),
+        #[cfg(feature = "window")]
+        snapshots: VecDeque::new(),
+        taken_actions: vec![],
+        #[cfg(any(feature = "window", feature = "terminal"))]
+        changed_outside_step: false,
+        next_entity_id: 0,
+        #[cfg(any(feature = "window", feature = "terminal"))]
+        inventory_sort: InventorySort::default(),
+        quickslots: [None; QUICKSLOT_COUNT],
+        projectiles: vec![],
+        events: vec![],
+        spawned_uniques: vec![],
+    };
+
+    game.messages.add(
+        tr!(game.config.locale, "welcome"),
+        MessageCategory::System,
+    );
+
+    populate_level(&mut game, &mut game_objects, &mut level_rng);
+    initialise_fov(&mut game, &game_objects);
+
+    (game, game_objects)
+}
+
+/// Objects get their id on the first frame they are around, whatever spawned them
+pub(crate) fn assign_entity_ids(game: &mut Game, game_objects: &mut [GameObject]) {
+    for game_object in game_objects.iter_mut().filter(|game_object| game_object.id.is_none()) {
+        game_object.id = Some(EntityId(game.next_entity_id));
+        game.next_entity_id += 1;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::combat::StatusEffect;
+    use crate::map::Tile;
+    use crate::monsters::new_monster_of;
+
+    /// A small room without walls, the player at (2, 2) and an orc at `orc`
+    pub(crate) fn open_room(orc: (i32, i32)) -> (Game, Vec<GameObject>) {
+        let config = Config { seed: Some(7), ..Config::default() };
+        let (mut game, _) = new_game("Tester", Class::default(), &config);
+        game.map = Map::new(12, 8, Tile::empty());
+        game.remembered.clear();
+        game.inventory.clear();
+        game.player.perks.clear();
+        game.player.set_position(2, 2);
+        let game_objects = vec![new_monster_of("orc", orc.0, orc.1, &config)];
+        initialise_fov(&mut game, &game_objects);
+        (game, game_objects)
+    }
+
+    #[test]
+    fn slow_moves_tick_statuses_once() {
+        let (mut game, mut game_objects) = open_room((10, 6));
+        game.player.statuses.push(StatusEffect::Poisoned { turns: 5 });
+
+        monsters_turn(ENCUMBERED_MOVE_COST, &mut game, &mut game_objects);
+        assert!(matches!(game.player.statuses[..], [StatusEffect::Poisoned { turns: 4 }]));
+    }
+}
@@ -0,0 +1,34 @@
+use super::*;
+use std::error::Error;
+use std::path::PathBuf;
+use crate::net::submit_run;
+use crate::save::{Replay, delete_slot, record_daily_score, save_replay, write_morgue};
+
+impl MessageCategory {
+    pub fn color(self) -> Color {
+        match self {
+            MessageCategory::Combat => WHITE,
+            MessageCategory::Item => GREEN,
+            MessageCategory::System => LIGHT_GREY,
+            MessageCategory::Danger => RED,
+        }
+    }
+}
+
+/// Files away a run that just ended in death, returning where its morgue file went
+pub(crate) fn record_death(game: &mut Game, replay: &mut Replay) -> Result<PathBuf, Box<dyn Error>> {
+    let morgue = write_morgue(game);
+    if let Err(error) = save_replay(&game.player_name, replay) {
+        game.messages.add(tr!(game.config.locale, "replay_save_failed", error = error), MessageCategory::Danger);
+    }
+    if game.daily.is_some() {
+        if let Err(error) = record_daily_score(game) {
+            game.messages.add(tr!(game.config.locale, "daily_score_failed", error = error), MessageCategory::Danger);
+        }
+        delete_slot(&game.player_name);
+    }
+    if let Some(url) = game.config.leaderboard_url.clone().filter(|_| game.tutorial.is_none()) {
+        game.leaderboard_submission = Some(submit_run(&url, game));
+    }
+    morgue
+}
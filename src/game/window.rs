@@ -0,0 +1,321 @@
+use super::*;
+use tcod::input;
+use tcod::console::*;
+use std::{iter, thread};
+use crate::audio::MusicTrack;
+use crate::bot::Observation;
+use crate::character::choose_perk;
+use crate::config::{Locale, content_modified, reload_changed_content};
+use crate::controls::{handle_keys, poll_input};
+use crate::map::view_position;
+use crate::narration::narrate_turn;
+use crate::net::{Broadcast, partner_input, partner_left, sync_partner};
+use crate::portable::KeyCode;
+use crate::save::{
+    ReplayRecorder, ansi_screen, autosave, autosave_due, load_replay, save_replay, slot_dir, take_snapshot,
+};
+use crate::tutorial::advance_tutorial;
+use crate::ui::{
+    LOW_HP_WARNINGS, SCREEN_WIDTH, Tcod, death_recap, escape_menu, hp_percent, render_all, show_level_banner,
+};
+
+const UNFOCUSED_FPS: i32 = 2;
+const PROJECTILE_STEP_MS: u64 = 40;
+
+impl MessageCategory {
+    pub(crate) const ALL: [MessageCategory; 4] = [
+        MessageCategory::Combat,
+        MessageCategory::Item,
+        MessageCategory::System,
+        MessageCategory::Danger,
+    ];
+
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            MessageCategory::Combat => tr!(locale, "category_combat"),
+            MessageCategory::Item => tr!(locale, "category_item"),
+            MessageCategory::System => tr!(locale, "category_system"),
+            MessageCategory::Danger => tr!(locale, "category_danger"),
+        }
+    }
+}
+
+impl Messages {
+    pub fn added(&self) -> u64 {
+        self.added
+    }
+
+    /// Texts of the messages added after the first `seen` ones, once per repeat, oldest first
+    pub fn added_since(&self, seen: u64) -> Vec<&str> {
+        let mut left = self.added.saturating_sub(seen);
+        let mut texts = vec![];
+        for message in self.messages.iter().rev() {
+            if left == 0 {
+                break;
+            }
+            let repeats = cmp::min(u64::from(message.count), left);
+            texts.extend(iter::repeat_n(message.text.as_str(), repeats as usize));
+            left -= repeats;
+        }
+        texts.reverse();
+        texts
+    }
+
+    pub fn danger_count(&self) -> u32 {
+        self.messages
+            .iter()
+            .filter(|message| message.category == MessageCategory::Danger)
+            .map(|message| message.count)
+            .sum()
+    }
+}
+
+/// The partner's half of a co-op turn, the monsters get theirs once it is done
+pub(crate) fn partner_step(game: &mut Game, game_objects: &mut Vec<GameObject>, action: Action) -> PlayerAction {
+    let partner_action = execute_action(Target::Partner, action, game, game_objects);
+    if partner_action == PlayerAction::TookTurn {
+        if let Some(turn_cost) = game.partner_turn.take() {
+            monsters_turn(turn_cost, game, game_objects);
+        }
+    }
+    dispatch_events(game, game_objects);
+    partner_action
+}
+
+fn animate_projectiles(tcod: &mut Tcod, game: &mut Game, game_objects: &[GameObject]) {
+    let projectiles = std::mem::take(&mut game.projectiles);
+    if !tcod.screen_motion {
+        return;
+    }
+    for projectile in projectiles {
+        for &tile in projectile.path.iter().filter(|&&(x, y)| game.fov.is_in_fov(x, y)) {
+            let (x, y) = match view_position(tile, game) {
+                Some(position) => position,
+                None => continue,
+            };
+            tcod.panel.set_default_background(BLACK);
+            tcod.panel.clear();
+            let shown = Instant::now();
+            render_all(tcod, game, game_objects);
+            tcod.root.set_default_foreground(tcod.palette.color(projectile.color));
+            tcod.root.put_char(x, y, projectile.glyph, BackgroundFlag::None);
+            tcod.root.flush();
+            if let Some(left) = Duration::from_millis(PROJECTILE_STEP_MS).checked_sub(shown.elapsed()) {
+                thread::sleep(left);
+            }
+        }
+    }
+}
+
+/// Where to go once a game ends, picked on the game-over screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum GameOver {
+    MainMenu,
+    NewGame,
+    Quit,
+}
+
+pub(crate) fn play_game(tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) -> GameOver {
+    let mut outcome = GameOver::MainMenu;
+    let mut narrated_messages = game.messages.added().saturating_sub(1);
+    narrate_turn(tcod, game, game_objects, &mut narrated_messages, true);
+
+    let mut content_modified = content_modified();
+    let mut redraw = true;
+    if game.config.wizard {
+        take_snapshot(game, game_objects);
+    }
+    // NOTICE: Loaded games carry on with the replay of their slot, new ones start their own
+    let replay = if game.turn == 0 { None } else { load_replay(&game.player_name) };
+    let mut recorder = ReplayRecorder::new(replay.unwrap_or_default());
+    recorder.keyframe(game, game_objects);
+    show_level_banner(tcod, game);
+    let mut partner_synced = false;
+    let mut broadcast_turn = None;
+    let mut exported_turn = None;
+    while !tcod.root.window_closed() {
+        // NOTICE: The options menu only reaches the front end, the game's config follows its movement keys & palette
+        game.config.movement_keys = tcod.movement_keys;
+        game.config.palette = tcod.palette.kind;
+        // NOTICE: A co-op save loaded without a partner connected, or one whose partner dropped, goes on solo
+        if game.partner.is_some() && !tcod.coop.as_ref().is_some_and(|link| link.connected) {
+            tcod.coop = None;
+            partner_left(game, game_objects);
+            redraw = true;
+        }
+        if game.config.wizard {
+            reload_changed_content(&mut content_modified, game);
+        }
+        if !tcod.root.has_focus() {
+            wait_for_focus(tcod, &game.config);
+            redraw = true;
+            continue;
+        }
+        tcod.audio.play_music(MusicTrack::for_level(game.dungeon_level));
+        tcod.audio.update_music(tcod::system::get_last_frame_length());
+
+        // NOTICE: Input, then the turn simulation, then rendering only if something changed or is animating
+        let input_received = poll_input(tcod);
+        let key_pressed = tcod.key.code != KeyCode::NoKey;
+
+        let dungeon_level = game.dungeon_level;
+        let player_was_alive = game.player.is_alive;
+        assign_entity_ids(game, game_objects);
+        let player_action = if game.partner_turn.is_some() {
+            partner_input(tcod, game, game_objects)
+        } else {
+            handle_keys(tcod, game, game_objects)
+        };
+        // NOTICE: Console commands change the game outside of `step`, their events still need handling
+        dispatch_events(game, game_objects);
+        advance_tutorial(game, game_objects);
+        for sound in game.sounds.drain(..) {
+            tcod.audio.play(sound);
+        }
+        let shake = std::mem::take(&mut game.shake);
+        if tcod.screen_motion && shake > 0 {
+            tcod.shake_end = Some(Instant::now() + Duration::from_millis(u64::from(shake)));
+        }
+        animate_projectiles(tcod, game, game_objects);
+        while game.pending_perks > 0 && game.player.is_alive && !tcod.root.window_closed() {
+            render_all(tcod, game, game_objects);
+            choose_perk(tcod, game);
+        }
+        // NOTICE: The partner's actions never go through `step`, co-op turns are left out of the replay
+        if player_was_alive && game.partner.is_some() {
+            game.taken_actions.clear();
+        } else if player_was_alive {
+            recorder.record(game, game_objects);
+        }
+
+        if player_was_alive && !game.player.is_alive {
+            let morgue = record_death(game, &mut recorder.replay);
+            outcome = death_recap(tcod, game, &morgue, &recorder.replay);
+            break;
+        }
+
+        if player_action == PlayerAction::Exit {
+            if let Some(choice) = escape_menu(tcod, game, game_objects) {
+                outcome = choice;
+                break;
+            }
+            redraw = true;
+            continue;
+        }
+
+        if game.dungeon_level != dungeon_level {
+            show_level_banner(tcod, game);
+        }
+        let describe = player_action == PlayerAction::TookTurn || game.dungeon_level != dungeon_level;
+        narrate_turn(tcod, game, game_objects, &mut narrated_messages, describe);
+        if tcod.coop.is_some() && (!partner_synced || player_action != PlayerAction::DidntTakeTurn || key_pressed) {
+            sync_partner(tcod, game, game_objects);
+            partner_synced = true;
+        }
+        if let Some(ref mut state_export) = tcod.state_export {
+            if state_export.accept() || exported_turn != Some(game.turn) {
+                exported_turn = Some(game.turn);
+                if let Err(error) = state_export.write(&Observation::new(game, game_objects)) {
+                    game.messages.add(tr!(game.config.locale, "state_export_failed", error = error), MessageCategory::Danger);
+                    tcod.state_export = None;
+                }
+            }
+        }
+
+        if game.config.wizard && player_action == PlayerAction::TookTurn {
+            take_snapshot(game, game_objects);
+        }
+        let autosave_due = player_action == PlayerAction::TookTurn && autosave_due(game);
+        if game.player.is_alive && (autosave_due || game.dungeon_level != dungeon_level) {
+            if let Err(error) = autosave(game, game_objects).and_then(|()| save_replay(&game.player_name, &mut recorder.replay)) {
+                game.messages.add(tr!(game.config.locale, "autosave_failed", error = error), MessageCategory::Danger);
+            }
+        }
+
+        if tcod.broadcast.as_mut().is_some_and(Broadcast::accept) {
+            broadcast_turn = None;
+            redraw = true;
+        }
+        redraw |= input_received || player_action != PlayerAction::DidntTakeTurn || is_animating(tcod, game);
+        if redraw {
+            // NOTICE: The panel flashes for as long as the map shakes
+            let panel_background = if tcod.shake_end.is_some() { DARKER_RED } else { BLACK };
+            tcod.panel.set_default_background(panel_background);
+            tcod.panel.clear();
+
+            let frame_start = Instant::now();
+            render_all(tcod, game, game_objects);
+            if game.partner_turn.is_some() {
+                tcod.root.set_default_foreground(WHITE);
+                let text = tr!(game.config.locale, "coop_partner_turn");
+                tcod.root.print_ex(SCREEN_WIDTH / 2, 0, BackgroundFlag::None, TextAlignment::Center, text);
+            }
+            tcod.root.flush();
+            tcod.render_time = frame_start.elapsed();
+            // NOTICE: Spectators get the screen once per turn, and right away when they join
+            if let Some(ref mut broadcast) = tcod.broadcast {
+                if broadcast_turn != Some(game.turn) {
+                    broadcast.send(&ansi_screen(&tcod.root));
+                    broadcast_turn = Some(game.turn);
+                }
+            }
+            redraw = false;
+        } else {
+            // NOTICE: Flushing is what usually caps the frame rate, so idle frames sleep instead
+            thread::sleep(Duration::from_secs(1) / tcod.fps_limit.max(1) as u32);
+        }
+    }
+
+    // NOTICE: Abandoned and finished daily runs have no slot left to keep the replay in
+    if game.player.is_alive && slot_dir(&game.player_name).exists() {
+        if let Err(error) = save_replay(&game.player_name, &mut recorder.replay) {
+            eprintln!("Replay could not be saved: {}", error);
+        }
+    }
+    tcod.audio.stop_music();
+    outcome
+}
+
+/// Whether the next frames differ even without any input
+fn is_animating(tcod: &Tcod, game: &Game) -> bool {
+    let (lowest, _) = LOW_HP_WARNINGS[LOW_HP_WARNINGS.len() - 1];
+    let low_hp_blinking = tcod.screen_motion && game.player.is_alive && hp_percent(game) < lowest;
+    tcod.shake_end.is_some() || tcod.banner.is_some() || tcod.debug_overlay || game.activity.is_some() || low_hp_blinking
+}
+
+// NOTICE: Nothing runs while the window is out of focus, and the keys pressed to switch back are dropped
+fn wait_for_focus(tcod: &mut Tcod, config: &Config) {
+    tcod::system::set_fps(UNFOCUSED_FPS);
+    tcod.root.set_default_foreground(WHITE);
+    tcod.root.print_ex(SCREEN_WIDTH / 2, 0, BackgroundFlag::None, TextAlignment::Center, tr!(config.locale, "focus_paused"));
+    while !tcod.root.has_focus() && !tcod.root.window_closed() {
+        tcod.root.flush();
+        while input::check_for_event(input::MOUSE | input::KEY_PRESS).is_some() {}
+    }
+    while input::check_for_event(input::MOUSE | input::KEY_PRESS).is_some() {}
+    tcod.key = Default::default();
+    tcod.input_queue.clear();
+    tcod.held_key = None;
+    tcod::system::set_fps(frame_limit(tcod.fps_limit, tcod.vsync));
+}
+
+/// The frame rate libtcod caps flushes at, none when vsync already paces them
+pub(crate) fn frame_limit(fps_limit: i32, vsync: bool) -> i32 {
+    if vsync { 0 } else { fps_limit }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_repeats_are_still_new_messages() {
+        let mut messages = Messages::new();
+        messages.add("You hit the orc.", MessageCategory::Combat);
+        let seen = messages.added();
+        messages.add("You hit the orc.", MessageCategory::Combat);
+        messages.add("The orc dies.", MessageCategory::Combat);
+        assert_eq!(messages.iter().count(), 2);
+        assert_eq!(messages.added_since(seen), vec!["You hit the orc.", "The orc dies."]);
+    }
+}
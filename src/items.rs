@@ -0,0 +1,1203 @@
+use std::{cmp, mem};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::character::{Class, Perk, Skill, train};
+use crate::combat::{Ai, DamageType, GameEvent, StatusEffect, fire_projectile, ranged_hit};
+use crate::config::{
+    ArmorDefinition, Component, Config, CraftResult, Locale, Name, OnHit, Rarity, WeaponDefinition,
+    pick_by_rarity,
+};
+use crate::game::{Game, MessageCategory, with_player};
+use crate::map::{Decal, Terrain, leave_decal, map_size};
+use crate::object::{GameObject, SHAKE_MS};
+use crate::portable::colors::*;
+
+/// Inventory rows, their sorting, quickslots and hunger tags, for both front ends
+#[cfg(any(feature = "window", feature = "terminal"))]
+mod frontend;
+/// The window's inventory, quickslot and crafting menus, with the comparison to what is equipped
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(any(feature = "window", feature = "terminal"))]
+pub(crate) use self::frontend::*;
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+pub(crate) const QUICKSLOT_COUNT: usize = 5;
+const LIGHTNING_RANGE: i32 = 5;
+const CONFUSION_RANGE: i32 = 5;
+const CONFUSE_TURN_COUNT: i32 = 10;
+const BLIZZARD_RANGE: i32 = 10;
+const BLIZZARD_TURN_COUNT: i32 = 5;
+const BLIZZARD_DAMAGE: i32 = 2;
+const BASE_CARRY_CAPACITY: i32 = 5;
+const CARRY_CAPACITY_PER_POWER: i32 = 2;
+const SEE_INVISIBLE_TURN_COUNT: i32 = 30;
+const LEVITATION_TURN_COUNT: i32 = 20;
+const MAGIC_MISSILE_COST: i32 = 4;
+const MAGIC_MISSILE_DAMAGE: i32 = 6;
+const MAGIC_MISSILE_RANGE: i32 = 6;
+const SCROLL_LORE_PRACTICE: i32 = 4;
+const FOOD_FRESHNESS: i32 = 400;
+const FOOD_HEAL: i32 = 3;
+const COOKED_FOOD_HEAL: i32 = 6;
+const SPOILED_POISON_CHANCE: f32 = 0.6;
+const FOOD_POISON_TURNS: i32 = 8;
+pub(crate) const FULL_SATIETY: i32 = 1200;
+pub(crate) const HUNGRY_SATIETY: i32 = 300;
+pub(crate) const FOOD_SATIETY: i32 = 500;
+const SPOILED_FOOD_SATIETY: i32 = 250;
+pub(crate) const STARVATION_TURNS: i32 = 10;
+pub(crate) const STARVATION_DAMAGE: i32 = 1;
+const WEAPON_BREAK_CHANCE: f32 = 0.1;
+const REPAIR_KIT_AMOUNT: i32 = 30;
+const SAFE_ENCHANTMENT: i32 = 2;
+const MAX_SOCKETS: usize = 2;
+const SOCKET_CHANCE: f32 = 0.3;
+pub(crate) const RUBY_FIRE_DAMAGE: i32 = 2;
+const ARTIFACT_CHANCE: f32 = 0.05;
+const ARTIFACT_NAME_STARTS: [&str; 10] = ["Grim", "Storm", "Dusk", "Ash", "Frost", "Blood", "Sun", "Night", "Iron", "Wyrm"];
+const ARTIFACT_NAME_ENDS: [&str; 10] = ["fang", "bane", "song", "brand", "ward", "reaver", "caller", "heart", "shard", "wake"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Item {
+    Heal,
+    ScrollOfLightning,
+    ScrollOfConfusion,
+    ScrollOfBlizzard,
+    Weapon,
+    SeeInvisible,
+    RingOfDetection,
+    Levitation,
+    Key,
+    Food,
+    Component(Component),
+    Dart,
+    RepairKit,
+    Armor,
+    EnchantWeapon,
+    EnchantArmor,
+    Gem(Gem),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Slot {
+    #[default]
+    Weapon,
+    Ring,
+    Armor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Equipment {
+    #[serde(default)]
+    pub(crate) slot: Slot,
+    pub(crate) equipped: bool,
+    power_bonus: i32,
+    pub(crate) weight: i32,
+    pub(crate) on_hit: Option<OnHit>,
+    #[serde(default)]
+    pub(crate) durability: i32,
+    /// Zero for equipment that never wears out
+    #[serde(default)]
+    pub(crate) max_durability: i32,
+    #[serde(default)]
+    defense_bonus: i32,
+    #[serde(default)]
+    pub(crate) enchantment: i32,
+    #[serde(default)]
+    sockets: usize,
+    #[serde(default)]
+    pub(crate) gems: [Option<Gem>; MAX_SOCKETS],
+    #[serde(default)]
+    pub(crate) rarity: Rarity,
+}
+
+// NOTICE: Gems grant their bonus for as long as the item they are socketed in is equipped
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Gem {
+    Ruby,
+    Diamond,
+    Onyx,
+}
+
+impl Gem {
+    pub(crate) const ALL: [Gem; 3] = [Gem::Ruby, Gem::Diamond, Gem::Onyx];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Gem::Ruby => "ruby",
+            Gem::Diamond => "diamond",
+            Gem::Onyx => "onyx",
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        tr!(locale, self.key())
+    }
+
+    pub fn bonus(self) -> Name {
+        match self {
+            Gem::Ruby => Name::key("ruby_bonus").with("damage", Name::Text(RUBY_FIRE_DAMAGE.to_string())),
+            Gem::Diamond => Name::key("diamond_bonus"),
+            Gem::Onyx => Name::key("onyx_bonus"),
+        }
+    }
+}
+
+/// How many of `gem` are socketed in the equipped items
+pub(crate) fn gem_bonus(inventory: &[GameObject], gem: Gem) -> i32 {
+    inventory
+        .iter()
+        .filter_map(|game_object| game_object.equipment)
+        .filter(|equipment| equipment.equipped)
+        .flat_map(|equipment| equipment.gems)
+        .filter(|&socketed| socketed == Some(gem))
+        .count() as i32
+}
+
+pub(crate) fn new_gem(x: i32, y: i32, gem: Gem) -> GameObject {
+    let color = match gem {
+        Gem::Ruby => LIGHT_RED,
+        Gem::Diamond => WHITE,
+        Gem::Onyx => DARK_GREY,
+    };
+    let mut game_object = GameObject::new(x, y, '*', color, gem.key(), false);
+    game_object.item = Some(Item::Gem(gem));
+    game_object.description = Name::key("gem_description").with("bonus", gem.bonus());
+    game_object
+}
+
+impl Equipment {
+    pub fn free_socket(&self) -> Option<usize> {
+        self.gems.iter().take(self.sockets).position(Option::is_none)
+    }
+
+    pub fn is_worn_out(&self) -> bool {
+        self.max_durability > 0 && self.durability == 0
+    }
+
+    /// Worn out weapons only hit half as hard and lose their special effect, worn out armor protects half as well
+    pub fn effective_power_bonus(&self) -> i32 {
+        let bonus = if self.slot == Slot::Weapon { self.power_bonus + self.enchantment } else { self.power_bonus };
+        if self.is_worn_out() { bonus / 2 } else { bonus }
+    }
+
+    pub fn effective_defense_bonus(&self) -> i32 {
+        let bonus = if self.slot == Slot::Armor { self.defense_bonus + self.enchantment } else { self.defense_bonus };
+        if self.is_worn_out() { bonus / 2 } else { bonus }
+    }
+
+    pub fn effective_on_hit(&self) -> Option<OnHit> {
+        if self.is_worn_out() { None } else { self.on_hit }
+    }
+}
+
+/// Weapons wear out when they hit, armor when the player is hit
+pub(crate) fn wear_equipment(slot: Slot, game: &mut Game) {
+    let index = game
+        .inventory
+        .iter()
+        .position(|item| item.equipment.is_some_and(|e| e.equipped && e.slot == slot && e.max_durability > 0));
+    let index = match index {
+        Some(index) => index,
+        None => return,
+    };
+    let name = game.inventory[index].display_name(&game.config.locale);
+    if let Some(ref mut equipment) = game.inventory[index].equipment {
+        if equipment.durability > 0 {
+            equipment.durability -= 1;
+            if equipment.durability == 0 {
+                game.messages.add(tr!(game.config.locale, "equipment_worn_out", item = name), MessageCategory::Danger);
+            }
+            return;
+        }
+    }
+    if game.rng.gen::<f32>() < WEAPON_BREAK_CHANCE {
+        game.inventory.remove(index);
+        game.messages.add(tr!(game.config.locale, "equipment_broke", item = name), MessageCategory::Danger);
+    }
+}
+
+pub(crate) fn equipped_weapon(inventory: &[GameObject]) -> Option<Equipment> {
+    inventory
+        .iter()
+        .filter_map(|game_object| game_object.equipment)
+        .find(|equipment| equipment.equipped && equipment.slot == Slot::Weapon)
+}
+
+/// Defense the player gets from their armor and the diamonds set in their equipment
+pub(crate) fn worn_defense_bonus(inventory: &[GameObject]) -> i32 {
+    equipped_armor(inventory).map_or(0, |armor| armor.effective_defense_bonus()) + gem_bonus(inventory, Gem::Diamond)
+}
+
+fn equipped_armor(inventory: &[GameObject]) -> Option<Equipment> {
+    inventory
+        .iter()
+        .filter_map(|game_object| game_object.equipment)
+        .find(|equipment| equipment.equipped && equipment.slot == Slot::Armor)
+}
+
+pub(crate) fn carried_weight(inventory: &[GameObject], config: &Config) -> i32 {
+    inventory.iter().map(|item| item.weight(config)).sum()
+}
+
+pub(crate) fn carry_capacity(player: &GameObject) -> i32 {
+    BASE_CARRY_CAPACITY + player.fighter.map_or(0, |f| f.power) * CARRY_CAPACITY_PER_POWER
+}
+
+pub(crate) fn is_encumbered(game: &Game) -> bool {
+    carried_weight(&game.inventory, &game.config) > carry_capacity(&game.player)
+}
+
+enum UseResult {
+    UsedUp,
+    UsedAndKept,
+    Cancelled,
+}
+
+pub(crate) fn pick_item_up(object_id: usize, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    if let Some(Item::Component(component)) = game_objects[object_id].item {
+        let item = game_objects.swap_remove(object_id);
+        *game.components.entry(component).or_insert(0) += 1;
+        game.events.push(GameEvent::ItemPickedUp { name: item.display_name(&game.config.locale), weight: 0 });
+    } else if game.inventory.len() >= 9 {
+        game.messages.add(
+            tr!(game.config.locale, "inventory_full", item = game_objects[object_id].display_name(&game.config.locale)),
+            MessageCategory::Item,
+        );
+    } else {
+        let item = game_objects.swap_remove(object_id);
+        game.events.push(GameEvent::ItemPickedUp { name: item.display_name(&game.config.locale), weight: item.weight(&game.config) });
+        game.inventory.push(item);
+    }
+}
+
+pub(crate) fn new_consumable(x: i32, y: i32, item: Item) -> GameObject {
+    let (glyph, color, name) = match item {
+        Item::ScrollOfLightning => ('~', LIGHT_YELLOW, "scroll_of_lightning"),
+        Item::SeeInvisible => ('!', LIGHT_CYAN, "potion_of_see_invisible"),
+        Item::Levitation => ('!', LIGHT_AZURE, "potion_of_levitation"),
+        Item::ScrollOfConfusion => ('c', LIGHT_YELLOW, "scroll_of_confusion"),
+        Item::ScrollOfBlizzard => ('b', LIGHT_YELLOW, "scroll_of_blizzard"),
+        Item::RepairKit => ('(', LIGHT_ORANGE, "repair_kit"),
+        Item::EnchantWeapon => ('?', LIGHT_MAGENTA, "scroll_of_enchant_weapon"),
+        Item::EnchantArmor => ('?', LIGHT_MAGENTA, "scroll_of_enchant_armor"),
+        _ => ('!', VIOLET, "healing_potion"),
+    };
+    let mut game_object = GameObject::new(x, y, glyph, color, name, false);
+    game_object.item = Some(item);
+    game_object.description = Name::key(&format!("{}_description", name));
+    game_object
+}
+
+pub(crate) fn new_ring_of_detection(x: i32, y: i32) -> GameObject {
+    let mut game_object = GameObject::new(x, y, '=', GOLD, "ring_of_detection", false);
+    game_object.item = Some(Item::RingOfDetection);
+    game_object.equipment = Some(Equipment {
+        slot: Slot::Ring,
+        equipped: false,
+        power_bonus: 0,
+        weight: 0,
+        on_hit: None,
+        durability: 0,
+        max_durability: 0,
+        defense_bonus: 0,
+        enchantment: 0,
+        sockets: 0,
+        gems: [None; MAX_SOCKETS],
+        rarity: Rarity::Common,
+    });
+    game_object.description = Name::key("ring_of_detection_description");
+    game_object
+}
+
+pub(crate) fn new_component(x: i32, y: i32, component: Component) -> GameObject {
+    let color = match component {
+        Component::Herb => LIGHT_GREEN,
+        Component::Bone => LIGHTEST_GREY,
+        Component::Shard => LIGHT_MAGENTA,
+    };
+    let mut game_object = GameObject::new(x, y, '&', color, component.key(), false);
+    game_object.item = Some(Item::Component(component));
+    game_object.description = Name::key("component_description");
+    game_object
+}
+
+pub(crate) fn new_dart(x: i32, y: i32) -> GameObject {
+    let mut game_object = GameObject::new(x, y, '/', LIGHTEST_GREY, "dart", false);
+    game_object.item = Some(Item::Dart);
+    game_object.description = Name::key("dart_description");
+    game_object
+}
+
+pub(crate) fn new_food(x: i32, y: i32) -> GameObject {
+    let mut game_object = GameObject::new(x, y, '%', LIGHT_SEPIA, "ration", false);
+    game_object.item = Some(Item::Food);
+    game_object.freshness = Some(FOOD_FRESHNESS);
+    game_object.description = Name::key("ration_description");
+    game_object
+}
+
+pub(crate) fn new_weapon(x: i32, y: i32, weapon: &WeaponDefinition) -> GameObject {
+    let mut game_object = GameObject::new(x, y, weapon.glyph, weapon.color, &weapon.name, false);
+    game_object.item = Some(Item::Weapon);
+    game_object.equipment = Some(Equipment {
+        slot: Slot::Weapon,
+        equipped: false,
+        power_bonus: weapon.power_bonus,
+        weight: weapon.weight,
+        on_hit: weapon.on_hit,
+        durability: weapon.durability,
+        max_durability: weapon.durability,
+        defense_bonus: 0,
+        enchantment: 0,
+        sockets: 0,
+        gems: [None; MAX_SOCKETS],
+        rarity: weapon.rarity,
+    });
+    game_object.color = weapon.rarity.color().unwrap_or(weapon.color);
+    game_object.description = Name::key(&format!("{}_description", weapon.name));
+    game_object
+}
+
+pub(crate) fn new_armor(x: i32, y: i32, armor: &ArmorDefinition) -> GameObject {
+    let mut game_object = GameObject::new(x, y, armor.glyph, armor.color, &armor.name, false);
+    game_object.item = Some(Item::Armor);
+    game_object.equipment = Some(Equipment {
+        slot: Slot::Armor,
+        equipped: false,
+        power_bonus: 0,
+        weight: armor.weight,
+        on_hit: None,
+        durability: armor.durability,
+        max_durability: armor.durability,
+        defense_bonus: armor.defense_bonus,
+        enchantment: 0,
+        sockets: 0,
+        gems: [None; MAX_SOCKETS],
+        rarity: armor.rarity,
+    });
+    game_object.color = armor.rarity.color().unwrap_or(armor.color);
+    game_object.description = Name::key(&format!("{}_description", armor.name));
+    game_object
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArtifactAffix {
+    Keen,
+    Warding,
+    Sturdy,
+    Socketed,
+    Blazing,
+    Thirsting,
+}
+
+impl ArtifactAffix {
+    fn name(self) -> Name {
+        Name::key(match self {
+            ArtifactAffix::Keen => "artifact_keen",
+            ArtifactAffix::Warding => "artifact_warding",
+            ArtifactAffix::Sturdy => "artifact_sturdy",
+            ArtifactAffix::Socketed => "artifact_socketed",
+            ArtifactAffix::Blazing => "artifact_blazing",
+            ArtifactAffix::Thirsting => "artifact_thirsting",
+        })
+    }
+}
+
+/// Artifacts get a name of their own and two or three affixes on top of their base item
+fn make_artifact<R: Rng>(item: &mut GameObject, rng: &mut R) {
+    let equipment = match item.equipment.as_mut() {
+        Some(equipment) => equipment,
+        None => return,
+    };
+    let mut pool = if equipment.slot == Slot::Armor {
+        vec![ArtifactAffix::Warding, ArtifactAffix::Sturdy, ArtifactAffix::Socketed]
+    } else {
+        vec![
+            ArtifactAffix::Keen,
+            ArtifactAffix::Sturdy,
+            ArtifactAffix::Socketed,
+            ArtifactAffix::Blazing,
+            ArtifactAffix::Thirsting,
+        ]
+    };
+    let mut affixes = vec![];
+    let count = rng.gen_range(2, 4);
+    while affixes.len() < count && !pool.is_empty() {
+        let affix = pool.remove(rng.gen_range(0, pool.len()));
+        match affix {
+            ArtifactAffix::Keen => equipment.power_bonus += 1,
+            ArtifactAffix::Warding => equipment.defense_bonus += 1,
+            ArtifactAffix::Sturdy => {
+                equipment.max_durability *= 2;
+                equipment.durability = equipment.max_durability;
+            }
+            ArtifactAffix::Socketed => equipment.sockets = MAX_SOCKETS,
+            ArtifactAffix::Blazing => equipment.on_hit = Some(OnHit::Flaming),
+            ArtifactAffix::Thirsting => equipment.on_hit = Some(OnHit::Vampiric),
+        }
+        // NOTICE: A weapon has a single on-hit effect
+        if let ArtifactAffix::Blazing | ArtifactAffix::Thirsting = affix {
+            pool.retain(|other| !matches!(other, ArtifactAffix::Blazing | ArtifactAffix::Thirsting));
+        }
+        affixes.push(affix);
+    }
+    equipment.rarity = Rarity::Artifact;
+
+    let name = format!(
+        "{}{}",
+        ARTIFACT_NAME_STARTS[rng.gen_range(0, ARTIFACT_NAME_STARTS.len())],
+        ARTIFACT_NAME_ENDS[rng.gen_range(0, ARTIFACT_NAME_ENDS.len())],
+    );
+    let affixes = affixes.iter().map(|affix| affix.name()).collect();
+    item.name = Name::key("artifact_name").with("name", Name::Text(name)).with("item", mem::take(&mut item.name));
+    item.color = Rarity::Artifact.color().unwrap_or(item.color);
+    item.description = Name::key("artifact_affixes")
+        .with("description", mem::take(&mut item.description))
+        .with("affixes", Name::List(affixes));
+}
+
+pub(crate) fn new_item<R: Rng>(x: i32, y: i32, config: &Config, rng: &mut R) -> GameObject {
+    let dice = rng.gen::<f32>();
+    let mut item = if dice < 0.36 {
+        new_consumable(x, y, Item::Heal)
+    } else if dice < 0.42 {
+        let component = [Component::Herb, Component::Bone, Component::Shard][rng.gen_range(0, 3)];
+        new_component(x, y, component)
+    } else if dice < 0.5 {
+        new_food(x, y)
+    } else if dice < 0.58 {
+        new_consumable(x, y, Item::ScrollOfLightning)
+    } else if dice < 0.66 {
+        new_consumable(x, y, Item::ScrollOfConfusion)
+    } else if dice < 0.72 {
+        new_consumable(x, y, Item::SeeInvisible)
+    } else if dice < 0.76 {
+        new_consumable(x, y, Item::Levitation)
+    } else if dice < 0.79 {
+        new_ring_of_detection(x, y)
+    } else if dice < 0.82 {
+        new_consumable(x, y, Item::RepairKit)
+    } else if dice < 0.84 {
+        new_consumable(x, y, Item::EnchantWeapon)
+    } else if dice < 0.85 {
+        new_consumable(x, y, Item::EnchantArmor)
+    } else if dice < 0.87 {
+        new_gem(x, y, Gem::ALL[rng.gen_range(0, Gem::ALL.len())])
+    } else if dice >= 0.93 && !config.content.weapons.is_empty() {
+        let weapons = &config.content.weapons;
+        let rarities: Vec<Rarity> = weapons.iter().map(|weapon| weapon.rarity).collect();
+        let index = pick_by_rarity(&rarities, rng).unwrap_or_else(|| rng.gen_range(0, weapons.len()));
+        new_weapon(x, y, &weapons[index])
+    } else if dice >= 0.89 && !config.content.armor.is_empty() {
+        let armor = &config.content.armor;
+        let rarities: Vec<Rarity> = armor.iter().map(|armor| armor.rarity).collect();
+        let index = pick_by_rarity(&rarities, rng).unwrap_or_else(|| rng.gen_range(0, armor.len()));
+        new_armor(x, y, &armor[index])
+    } else {
+        new_consumable(x, y, Item::ScrollOfBlizzard)
+    };
+
+    if item.equipment.is_some_and(|e| e.slot != Slot::Ring) && rng.gen::<f32>() < ARTIFACT_CHANCE {
+        make_artifact(&mut item, rng);
+    }
+    if let Some(ref mut equipment) = item.equipment {
+        if equipment.slot != Slot::Ring && equipment.sockets == 0 && rng.gen::<f32>() < SOCKET_CHANCE {
+            equipment.sockets = rng.gen_range(1, MAX_SOCKETS + 1);
+        }
+    }
+    item
+}
+
+/// `target_id` is the inventory index of the item a scroll is read on, the first that fits when left out
+pub(crate) fn use_item(inventory_id: usize, target_id: Option<usize>, game: &mut Game, game_objects: &mut [GameObject]) {
+    use Item::*;
+
+    if let Some(item) = game.inventory[inventory_id].item {
+        let result = match item {
+            Heal => cast_heal(inventory_id, game, game_objects),
+            ScrollOfLightning => cast_lightning(inventory_id, game, game_objects),
+            ScrollOfConfusion => cast_confusion(inventory_id, game, game_objects),
+            ScrollOfBlizzard => cast_blizzard(inventory_id, game, game_objects),
+            Weapon | Armor | RingOfDetection => toggle_equipment(inventory_id, game, game_objects),
+            SeeInvisible => cast_see_invisible(inventory_id, game, game_objects),
+            Levitation => cast_levitation(inventory_id, game, game_objects),
+            Key => use_key(inventory_id, game, game_objects),
+            Food => cast_eat(inventory_id, game, game_objects),
+            Component(_) => use_component(inventory_id, game, game_objects),
+            Dart => use_dart(inventory_id, game, game_objects),
+            RepairKit => cast_repair(inventory_id, game, game_objects),
+            EnchantWeapon | EnchantArmor => cast_enchant(item, target_id, game),
+            Gem(gem) => insert_gem(item, gem, target_id, game),
+        };
+        match result {
+            UseResult::UsedUp => {
+                game.inventory.remove(inventory_id);
+                game.stats.items_used += 1;
+                if let ScrollOfLightning | ScrollOfConfusion | ScrollOfBlizzard | EnchantWeapon | EnchantArmor = item {
+                    train(Skill::Lore, SCROLL_LORE_PRACTICE, game);
+                }
+            }
+            UseResult::UsedAndKept => {}
+            UseResult::Cancelled => {
+                game.messages.add(tr!(game.config.locale, "cancelled"), MessageCategory::System);
+            }
+        }
+    } else {
+        game.messages.add(
+            tr!(game.config.locale, "cannot_use", item = game.inventory[inventory_id].display_name(&game.config.locale)),
+            MessageCategory::Item,
+        )
+    }
+}
+
+fn toggle_equipment(inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    let equipment = match game.inventory[inventory_id].equipment {
+        Some(equipment) => equipment,
+        None => return UseResult::Cancelled,
+    };
+    if equipment.equipped {
+        game.messages.add(tr!(game.config.locale, "unequipped", item = game.inventory[inventory_id].display_name(&game.config.locale)), MessageCategory::Item);
+    } else {
+        for game_object in game.inventory.iter_mut() {
+            if let Some(ref mut other) = game_object.equipment {
+                if other.slot == equipment.slot {
+                    other.equipped = false;
+                }
+            }
+        }
+        game.messages.add(tr!(game.config.locale, "equipped", item = game.inventory[inventory_id].display_name(&game.config.locale)), MessageCategory::Item);
+    }
+    if let Some(ref mut toggled) = game.inventory[inventory_id].equipment {
+        toggled.equipped = !equipment.equipped;
+    }
+    UseResult::UsedAndKept
+}
+
+fn cast_blizzard(_inventory_id: usize, game: &mut Game, game_objects: &mut [GameObject]) -> UseResult {
+    let monsters_id = find_monsters_in_radius(game, game_objects, BLIZZARD_RANGE);
+    if !monsters_id.is_empty() {
+        for id in monsters_id {
+            let old_ai = game_objects[id].ai.take().unwrap_or(Ai::Basic);
+            game_objects[id].ai = Some(Ai::Frozen {
+                previous_ai: Box::new(old_ai),
+                num_turns: BLIZZARD_TURN_COUNT + game.skills.level(Skill::Lore),
+            });
+            game_objects[id].take_damage(BLIZZARD_DAMAGE, game);
+            game.messages.add(
+                tr!(game.config.locale, "frozen", monster = game_objects[id].display_name(&game.config.locale)),
+                MessageCategory::Combat,
+            );
+        }
+        game.shake = SHAKE_MS;
+        UseResult::UsedUp 
+    } else {
+        game.messages.add(
+            tr!(game.config.locale, "no_enemy"),
+            MessageCategory::System,
+        );
+        UseResult::Cancelled 
+    }
+}
+
+fn find_monsters_in_radius(game: &Game, game_objects: &[GameObject], max_range: i32) -> Vec<usize> {
+    let mut ennemies_in_radius = vec![];
+    for (id, game_object) in game_objects.iter().enumerate() {
+        if game_object.fighter.is_some()
+            && game_object.ai.is_some()
+            && game.player.distance_to(game_object) < (max_range as f32)
+        {
+            ennemies_in_radius.push(id);
+        }
+    }
+    ennemies_in_radius
+}
+
+fn cast_confusion(_inventory_id: usize, game: &mut Game, game_objects: &mut [GameObject]) -> UseResult {
+    let monster_id = closest_monster(game, game_objects, CONFUSION_RANGE);
+    if let Some(monster_id) = monster_id {
+        let old_ai = game_objects[monster_id].ai.take().unwrap_or(Ai::Basic);
+        game_objects[monster_id].ai = Some(Ai::Confused {
+            previous_ai: Box::new(old_ai),
+            num_turns: CONFUSE_TURN_COUNT + game.skills.level(Skill::Lore),
+        });
+        game.messages.add(
+            tr!(game.config.locale, "confused", monster = game_objects[monster_id].display_name(&game.config.locale)),
+            MessageCategory::Combat,
+        );
+        UseResult::UsedUp 
+    } else {
+        game.messages.add(
+            tr!(game.config.locale, "no_enemy"),
+            MessageCategory::System,
+        );
+        UseResult::Cancelled
+    }
+}
+
+fn cast_lightning(_inventory_id: usize, game: &mut Game, game_objects: &mut [GameObject]) -> UseResult {
+    let monster_id = closest_monster(game, game_objects, LIGHTNING_RANGE);
+    if let Some(monster_id) = monster_id {
+        // NOTICE: Mages channel scrolls better than anyone else
+        let base_damage = game.config.lightning_damage + game.skills.level(Skill::Lore);
+        let damage = if game.class == Class::Mage { base_damage * 3 / 2 } else { base_damage };
+        game.messages.add(
+            tr!(
+                game.config.locale,
+                "lightning_strike",
+                monster = game_objects[monster_id].display_name(&game.config.locale),
+                damage = damage,
+            ),
+            MessageCategory::Combat,
+        );
+        game_objects[monster_id].take_damage(damage, game);
+        leave_decal(&mut game.map, game_objects[monster_id].position(), Decal::Scorch);
+        game.shake = SHAKE_MS;
+        UseResult::UsedUp
+    } else {
+        game.messages.add(
+            tr!(game.config.locale, "no_enemy"),
+            MessageCategory::System,
+        );
+        UseResult::Cancelled
+    }
+}
+
+fn cast_heal(_inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    if let Some(fighter) = game.player.fighter {
+        if fighter.hp == fighter.max_hp {
+            game.messages.add(
+                tr!(game.config.locale, "full_health"),
+                MessageCategory::Item,
+            );
+
+            return UseResult::Cancelled;
+        } else {
+            game.messages.add(
+                tr!(game.config.locale, "healed"),
+                MessageCategory::Item
+            );
+            let amount = if game.player.has_perk(Perk::Medic) {
+                game.config.heal_amount * 3 / 2
+            } else {
+                game.config.heal_amount
+            };
+            game.player.heal(amount);
+            return UseResult::UsedUp;
+        }
+    }
+    UseResult::Cancelled
+}
+
+// NOTICE: Food feeds the hunger clock and mends wounds, it goes bad if kept too long
+fn cast_eat(inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    let food = game.inventory[inventory_id].display_name(&game.config.locale);
+    let spoiled = game.inventory[inventory_id].freshness == Some(0);
+    let (player_x, player_y) = game.player.position();
+    let (map_width, map_height) = map_size(&game.map);
+    let at_campfire = (player_x - 1..=player_x + 1)
+        .flat_map(|x| (player_y - 1..=player_y + 1).map(move |y| (x, y)))
+        .filter(|&(x, y)| x >= 0 && y >= 0 && x < map_width && y < map_height)
+        .any(|(x, y)| game.map[(x, y)].terrain == Terrain::Campfire);
+
+    if at_campfire {
+        game.messages.add(tr!(game.config.locale, "food_cooked", food = food), MessageCategory::Item);
+        game.player.heal(COOKED_FOOD_HEAL);
+        feed(game, FOOD_SATIETY);
+        return UseResult::UsedUp;
+    }
+    game.messages.add(tr!(game.config.locale, "food_eaten", food = food), MessageCategory::Item);
+    game.player.heal(FOOD_HEAL);
+    feed(game, if spoiled { SPOILED_FOOD_SATIETY } else { FOOD_SATIETY });
+    let chance = if game.player.has_perk(Perk::KeenNose) {
+        SPOILED_POISON_CHANCE / 2.0
+    } else {
+        SPOILED_POISON_CHANCE
+    };
+    if spoiled && game.rng.gen::<f32>() < chance {
+        let statuses = &mut game.player.statuses;
+        statuses.retain(|status| !matches!(status, StatusEffect::Poisoned { .. }));
+        statuses.push(StatusEffect::Poisoned { turns: FOOD_POISON_TURNS });
+        game.messages.add(tr!(game.config.locale, "food_poisoning", food = food), MessageCategory::Danger);
+    }
+    UseResult::UsedUp
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Hunger {
+    Fed,
+    Hungry,
+    Starving,
+}
+
+impl Hunger {
+    pub(crate) fn of(satiety: i32) -> Self {
+        if satiety <= 0 {
+            Hunger::Starving
+        } else if satiety <= HUNGRY_SATIETY {
+            Hunger::Hungry
+        } else {
+            Hunger::Fed
+        }
+    }
+}
+
+pub(crate) fn feed(game: &mut Game, satiety: i32) {
+    game.satiety = cmp::min(game.satiety.max(0) + satiety, FULL_SATIETY);
+}
+
+/// The hunger clock, warns once on the way to each stage and starving wears the player down until they eat
+pub(crate) fn tick_hunger(game: &mut Game) {
+    if !game.player.is_alive {
+        return;
+    }
+    let before = Hunger::of(game.satiety);
+    game.satiety -= 1;
+    let hunger = Hunger::of(game.satiety);
+    if hunger != before {
+        match hunger {
+            Hunger::Hungry => game.messages.add(tr!(game.config.locale, "hungry"), MessageCategory::Danger),
+            Hunger::Starving => game.messages.add(tr!(game.config.locale, "starving"), MessageCategory::Danger),
+            Hunger::Fed => {}
+        }
+    }
+    // NOTICE: Starving counts down below zero, every `STARVATION_TURNS` of it costs some health
+    if game.satiety <= -STARVATION_TURNS {
+        game.satiety = 0;
+        game.killer = Some(tr!(game.config.locale, "killer_starvation"));
+        with_player(game, |player, game| player.take_damage(STARVATION_DAMAGE, game));
+    }
+}
+
+/// Food rots wherever it lies, only a keen nose notices when it turns
+pub(crate) fn tick_food(game: &mut Game, game_objects: &mut [GameObject]) {
+    let keen_nose = game.player.has_perk(Perk::KeenNose);
+    let locale = &game.config.locale;
+    for (carried, food) in game
+        .inventory
+        .iter_mut()
+        .map(|food| (true, food))
+        .chain(game_objects.iter_mut().map(|food| (false, food)))
+    {
+        let freshness = match food.freshness {
+            Some(freshness) if freshness > 0 => freshness - 1,
+            _ => continue,
+        };
+        food.freshness = Some(freshness);
+        if freshness == 0 && keen_nose {
+            if carried {
+                game.messages.add(tr!(locale, "food_spoils", food = food.display_name(locale)), MessageCategory::Item);
+            }
+            food.name = Name::key("spoiled_food").with("food", mem::take(&mut food.name));
+            food.color = DESATURATED_GREEN;
+        }
+    }
+}
+
+fn cast_see_invisible(_inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    let statuses = &mut game.player.statuses;
+    statuses.retain(|status| !matches!(status, StatusEffect::SeeInvisible { .. }));
+    statuses.push(StatusEffect::SeeInvisible { turns: SEE_INVISIBLE_TURN_COUNT });
+    game.messages.add(tr!(game.config.locale, "see_invisible"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+fn cast_levitation(_inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    let statuses = &mut game.player.statuses;
+    statuses.retain(|status| !matches!(status, StatusEffect::Levitating { .. }));
+    statuses.push(StatusEffect::Levitating { turns: LEVITATION_TURN_COUNT });
+    game.messages.add(tr!(game.config.locale, "levitate"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+fn use_key(_inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    game.messages.add(tr!(game.config.locale, "key_use_hint"), MessageCategory::System);
+    UseResult::Cancelled
+}
+
+fn enchant_slot(scroll: Item) -> Slot {
+    if scroll == Item::EnchantArmor { Slot::Armor } else { Slot::Weapon }
+}
+
+/// Inventory indexes of the items an enchant scroll or a gem can be used on, `None` for items used on their own
+pub(crate) fn item_targets(item: Item, inventory: &[GameObject]) -> Option<Vec<usize>> {
+    let fits = |equipment: Equipment| match item {
+        Item::EnchantWeapon | Item::EnchantArmor => equipment.equipped && equipment.slot == enchant_slot(item),
+        _ => equipment.free_socket().is_some(),
+    };
+    match item {
+        Item::EnchantWeapon | Item::EnchantArmor | Item::Gem(_) => {
+            Some((0..inventory.len()).filter(|&index| inventory[index].equipment.is_some_and(fits)).collect())
+        }
+        _ => None,
+    }
+}
+
+fn insert_gem(item: Item, gem: Gem, target_id: Option<usize>, game: &mut Game) -> UseResult {
+    let targets = item_targets(item, &game.inventory).unwrap_or_default();
+    let target_id = match target_id.filter(|id| targets.contains(id)).or_else(|| targets.first().copied()) {
+        Some(target_id) => target_id,
+        None => {
+            game.messages.add(tr!(game.config.locale, "no_free_socket"), MessageCategory::System);
+            return UseResult::Cancelled;
+        }
+    };
+    let target = &mut game.inventory[target_id];
+    if let Some(ref mut equipment) = target.equipment {
+        if let Some(socket) = equipment.free_socket() {
+            equipment.gems[socket] = Some(gem);
+        }
+    }
+    game.messages.add(
+        tr!(game.config.locale, "gem_socketed", gem = gem.name(&game.config.locale), item = target.display_name(&game.config.locale)),
+        MessageCategory::Item,
+    );
+    UseResult::UsedUp
+}
+
+fn enchantment_chance(enchantment: i32) -> f32 {
+    if enchantment < SAFE_ENCHANTMENT {
+        1.0
+    } else {
+        0.5f32.powi(enchantment - SAFE_ENCHANTMENT + 1)
+    }
+}
+
+fn cast_enchant(scroll: Item, target_id: Option<usize>, game: &mut Game) -> UseResult {
+    let targets = item_targets(scroll, &game.inventory).unwrap_or_default();
+    let target_id = match target_id.filter(|id| targets.contains(id)).or_else(|| targets.first().copied()) {
+        Some(target_id) => target_id,
+        None => {
+            game.messages.add(tr!(game.config.locale, "nothing_to_enchant"), MessageCategory::System);
+            return UseResult::Cancelled;
+        }
+    };
+    let enchantment = game.inventory[target_id].equipment.map_or(0, |e| e.enchantment);
+    let old_name = game.inventory[target_id].display_name(&game.config.locale);
+    if game.rng.gen::<f32>() >= enchantment_chance(enchantment) {
+        game.messages.add(tr!(game.config.locale, "enchant_failed", item = old_name), MessageCategory::Item);
+        return UseResult::UsedUp;
+    }
+    let target = &mut game.inventory[target_id];
+    if let Some(ref mut equipment) = target.equipment {
+        equipment.enchantment += 1;
+    }
+    let name = target.display_name(&game.config.locale);
+    game.messages.add(tr!(game.config.locale, "enchanted", item = old_name, name = name), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+fn cast_repair(_inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    let weapon = game
+        .inventory
+        .iter_mut()
+        .find(|item| item.equipment.is_some_and(|e| e.equipped && e.durability < e.max_durability));
+    match weapon {
+        Some(weapon) => {
+            if let Some(ref mut equipment) = weapon.equipment {
+                equipment.durability = cmp::min(equipment.durability + REPAIR_KIT_AMOUNT, equipment.max_durability);
+            }
+            game.messages.add(tr!(game.config.locale, "repaired", item = weapon.display_name(&game.config.locale)), MessageCategory::Item);
+            UseResult::UsedUp
+        }
+        None => {
+            game.messages.add(tr!(game.config.locale, "nothing_to_repair"), MessageCategory::System);
+            UseResult::Cancelled
+        }
+    }
+}
+
+fn use_component(_inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    game.messages.add(tr!(game.config.locale, "component_use_hint"), MessageCategory::System);
+    UseResult::Cancelled
+}
+
+fn use_dart(_inventory_id: usize, game: &mut Game, _game_objects: &mut [GameObject]) -> UseResult {
+    game.messages.add(tr!(game.config.locale, "dart_use_hint"), MessageCategory::System);
+    UseResult::Cancelled
+}
+
+// NOTICE: Components go to a pouch rather than the inventory, recipes come from assets/content/recipes.toml
+pub(crate) fn craft(recipe_index: usize, game: &mut Game) -> bool {
+    let recipe = match game.config.content.recipes.get(recipe_index) {
+        Some(recipe) => recipe.clone(),
+        None => return false,
+    };
+    let name = recipe.result.name(&game.config.locale);
+    let missing = recipe
+        .ingredients
+        .iter()
+        .any(|ingredient| game.components.get(&ingredient.component).copied().unwrap_or(0) < ingredient.count);
+    if missing {
+        game.messages.add(tr!(game.config.locale, "missing_components", item = name), MessageCategory::System);
+        return false;
+    }
+    let weapon_index = game
+        .inventory
+        .iter()
+        .position(|item| item.equipment.is_some_and(|e| e.equipped && e.slot == Slot::Weapon));
+    let produced = if recipe.result == CraftResult::Sharpen { 0 } else { recipe.count as usize };
+    if recipe.result == CraftResult::Sharpen && weapon_index.is_none() {
+        game.messages.add(tr!(game.config.locale, "nothing_to_sharpen"), MessageCategory::System);
+        return false;
+    }
+    if game.inventory.len() + produced > 9 {
+        game.messages.add(tr!(game.config.locale, "inventory_full", item = name), MessageCategory::Item);
+        return false;
+    }
+
+    for ingredient in &recipe.ingredients {
+        if let Some(owned) = game.components.get_mut(&ingredient.component) {
+            *owned -= ingredient.count;
+        }
+    }
+    for _ in 0..produced {
+        let item = match recipe.result {
+            CraftResult::HealingPotion => new_consumable(0, 0, Item::Heal),
+            CraftResult::PotionOfSeeInvisible => new_consumable(0, 0, Item::SeeInvisible),
+            CraftResult::PotionOfLevitation => new_consumable(0, 0, Item::Levitation),
+            CraftResult::Darts => new_dart(0, 0),
+            CraftResult::Sharpen => break,
+        };
+        game.inventory.push(item);
+    }
+    if let (CraftResult::Sharpen, Some(index)) = (recipe.result, weapon_index) {
+        if let Some(ref mut equipment) = game.inventory[index].equipment {
+            equipment.power_bonus += 1;
+        }
+        let weapon = game.inventory[index].display_name(&game.config.locale);
+        game.messages.add(tr!(game.config.locale, "sharpened", item = weapon), MessageCategory::Item);
+    } else {
+        game.messages.add(tr!(game.config.locale, "crafted", item = name, count = produced), MessageCategory::Item);
+    }
+    true
+}
+
+fn closest_monster(game: &Game, game_objects: &[GameObject], max_range: i32) -> Option<usize> {
+    let mut closest_enemy = None;
+    let mut closest_distance = (max_range + 1) as f32;
+    for (id, game_object) in game_objects.iter().enumerate() {
+        if game_object.fighter.is_some()
+            && game_object.ai.is_some()
+            && game.fov.is_in_fov(game_object.x, game_object.y)
+        {
+            let distance = game.player.distance_to(game_object);
+            if distance < closest_distance {
+                closest_enemy = Some(id);
+                closest_distance = distance;
+            }
+        }
+    }
+    closest_enemy
+}
+
+pub(crate) fn cast_magic_missile(game: &mut Game, game_objects: &mut [GameObject]) -> bool {
+    let mana = match game.player.caster {
+        Some(caster) => caster.mana,
+        None => {
+            game.messages.add(tr!(game.config.locale, "no_magic"), MessageCategory::System);
+            return false;
+        }
+    };
+    if mana < MAGIC_MISSILE_COST {
+        game.messages.add(tr!(game.config.locale, "not_enough_mana"), MessageCategory::System);
+        return false;
+    }
+    let target_id = match closest_monster(game, game_objects, MAGIC_MISSILE_RANGE) {
+        Some(target_id) => target_id,
+        None => {
+            game.messages.add(tr!(game.config.locale, "no_enemy"), MessageCategory::System);
+            return false;
+        }
+    };
+    if let Some(ref mut caster) = game.player.caster {
+        caster.mana -= MAGIC_MISSILE_COST;
+    }
+    let from = game.player.position();
+    let to = game_objects[target_id].position();
+    let (_, hit) = fire_projectile(from, to, '*', LIGHT_VIOLET, game, game_objects);
+    let projectile = tr!(game.config.locale, "magic_missile");
+    match hit {
+        Some(target) => ranged_hit(target, &projectile, MAGIC_MISSILE_DAMAGE, DamageType::Physical, game, game_objects),
+        None => game.messages.add(tr!(game.config.locale, "projectile_miss", projectile = projectile), MessageCategory::Combat),
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::open_room;
+
+    #[test]
+    fn hunger_starves_until_fed() {
+        let (mut game, _) = open_room((8, 5));
+        let hp = game.player.fighter.unwrap().hp;
+        game.satiety = HUNGRY_SATIETY + 1;
+
+        tick_hunger(&mut game);
+        assert_eq!(Hunger::of(game.satiety), Hunger::Hungry);
+        game.satiety = 1;
+        for _ in 0..STARVATION_TURNS {
+            tick_hunger(&mut game);
+        }
+        assert_eq!(game.player.fighter.unwrap().hp, hp);
+        tick_hunger(&mut game);
+        assert_eq!(game.player.fighter.unwrap().hp, hp - STARVATION_DAMAGE);
+
+        feed(&mut game, FOOD_SATIETY);
+        assert_eq!(Hunger::of(game.satiety), Hunger::Fed);
+    }
+
+    fn recipe(game: &Game, result: CraftResult) -> usize {
+        game.config.content.recipes.iter().position(|recipe| recipe.result == result).unwrap()
+    }
+
+    #[test]
+    fn crafting_makes_the_recipe_result() {
+        let (mut game, _) = open_room((8, 5));
+        game.components.insert(Component::Herb, 2);
+
+        assert!(craft(recipe(&game, CraftResult::HealingPotion), &mut game));
+        assert_eq!(game.inventory.len(), 1);
+        assert_eq!(game.inventory[0].item, Some(Item::Heal));
+    }
+
+    #[test]
+    fn crafting_needs_every_component() {
+        let (mut game, _) = open_room((8, 5));
+        game.components.insert(Component::Herb, 1);
+
+        assert!(!craft(recipe(&game, CraftResult::PotionOfSeeInvisible), &mut game));
+        assert!(game.inventory.is_empty());
+        assert_eq!(game.components[&Component::Herb], 1);
+    }
+
+    #[test]
+    fn crafting_uses_the_ingredients_up() {
+        let (mut game, _) = open_room((8, 5));
+        game.components.insert(Component::Herb, 1);
+        game.components.insert(Component::Shard, 1);
+        let see_invisible = recipe(&game, CraftResult::PotionOfSeeInvisible);
+
+        assert!(craft(see_invisible, &mut game));
+        assert_eq!(game.components[&Component::Herb], 0);
+        assert_eq!(game.components[&Component::Shard], 0);
+        assert!(!craft(see_invisible, &mut game));
+        assert_eq!(game.inventory.len(), 1);
+    }
+
+    /// Puts an equipped dagger in the inventory and returns its index
+    fn wield_dagger(game: &mut Game) -> usize {
+        let mut dagger = new_weapon(0, 0, game.config.content.weapon("dagger").unwrap());
+        if let Some(ref mut equipment) = dagger.equipment {
+            equipment.equipped = true;
+        }
+        game.inventory.push(dagger);
+        game.inventory.len() - 1
+    }
+
+    #[test]
+    fn worn_out_weapons_eventually_break() {
+        let (mut game, _) = open_room((8, 5));
+        let dagger = wield_dagger(&mut game);
+        if let Some(ref mut equipment) = game.inventory[dagger].equipment {
+            equipment.durability = 2;
+        }
+
+        wear_equipment(Slot::Weapon, &mut game);
+        assert!(!game.inventory[dagger].equipment.unwrap().is_worn_out());
+        wear_equipment(Slot::Weapon, &mut game);
+        assert!(game.inventory[dagger].equipment.unwrap().is_worn_out());
+        for _ in 0..1000 {
+            wear_equipment(Slot::Weapon, &mut game);
+        }
+        assert!(game.inventory.is_empty());
+    }
+
+    #[test]
+    fn repairs_stop_at_full_durability() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        let dagger = wield_dagger(&mut game);
+        let max_durability = game.inventory[dagger].equipment.unwrap().max_durability;
+        if let Some(ref mut equipment) = game.inventory[dagger].equipment {
+            equipment.durability = max_durability - 5;
+        }
+        game.inventory.push(new_consumable(0, 0, Item::RepairKit));
+        game.inventory.push(new_consumable(0, 0, Item::RepairKit));
+
+        use_item(1, None, &mut game, &mut game_objects);
+        assert_eq!(game.inventory[dagger].equipment.unwrap().durability, max_durability);
+        use_item(1, None, &mut game, &mut game_objects);
+        assert_eq!(game.inventory.len(), 2);
+    }
+
+    #[test]
+    fn enchanting_gets_riskier_past_the_safe_level() {
+        assert_eq!(enchantment_chance(0), 1.0);
+        assert_eq!(enchantment_chance(SAFE_ENCHANTMENT - 1), 1.0);
+        assert_eq!(enchantment_chance(SAFE_ENCHANTMENT), 0.5);
+        assert_eq!(enchantment_chance(SAFE_ENCHANTMENT + 1), 0.25);
+    }
+
+    #[test]
+    fn enchanting_below_the_safe_level_always_works() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        let dagger = wield_dagger(&mut game);
+        for enchantment in 1..=SAFE_ENCHANTMENT {
+            game.inventory.push(new_consumable(0, 0, Item::EnchantWeapon));
+            use_item(game.inventory.len() - 1, Some(dagger), &mut game, &mut game_objects);
+            assert_eq!(game.inventory[dagger].equipment.unwrap().enchantment, enchantment);
+        }
+        assert_eq!(game.inventory.len(), 1);
+    }
+
+    #[test]
+    fn failed_enchantments_still_use_the_scroll() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        let dagger = wield_dagger(&mut game);
+        let enchantment = SAFE_ENCHANTMENT + 20;
+        if let Some(ref mut equipment) = game.inventory[dagger].equipment {
+            equipment.enchantment = enchantment;
+        }
+        game.inventory.push(new_consumable(0, 0, Item::EnchantWeapon));
+
+        use_item(1, Some(dagger), &mut game, &mut game_objects);
+        assert_eq!(game.inventory[dagger].equipment.unwrap().enchantment, enchantment);
+        assert_eq!(game.inventory.len(), 1);
+    }
+
+    #[test]
+    fn socketed_gems_leave_the_inventory_and_grant_their_bonus() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        let dagger = wield_dagger(&mut game);
+        if let Some(ref mut equipment) = game.inventory[dagger].equipment {
+            equipment.sockets = 1;
+        }
+        game.inventory.push(new_gem(0, 0, Gem::Diamond));
+
+        use_item(1, Some(dagger), &mut game, &mut game_objects);
+        assert_eq!(game.inventory.len(), 1);
+        assert_eq!(game.inventory[dagger].equipment.unwrap().gems[0], Some(Gem::Diamond));
+        assert_eq!(gem_bonus(&game.inventory, Gem::Diamond), 1);
+    }
+
+    #[test]
+    fn gems_need_a_free_socket() {
+        let (mut game, mut game_objects) = open_room((8, 5));
+        let dagger = wield_dagger(&mut game);
+        if let Some(ref mut equipment) = game.inventory[dagger].equipment {
+            equipment.sockets = 1;
+            equipment.gems[0] = Some(Gem::Ruby);
+        }
+        game.inventory.push(new_gem(0, 0, Gem::Onyx));
+
+        use_item(1, Some(dagger), &mut game, &mut game_objects);
+        assert_eq!(game.inventory.len(), 2);
+        assert_eq!(game.inventory[dagger].equipment.unwrap().gems, [Some(Gem::Ruby), None]);
+        assert_eq!(gem_bonus(&game.inventory, Gem::Onyx), 0);
+    }
+}
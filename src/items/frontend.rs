@@ -0,0 +1,168 @@
+use super::*;
+use crate::ui::MenuRow;
+
+pub(crate) const INVENTORY_WIDTH: i32 = 50;
+
+impl Hunger {
+    /// The short colored tag shown in the panel with the status effects, a fed player has none
+    pub(crate) fn tag(self, locale: &Locale) -> Option<(String, String, Color)> {
+        match self {
+            Hunger::Fed => None,
+            Hunger::Hungry => Some((tr!(locale, "tag_hungry"), tr!(locale, "tooltip_hungry"), LIGHT_YELLOW)),
+            Hunger::Starving => Some((tr!(locale, "tag_starving"), tr!(locale, "tooltip_starving"), LIGHT_RED)),
+        }
+    }
+}
+
+// NOTICE: Quickslots hold a kind of item, pressing the slot uses the first one of that kind in the inventory
+pub(crate) fn quickslot_item(slot: usize, game: &Game) -> Option<usize> {
+    let item = game.quickslots[slot]?;
+    game.inventory.iter().position(|game_object| game_object.item == Some(item))
+}
+
+pub(crate) fn inventory_rows(inventory: &[GameObject], sort: InventorySort, locale: &Locale) -> Vec<MenuRow> {
+    let options: Vec<String> = if inventory.is_empty() {
+        vec![tr!(locale, "inventory_empty")]
+    } else {
+        inventory
+            .iter()
+            .map(|item| {
+                let label = match item.equipment {
+                    Some(equipment) if equipment.equipped && equipment.slot != Slot::Weapon => tr!(locale, "worn", item = item.display_name(locale)),
+                    Some(equipment) if equipment.equipped => tr!(locale, "wielded", item = item.display_name(locale)),
+                    _ => item.display_name(locale),
+                };
+                match item.equipment.filter(|equipment| equipment.max_durability > 0) {
+                    Some(equipment) => tr!(
+                        locale,
+                        "condition",
+                        item = label,
+                        durability = equipment.durability,
+                        max_durability = equipment.max_durability,
+                    ),
+                    None => label,
+                }
+            })
+            .collect()
+    };
+
+    // NOTICE: Items are grouped and sorted for display only, each keeps the number of its inventory slot
+    let mut rows = vec![];
+    if inventory.is_empty() {
+        rows.push(MenuRow::Option(0, options[0].clone()));
+    }
+    for category in ItemCategory::ALL.iter() {
+        let mut indices: Vec<usize> = (0..inventory.len())
+            .filter(|&index| ItemCategory::of(&inventory[index]) == *category)
+            .collect();
+        match sort {
+            InventorySort::Name => indices.sort_by_key(|&index| inventory[index].display_name(locale)),
+            InventorySort::Value => indices.sort_by_key(|&index| cmp::Reverse(item_value(&inventory[index]))),
+            InventorySort::Recency => indices.reverse(),
+        }
+        if !indices.is_empty() {
+            rows.push(MenuRow::Heading(category.name(locale)));
+            rows.extend(indices.into_iter().map(|index| MenuRow::Option(index, options[index].clone())));
+        }
+    }
+    rows
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ItemCategory {
+    Weapons,
+    Armor,
+    Potions,
+    Scrolls,
+    Misc,
+}
+
+impl ItemCategory {
+    const ALL: [ItemCategory; 5] = [
+        ItemCategory::Weapons,
+        ItemCategory::Armor,
+        ItemCategory::Potions,
+        ItemCategory::Scrolls,
+        ItemCategory::Misc,
+    ];
+
+    fn of(item: &GameObject) -> ItemCategory {
+        use Item::*;
+
+        match item.item {
+            Some(Weapon) | Some(Dart) => ItemCategory::Weapons,
+            Some(Armor) | Some(RingOfDetection) => ItemCategory::Armor,
+            Some(Heal) | Some(SeeInvisible) | Some(Levitation) => ItemCategory::Potions,
+            Some(ScrollOfLightning) | Some(ScrollOfConfusion) | Some(ScrollOfBlizzard) | Some(EnchantWeapon) | Some(EnchantArmor) => {
+                ItemCategory::Scrolls
+            }
+            _ => ItemCategory::Misc,
+        }
+    }
+
+    fn name(self, locale: &Locale) -> String {
+        match self {
+            ItemCategory::Weapons => tr!(locale, "category_weapons"),
+            ItemCategory::Armor => tr!(locale, "category_armor"),
+            ItemCategory::Potions => tr!(locale, "category_potions"),
+            ItemCategory::Scrolls => tr!(locale, "category_scrolls"),
+            ItemCategory::Misc => tr!(locale, "category_misc"),
+        }
+    }
+}
+
+/// Recency is the order items were picked up in, the most recent first
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum InventorySort {
+    #[default]
+    Recency,
+    Name,
+    Value,
+}
+
+impl InventorySort {
+    pub(crate) fn next(self) -> InventorySort {
+        match self {
+            InventorySort::Recency => InventorySort::Name,
+            InventorySort::Name => InventorySort::Value,
+            InventorySort::Value => InventorySort::Recency,
+        }
+    }
+
+    pub(crate) fn name(self, locale: &Locale) -> String {
+        match self {
+            InventorySort::Recency => tr!(locale, "sort_recency"),
+            InventorySort::Name => tr!(locale, "sort_name"),
+            InventorySort::Value => tr!(locale, "sort_value"),
+        }
+    }
+}
+
+/// Switches the inventory to the next sort order and says which it is now
+pub(crate) fn cycle_inventory_sort(game: &mut Game) {
+    game.inventory_sort = game.inventory_sort.next();
+    let message = tr!(game.config.locale, "inventory_sorted", order = game.inventory_sort.name(&game.config.locale));
+    game.messages.add(message, MessageCategory::System);
+}
+
+/// A rough worth used to sort the inventory, equipment by its bonuses and tier, other items by what they do
+fn item_value(item: &GameObject) -> i32 {
+    use Item::*;
+
+    if let Some(equipment) = item.equipment {
+        let tier = match equipment.rarity {
+            Rarity::Common => 0,
+            Rarity::Uncommon => 1,
+            Rarity::Rare => 2,
+            Rarity::Artifact => 4,
+        };
+        return 20 + tier * 10 + (equipment.effective_power_bonus() + equipment.effective_defense_bonus()) * 5 + equipment.sockets as i32 * 3;
+    }
+    match item.item {
+        Some(EnchantWeapon) | Some(EnchantArmor) | Some(Gem(_)) => 25,
+        Some(ScrollOfLightning) | Some(ScrollOfConfusion) | Some(ScrollOfBlizzard) | Some(RepairKit) => 15,
+        Some(Heal) | Some(SeeInvisible) | Some(Levitation) => 10,
+        Some(Food) | Some(Dart) | Some(Key) => 5,
+        _ => 1,
+    }
+}
@@ -0,0 +1,179 @@
+use super::*;
+use crate::controls::{Action, PlayerAction};
+use crate::game::step;
+use crate::ui::{Tcod, menu, menu_rows};
+
+impl Equipment {
+    pub fn socket_lines(&self, locale: &Locale) -> Vec<String> {
+        self.gems
+            .iter()
+            .take(self.sockets)
+            .map(|socketed| match socketed {
+                Some(gem) => tr!(locale, "socketed_gem", gem = gem.name(locale), bonus = gem.bonus().translate(locale)),
+                None => tr!(locale, "empty_socket"),
+            })
+            .collect()
+    }
+}
+
+fn fits_quickslot(item: Item) -> bool {
+    use Item::*;
+
+    matches!(
+        item,
+        Heal | ScrollOfLightning | ScrollOfConfusion | ScrollOfBlizzard | SeeInvisible | Levitation | Food | Dart | RepairKit
+    )
+}
+
+pub(crate) fn assign_quickslot(tcod: &mut Tcod, game: &mut Game) {
+    let inventory_index = inventory_menu(
+        &game.inventory,
+        &tr!(game.config.locale, "quickslot_item_header"),
+        game.inventory_sort,
+        &game.config.locale,
+        tcod,
+    );
+    let inventory_index = match inventory_index {
+        Some(inventory_index) => inventory_index,
+        None => return,
+    };
+    let item = match game.inventory[inventory_index].item.filter(|&item| fits_quickslot(item)) {
+        Some(item) => item,
+        None => {
+            let message = tr!(game.config.locale, "cannot_quickslot", item = game.inventory[inventory_index].display_name(&game.config.locale));
+            game.messages.add(message, MessageCategory::System);
+            return;
+        }
+    };
+    let options: Vec<String> = (0..QUICKSLOT_COUNT)
+        .map(|slot| match quickslot_item(slot, game) {
+            Some(index) => tr!(game.config.locale, "quickslot_option", slot = slot + 1, item = game.inventory[index].display_name(&game.config.locale)),
+            None => tr!(game.config.locale, "quickslot_option_empty", slot = slot + 1),
+        })
+        .collect();
+    if let Some(slot) = menu(&tr!(game.config.locale, "quickslot_slot_header"), &options, INVENTORY_WIDTH, tcod) {
+        for quickslot in game.quickslots.iter_mut().filter(|quickslot| **quickslot == Some(item)) {
+            *quickslot = None;
+        }
+        game.quickslots[slot] = Some(item);
+        game.changed_outside_step = true;
+        let message = tr!(game.config.locale, "quickslot_assigned", item = game.inventory[inventory_index].display_name(&game.config.locale), slot = slot + 1);
+        game.messages.add(message, MessageCategory::System);
+    }
+}
+
+pub(crate) fn use_item_from_menu(tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    let inventory_index = inventory_menu(
+        &game.inventory,
+        &tr!(game.config.locale, "inventory_header"),
+        game.inventory_sort,
+        &game.config.locale,
+        tcod,
+    );
+    let inventory_index = match inventory_index {
+        Some(inventory_index) => inventory_index,
+        None => return PlayerAction::DidntTakeTurn,
+    };
+    // NOTICE: Enchant scrolls and gems open a picker of the items they can be used on
+    let targets = game.inventory[inventory_index]
+        .item
+        .and_then(|item| item_targets(item, &game.inventory))
+        .unwrap_or_default();
+    if targets.is_empty() {
+        return step(game, game_objects, Action::UseItem(inventory_index));
+    }
+    let options: Vec<String> = targets.iter().map(|&target| game.inventory[target].display_name(&game.config.locale)).collect();
+    match menu(&tr!(game.config.locale, "use_on_header"), &options, INVENTORY_WIDTH, tcod) {
+        Some(choice) => step(game, game_objects, Action::UseItemOn(inventory_index, targets[choice])),
+        None => PlayerAction::DidntTakeTurn,
+    }
+}
+
+fn component_list(components: impl IntoIterator<Item = (Component, u32)>, locale: &Locale) -> String {
+    components
+        .into_iter()
+        .map(|(component, count)| format!("{} x{}", component.name(locale), count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn crafting_menu(tcod: &mut Tcod, game: &Game) -> Option<usize> {
+    let locale = &game.config.locale;
+    let owned: Vec<_> = game.components.iter().filter(|(_, &count)| count > 0).map(|(&component, &count)| (component, count)).collect();
+    let pouch = if owned.is_empty() {
+        tr!(locale, "pouch_empty")
+    } else {
+        tr!(locale, "pouch", components = component_list(owned, locale))
+    };
+    let options: Vec<String> = game
+        .config
+        .content
+        .recipes
+        .iter()
+        .take(9)
+        .map(|recipe| {
+            let needed = recipe.ingredients.iter().map(|ingredient| (ingredient.component, ingredient.count));
+            format!("{}: {}", recipe.result.name(locale), component_list(needed, locale))
+        })
+        .collect();
+    menu(&format!("{}\n{}\n", tr!(locale, "craft_header"), pouch), &options, INVENTORY_WIDTH, tcod)
+}
+
+pub(crate) fn inventory_menu(
+    inventory: &[GameObject],
+    header: &str,
+    sort: InventorySort,
+    locale: &Locale,
+    tcod: &mut Tcod,
+) -> Option<usize> {
+    let rows = inventory_rows(inventory, sort, locale);
+    let compare = |index: usize| inventory.get(index).map(|item| compare_equipment(item, inventory, locale)).unwrap_or_default();
+    let inventory_index = menu_rows(header, &rows, INVENTORY_WIDTH, None, Some(&compare), tcod);
+
+    if !inventory.is_empty() {
+        inventory_index
+    } else {
+        None
+    }
+}
+
+/// Stats of an unequipped item next to those of the item equipped in its slot, gains in green and losses in red
+fn compare_equipment(item: &GameObject, inventory: &[GameObject], locale: &Locale) -> Vec<(String, Color)> {
+    let equipment = match item.equipment.filter(|equipment| !equipment.equipped) {
+        Some(equipment) => equipment,
+        None => return vec![],
+    };
+    let current = inventory
+        .iter()
+        .find(|other| other.equipment.is_some_and(|e| e.equipped && e.slot == equipment.slot));
+    let mut lines = vec![match current {
+        Some(current) => (tr!(locale, "compare_header", item = current.display_name(locale)), WHITE),
+        None => (tr!(locale, "compare_nothing"), WHITE),
+    }];
+
+    let stats = |equipment: Option<Equipment>| match equipment {
+        Some(e) => {
+            let gems = |gem| e.gems.iter().take(e.sockets).filter(|&&socketed| socketed == Some(gem)).count() as i32;
+            [
+                e.effective_power_bonus() + gems(Gem::Onyx),
+                e.effective_defense_bonus() + gems(Gem::Diamond),
+                e.durability,
+                e.sockets as i32,
+            ]
+        }
+        None => [0; 4],
+    };
+    let keys = ["compare_power", "compare_defense", "compare_durability", "compare_sockets"];
+    let new = stats(Some(equipment));
+    let old = stats(current.and_then(|current| current.equipment));
+    for ((key, value), old_value) in keys.iter().zip(new.iter()).zip(old.iter()) {
+        let delta = value - old_value;
+        let color = match delta.cmp(&0) {
+            cmp::Ordering::Greater => LIGHT_GREEN,
+            cmp::Ordering::Less => LIGHT_RED,
+            cmp::Ordering::Equal => LIGHT_GREY,
+        };
+        lines.push((tr!(locale, key, value = value, delta = format!("{:+}", delta)), color));
+    }
+    lines
+}
@@ -0,0 +1,239 @@
+use std::str::FromStr;
+use clap::{App, Arg};
+use crate::bot::{print_map, run_bot};
+use crate::character::Class;
+use crate::config::{CONFIG_FILE, WindowMode, load_config, load_content, load_locale};
+use crate::game::new_game;
+use crate::save::load_autosave;
+use crate::ui::PaletteKind;
+#[cfg(feature = "window")]
+use crate::window::{WindowOptions, run_window};
+
+// NOTICE: Looks up a localized string, named arguments fill the matching {placeholders}
+macro_rules! tr {
+    ($locale:expr, $key:expr) => {
+        $locale.get($key, &[])
+    };
+    ($locale:expr, $key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $locale.get($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+mod portable;
+mod config;
+mod audio;
+#[cfg(feature = "window")]
+mod narration;
+mod controls;
+mod object;
+mod map;
+mod game;
+mod character;
+mod combat;
+mod monsters;
+mod items;
+#[cfg(feature = "window")]
+mod activity;
+mod ui;
+#[cfg(feature = "window")]
+mod menu;
+mod tutorial;
+mod arena;
+#[cfg(feature = "window")]
+mod wizard;
+mod save;
+#[cfg(any(feature = "window", feature = "terminal"))]
+mod net;
+mod bot;
+#[cfg(feature = "window")]
+mod window;
+
+// NOTICE: Built with `--features terminal` and started with `--terminal`, the same 80x50 screen is drawn with
+// ANSI colors in the launching terminal, for playing over SSH or without a display
+#[cfg(feature = "terminal")]
+mod terminal;
+
+pub use character::new_player;
+pub use config::{Config, Locale, Name};
+pub use map::{make_cave, make_map, seeded_rng, validate_cave, validate_map, Map, Tile};
+
+struct CliOptions {
+    load: Option<String>,
+    bot: Option<String>,
+    headless: bool,
+    #[cfg(feature = "window")]
+    window: WindowOptions,
+}
+
+fn parse_map_size(value: &str) -> Result<(i32, i32), String> {
+    let mut dimensions = value.split('x').map(|dimension| dimension.trim().parse::<i32>());
+    match (dimensions.next(), dimensions.next(), dimensions.next()) {
+        (Some(Ok(width)), Some(Ok(height)), None) => Ok((width, height)),
+        _ => Err(format!("Invalid map size '{}', expected WIDTHxHEIGHT", value)),
+    }
+}
+
+fn parse_cli(config: &mut Config) -> CliOptions {
+    let matches = App::new("rust-rogue")
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .takes_value(true)
+            .value_name("SEED")
+            .validator(|value| value.parse::<u64>().map(|_| ()).map_err(|error| error.to_string()))
+            .help("Seed used to generate the dungeon"))
+        .arg(Arg::with_name("map-size")
+            .long("map-size")
+            .takes_value(true)
+            .value_name("WxH")
+            .validator(|value| parse_map_size(&value).map(|_| ()))
+            .help("Size of the generated dungeon levels"))
+        .arg(Arg::with_name("fullscreen")
+            .long("fullscreen")
+            .help("Start in fullscreen"))
+        .arg(Arg::with_name("wizard")
+            .long("wizard")
+            .help("Enable wizard mode"))
+        .arg(Arg::with_name("load")
+            .long("load")
+            .takes_value(true)
+            .value_name("SLOT")
+            .help("Load the given save slot instead of showing the main menu"))
+        .arg(Arg::with_name("replay")
+            .long("replay")
+            .takes_value(true)
+            .value_name("SLOT")
+            .help("Watch the recorded replay of the given save slot before the main menu"))
+        .arg(Arg::with_name("host")
+            .long("host")
+            .takes_value(true)
+            .value_name("PORT")
+            .validator(|value| value.parse::<u16>().map(|_| ()).map_err(|error| error.to_string()))
+            .conflicts_with("join")
+            .help("Start an experimental co-op game, waiting for a second player to join on the given port"))
+        .arg(Arg::with_name("join")
+            .long("join")
+            .takes_value(true)
+            .value_name("ADDRESS")
+            .help("Join the co-op game hosted at the given HOST:PORT address"))
+        .arg(Arg::with_name("broadcast")
+            .long("broadcast")
+            .takes_value(true)
+            .value_name("PORT")
+            .validator(|value| value.parse::<u16>().map(|_| ()).map_err(|error| error.to_string()))
+            .help("Let telnet or netcat clients on the given port watch the game, rendered with ANSI colors"))
+        .arg(Arg::with_name("public")
+            .long("public")
+            .help("Let other machines connect to --host, --broadcast and --export-state tcp:PORT, only this one can otherwise"))
+        .arg(Arg::with_name("accessible")
+            .long("accessible")
+            .help("Narrate messages and surroundings to stdout, or to the configured narrator command"))
+        .arg(Arg::with_name("palette")
+            .long("palette")
+            .takes_value(true)
+            .possible_values(&["default", "deuteranopia", "protanopia", "high_contrast"])
+            .help("Color palette, with colorblind-safe and high contrast alternatives"))
+        .arg(Arg::with_name("language")
+            .long("language")
+            .takes_value(true)
+            .value_name("LANG")
+            .help("Language of the game texts, matching a file in assets/lang"))
+        .arg(Arg::with_name("export-state")
+            .long("export-state")
+            .takes_value(true)
+            .value_name("TARGET")
+            .help("Write what the player knows as JSON each turn, to a file or to tcp:PORT for whoever connects"))
+        .arg(Arg::with_name("bot")
+            .long("bot")
+            .takes_value(true)
+            .value_name("PATH")
+            .help("Let the program at PATH play a new game without a window, over JSON lines on its stdin and stdout"))
+        .arg(Arg::with_name("headless")
+            .long("headless")
+            .help("Print the dungeon to stdout instead of opening a window"))
+        .arg(Arg::with_name("terminal")
+            .long("terminal")
+            .help("Play in this terminal with ANSI colors instead of opening a window, needs a build with --features terminal"))
+        .get_matches();
+
+    if let Some(seed) = matches.value_of("seed") {
+        config.seed = seed.parse().ok();
+    }
+    if let Some(Ok((width, height))) = matches.value_of("map-size").map(parse_map_size) {
+        config.map_width = width;
+        config.map_height = height;
+    }
+    if matches.is_present("fullscreen") {
+        config.window_mode = WindowMode::Fullscreen;
+    }
+    if matches.is_present("wizard") {
+        config.wizard = true;
+    }
+    if matches.is_present("accessible") {
+        config.accessibility = true;
+    }
+    if let Some(Ok(palette)) = matches.value_of("palette").map(PaletteKind::from_str) {
+        config.palette = palette;
+    }
+    if let Some(language) = matches.value_of("language") {
+        config.language = language.into();
+    }
+
+    CliOptions {
+        load: matches.value_of("load").map(String::from),
+        bot: matches.value_of("bot").map(String::from),
+        headless: matches.is_present("headless"),
+        #[cfg(feature = "window")]
+        window: WindowOptions::new(&matches),
+    }
+}
+
+pub fn run() {
+    let mut config = load_config(CONFIG_FILE);
+    let options = parse_cli(&mut config);
+    load_locale(&mut config);
+    load_content(&mut config);
+    if let Err(error) = config.check_map_size() {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+
+    if options.headless {
+        let loaded_game = match options.load {
+            Some(ref slot) => load_autosave(slot, &config),
+            None => Some(new_game("headless", Class::default(), &config)),
+        };
+        match loaded_game {
+            Some((game, game_objects)) => print_map(&game, &game_objects),
+            None => {
+                eprintln!("No save could be loaded from slot {}.", options.load.unwrap_or_default());
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(ref path) = options.bot {
+        if let Err(error) = run_bot(path, &config) {
+            eprintln!("The bot run failed: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // NOTICE: Builds without the window always play in the terminal
+    #[cfg(feature = "window")]
+    {
+        if !options.window.terminal {
+            return run_window(config, options);
+        }
+    }
+
+    #[cfg(feature = "terminal")]
+    let result = terminal::run(&config, options.load.as_deref());
+    #[cfg(not(feature = "terminal"))]
+    let result: Result<(), Box<dyn std::error::Error>> = Err("this build has no terminal mode, rebuild it with --features terminal".into());
+    if let Err(error) = result {
+        eprintln!("The terminal game failed: {}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,885 @@
+use std::cmp;
+use std::ops::{Index, IndexMut};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use serde::{Deserialize, Serialize};
+use crate::character::Perk;
+use crate::combat::{Ai, DeathCallback, Fighter, sees_invisible};
+use crate::config::{Config, DoorHandling, Name, WeaponDefinition};
+use crate::controls::Action;
+use crate::game::{Game, Light, MessageCategory, RememberedObject};
+use crate::items::{Item, new_item, new_weapon};
+use crate::monsters::new_monster;
+use crate::object::{GameObject, Npc};
+use crate::portable::{FovAlgorithm, FovMap};
+use crate::portable::colors::*;
+use crate::ui::{PANEL_HEIGHT, SCREEN_HEIGHT};
+
+/// Map colors and the view's scrolling, for both front ends
+#[cfg(any(feature = "window", feature = "terminal"))]
+mod frontend;
+/// Which tile the mouse is over in the window
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(any(feature = "window", feature = "terminal"))]
+pub(crate) use self::frontend::*;
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+// NOTICE: Dungeon settings, levels as wide as the screen scroll under the view left of the sidebar
+pub(crate) const MAP_WIDTH: i32 = 80;
+pub(crate) const MAP_HEIGHT: i32 = SCREEN_HEIGHT - PANEL_HEIGHT;
+
+pub(crate) const ROOM_MAX_SIZE: i32 = 10;
+const ROOM_MIN_SIZE: i32 = 5;
+const POOL_CHANCE: f32 = 0.25;
+const DOOR_CHANCE: f32 = 0.5;
+const GATE_CHANCE: f32 = 0.3;
+const VAULT_CHANCE: f32 = 0.2;
+const VAULT_ATTEMPTS: i32 = 30;
+const VAULT_SIZE: i32 = 4;
+const MAX_ROOMS: i32 = 10;
+const CAVE_WALL_CHANCE: f32 = 0.42;
+const CAVE_SMOOTHING_PASSES: u32 = 4;
+const CAMPFIRE_CHANCE: f32 = 0.15;
+const BLACKSMITH_CHANCE: f32 = 0.3;
+
+// NOTICE: FOV parameters
+const FOV_ALGORITHM: FovAlgorithm = FovAlgorithm::Basic;
+const FOV_LIGHT_WALLS: bool = true;
+const CAMPFIRE_LIGHT_RADIUS: f32 = 6.0;
+
+// NOTICE: Terrain walkers can't cross, flying crosses both and swimming only deep water, closed doors need opening first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Terrain {
+    #[default]
+    Floor,
+    Chasm,
+    DeepWater,
+    ClosedDoor,
+    OpenDoor,
+    Rubble,
+    LockedGate,
+    UnlockedGate,
+    GlassWall,
+    Campfire,
+}
+
+impl Terrain {
+    pub fn glyph(self) -> Option<char> {
+        match self {
+            Terrain::Floor => None,
+            Terrain::Chasm => Some(':'),
+            Terrain::DeepWater => Some('~'),
+            Terrain::ClosedDoor => Some('+'),
+            Terrain::OpenDoor => Some('\''),
+            Terrain::Rubble => Some(','),
+            Terrain::LockedGate => Some('#'),
+            Terrain::UnlockedGate => Some('\''),
+            Terrain::GlassWall => Some('"'),
+            Terrain::Campfire => Some('^'),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Movement {
+    pub(crate) flying: bool,
+    pub(crate) swimming: bool,
+}
+
+impl Movement {
+    pub fn can_cross(self, terrain: Terrain) -> bool {
+        match terrain {
+            Terrain::Floor | Terrain::OpenDoor | Terrain::Rubble | Terrain::UnlockedGate | Terrain::Campfire => true,
+            Terrain::Chasm => self.flying,
+            Terrain::DeepWater => self.flying || self.swimming,
+            Terrain::ClosedDoor | Terrain::LockedGate | Terrain::GlassWall => false,
+        }
+    }
+}
+
+// NOTICE: Marks left by fights and fire, they tint the floor for as long as the level lasts
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Decal {
+    Blood,
+    Scorch,
+}
+
+/// Scorch marks cover blood, blood doesn't cover scorch marks
+pub(crate) fn leave_decal(map: &mut Map, (x, y): (i32, i32), decal: Decal) {
+    let tile = &mut map[(x, y)];
+    if tile.blocked || (decal == Decal::Blood && tile.decal == Some(Decal::Scorch)) {
+        return;
+    }
+    tile.decal = Some(decal);
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Tile {
+    pub(crate) blocked: bool,
+    pub(crate) explored: bool,
+    pub(crate) block_sight: bool,
+    #[serde(default)]
+    pub(crate) terrain: Terrain,
+    #[serde(default)]
+    pub(crate) decal: Option<Decal>,
+}
+
+impl Tile {
+    pub fn empty() -> Self {
+        Tile {
+            blocked: false,
+            explored: false,
+            block_sight: false,
+            terrain: Terrain::Floor,
+            decal: None,
+        }
+    }
+
+    pub fn wall() -> Self {
+        Tile {
+            blocked: true,
+            explored: false,
+            block_sight: true,
+            terrain: Terrain::Floor,
+            decal: None,
+        }
+    }
+
+    pub fn terrain(terrain: Terrain) -> Self {
+        Tile {
+            terrain,
+            blocked: terrain == Terrain::GlassWall,
+            block_sight: terrain == Terrain::ClosedDoor,
+            ..Tile::empty()
+        }
+    }
+}
+
+/// The tiles of a level stored row after row, saved as columns like the nested vectors it replaced
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "Vec<Vec<Tile>>", into = "Vec<Vec<Tile>>")]
+pub struct Map {
+    width: i32,
+    height: i32,
+    tiles: Vec<Tile>,
+}
+
+impl Map {
+    pub fn new(width: i32, height: i32, tile: Tile) -> Self {
+        Map {
+            width,
+            height,
+            tiles: vec![tile; (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<&Tile> {
+        if self.in_bounds(x, y) {
+            Some(&self.tiles[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut Tile> {
+        if self.in_bounds(x, y) {
+            Some(&mut self.tiles[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    /// Every position of the map, row after row like the tiles are stored
+    pub fn iter_coords(&self) -> impl Iterator<Item = (i32, i32)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = &Tile> {
+        self.tiles.iter()
+    }
+
+    pub fn tiles_mut(&mut self) -> impl Iterator<Item = &mut Tile> {
+        self.tiles.iter_mut()
+    }
+
+    /// Walls, and anything outside of the map
+    pub fn is_blocked_tile(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).is_none_or(|tile| tile.blocked)
+    }
+}
+
+impl Index<(i32, i32)> for Map {
+    type Output = Tile;
+
+    fn index(&self, (x, y): (i32, i32)) -> &Tile {
+        match self.get(x, y) {
+            Some(tile) => tile,
+            None => panic!("Tile ({}, {}) is outside of the {}x{} map", x, y, self.width, self.height),
+        }
+    }
+}
+
+impl IndexMut<(i32, i32)> for Map {
+    fn index_mut(&mut self, (x, y): (i32, i32)) -> &mut Tile {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(x, y) {
+            Some(tile) => tile,
+            None => panic!("Tile ({}, {}) is outside of the {}x{} map", x, y, width, height),
+        }
+    }
+}
+
+impl From<Vec<Vec<Tile>>> for Map {
+    fn from(columns: Vec<Vec<Tile>>) -> Self {
+        let width = columns.len();
+        let height = columns.first().map_or(0, Vec::len);
+        Map {
+            width: width as i32,
+            height: height as i32,
+            tiles: (0..height).flat_map(|y| columns.iter().map(move |column| column[y])).collect(),
+        }
+    }
+}
+
+impl From<Map> for Vec<Vec<Tile>> {
+    fn from(map: Map) -> Self {
+        (0..map.width).map(|x| (0..map.height).map(|y| map[(x, y)]).collect()).collect()
+    }
+}
+
+pub(crate) fn new_fov_map() -> FovMap {
+    FovMap::new(MAP_WIDTH, MAP_HEIGHT)
+}
+
+pub fn seeded_rng(seed: u64) -> XorShiftRng {
+    // NOTICE: XorShift refuses an all-zero seed, the constant halves keep it away from that
+    XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32, 0x9E37_79B9, 0x7F4A_7C15])
+}
+
+/// Levels roll from their own RNG rather than the game's, so runs sharing a seed share every level whatever happens in them
+pub(crate) fn level_rng(seed: u64, dungeon_level: u32) -> XorShiftRng {
+    seeded_rng(seed ^ u64::from(dungeon_level).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+pub(crate) fn map_size(map: &Map) -> (i32, i32) {
+    (map.width(), map.height())
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Rectangle {
+    x1: i32,
+    y1: i32,
+    x2: i32,
+    y2: i32,
+}
+
+impl Rectangle {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Rectangle {
+            x1: x,
+            y1: y,
+            x2: x + w,
+            y2: y + h,
+        }
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        let center_x = (self.x1 + self.x2) / 2;
+        let center_y = (self.y1 + self.y2) / 2;
+
+        (center_x, center_y)
+    }
+
+    pub fn is_intersecting(&self, other: &Rectangle) -> bool {
+        (self.x1 <= other.x2)
+            && (self.x2 >= other.x1)
+            && (self.y1 <= other.y2)
+            && (self.y2 >= other.y1) 
+    }
+}
+
+pub(crate) fn make_room(room: Rectangle, map: &mut Map) {
+    for x in (room.x1 + 1)..room.x2 {
+        for y in (room.y1 + 1)..room.y2 {
+            map[(x, y)] = Tile::empty();
+        }
+    }
+}
+
+/// Pools keep a ring of floor around them and stay off the room center so they never cut a room in two
+fn make_pool(room: Rectangle, map: &mut Map, rng: &mut XorShiftRng) {
+    let (min_x, max_x) = (room.x1 + 2, room.x2 - 2);
+    let (min_y, max_y) = (room.y1 + 2, room.y2 - 2);
+    if min_x > max_x || min_y > max_y || rng.gen::<f32>() >= POOL_CHANCE {
+        return;
+    }
+    let terrain = if rng.gen() { Terrain::Chasm } else { Terrain::DeepWater };
+    let x1 = rng.gen_range(min_x, max_x + 1);
+    let y1 = rng.gen_range(min_y, max_y + 1);
+    let x2 = rng.gen_range(x1, max_x + 1);
+    let y2 = rng.gen_range(y1, max_y + 1);
+    let (center_x, center_y) = room.center();
+    if (x1..=x2).contains(&center_x) && (y1..=y2).contains(&center_y) {
+        return;
+    }
+    for x in x1..=x2 {
+        for y in y1..=y2 {
+            map[(x, y)] = Tile::terrain(terrain);
+        }
+    }
+}
+
+fn make_campfire(room: Rectangle, map: &mut Map, rng: &mut XorShiftRng) {
+    if rng.gen::<f32>() >= CAMPFIRE_CHANCE {
+        return;
+    }
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = rng.gen_range(room.y1 + 1, room.y2);
+    if map[(x, y)].terrain == Terrain::Floor && (x, y) != room.center() {
+        map[(x, y)] = Tile::terrain(Terrain::Campfire);
+    }
+}
+
+/// Doors go where a tunnel breaks through a room wall, between two wall tiles
+fn make_doors(room: Rectangle, map: &mut Map, rng: &mut XorShiftRng) {
+    let is_wall = |map: &Map, x: i32, y: i32| map.is_blocked_tile(x, y);
+    let vertical_walls = [room.x1, room.x2]
+        .iter()
+        .flat_map(|&x| ((room.y1 + 1)..room.y2).map(move |y| (x, y, (0, 1))))
+        .collect::<Vec<_>>();
+    let horizontal_walls = [room.y1, room.y2]
+        .iter()
+        .flat_map(|&y| ((room.x1 + 1)..room.x2).map(move |x| (x, y, (1, 0))))
+        .collect::<Vec<_>>();
+    for (x, y, (dx, dy)) in vertical_walls.into_iter().chain(horizontal_walls) {
+        let tile = map[(x, y)];
+        let doorway = !tile.blocked
+            && tile.terrain == Terrain::Floor
+            && is_wall(map, x - dx, y - dy)
+            && is_wall(map, x + dx, y + dy);
+        if doorway && rng.gen::<f32>() < DOOR_CHANCE {
+            map[(x, y)] = Tile::terrain(Terrain::ClosedDoor);
+        }
+    }
+}
+
+pub(crate) fn make_horizontal_tunnel(x1: i32, x2: i32, y: i32, map: &mut Map) {
+    for x in cmp::min(x1, x2)..(cmp::max(x1, x2) + 1) {
+        map[(x, y)] = Tile::empty();
+    }
+}
+
+fn make_vertical_tunnel(y1: i32, y2: i32, x: i32, map: &mut Map) {
+    for y in cmp::min(y1, y2)..(cmp::max(y1, y2) + 1) {
+        map[(x, y)] = Tile::empty();
+    }
+}
+
+pub fn make_map(player: &mut GameObject, game_objects: &mut Vec<GameObject>, config: &Config, rng: &mut XorShiftRng) -> Map {
+    let mut map = Map::new(config.map_width, config.map_height, Tile::wall());
+
+    // NOTICE: Drop everything left over from the previous level
+    game_objects.clear();
+
+    let mut rooms = vec![];
+
+    for _ in 0..MAX_ROOMS {
+        let w = rng.gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
+        let h = rng.gen_range(ROOM_MIN_SIZE, ROOM_MAX_SIZE + 1);
+        let x = rng.gen_range(0, config.map_width - w);
+        let y = rng.gen_range(0, config.map_height - h);
+
+        let new_room = Rectangle::new(x, y, w, h);
+        let failed = rooms.iter().any(|other_room| new_room.is_intersecting(other_room));
+        if !failed {
+            make_room(new_room, &mut map);
+            make_pool(new_room, &mut map, rng);
+            make_campfire(new_room, &mut map, rng);
+            place_game_objects(new_room, &map, player, game_objects, config, rng);
+
+            let (new_x, new_y) = new_room.center();
+            if rooms.is_empty() {
+                player.set_position(new_x, new_y);
+            } else {
+                let (prev_x, prev_y) = rooms[rooms.len() - 1].center();
+
+                if rng.gen() {
+                    make_horizontal_tunnel(prev_x, new_x, prev_y, &mut map);
+                    make_vertical_tunnel(prev_y, new_y, new_x, &mut map);
+                } else {
+                    make_vertical_tunnel(prev_y, new_y, prev_x, &mut map);
+                    make_horizontal_tunnel(prev_x, new_x, new_y, &mut map);
+                }
+            }
+            rooms.push(new_room);
+        }
+    }
+
+    if rng.gen::<f32>() < VAULT_CHANCE {
+        make_vault(&rooms, &mut map, game_objects, config, rng);
+    }
+    for room in &rooms {
+        make_doors(*room, &mut map, rng);
+    }
+
+    let (last_room_x, last_room_y) = rooms[rooms.len() - 1].center();
+    let mut stairs = GameObject::new(last_room_x, last_room_y, '<', WHITE, "stairs", false);
+    stairs.description = Name::key("stairs_description");
+    stairs.always_visible = true;
+    stairs.is_stairs = true;
+    game_objects.push(stairs);
+
+    if rooms.len() > 1 && rng.gen::<f32>() < GATE_CHANCE {
+        make_locked_gate(rooms[rooms.len() - 1], &mut map, player.position(), game_objects, rng);
+    }
+    if rooms.len() > 1 && rng.gen::<f32>() < BLACKSMITH_CHANCE {
+        place_blacksmith(&rooms[1..], &map, game_objects, rng);
+    }
+
+    map
+}
+
+// NOTICE: A single open cave for the arena, smoothed random walls with the unreachable pockets filled in
+pub fn make_cave(player: &mut GameObject, game_objects: &mut Vec<GameObject>, config: &Config, rng: &mut XorShiftRng) -> Map {
+    let (width, height) = (config.map_width, config.map_height);
+    let is_border = |x: i32, y: i32| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+    let mut walls: Vec<Vec<bool>> = (0..width)
+        .map(|x| (0..height).map(|y| is_border(x, y) || rng.gen::<f32>() < CAVE_WALL_CHANCE).collect())
+        .collect();
+
+    for _ in 0..CAVE_SMOOTHING_PASSES {
+        walls = (0..width)
+            .map(|x| {
+                (0..height)
+                    .map(|y| {
+                        let neighbours = (x - 1..=x + 1)
+                            .flat_map(|nx| (y - 1..=y + 1).map(move |ny| (nx, ny)))
+                            .filter(|&(nx, ny)| nx < 0 || ny < 0 || nx >= width || ny >= height || walls[nx as usize][ny as usize])
+                            .count();
+                        is_border(x, y) || neighbours >= 5
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    let center = (width / 2, height / 2);
+    let mut map = Map::new(width, height, Tile::wall());
+    for x in 1..width - 1 {
+        for y in 1..height - 1 {
+            let near_center = (x - center.0).abs() <= 2 && (y - center.1).abs() <= 2;
+            if near_center || !walls[x as usize][y as usize] {
+                map[(x, y)] = Tile::empty();
+            }
+        }
+    }
+    let reachable = reachable_tiles(&map, center, false);
+    for (x, y) in map.iter_coords() {
+        if !reachable[x as usize][y as usize] {
+            map[(x, y)] = Tile::wall();
+        }
+    }
+
+    game_objects.clear();
+    player.set_position(center.0, center.1);
+    map
+}
+
+fn place_blacksmith(rooms: &[Rectangle], map: &Map, game_objects: &mut Vec<GameObject>, rng: &mut XorShiftRng) {
+    let room = rooms[rng.gen_range(0, rooms.len())];
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = rng.gen_range(room.y1 + 1, room.y2);
+    let occupied = game_objects.iter().any(|game_object| game_object.position() == (x, y));
+    if occupied || (x, y) == room.center() || is_blocked(x, y, Movement::default(), map, game_objects) {
+        return;
+    }
+    let mut blacksmith = GameObject::new(x, y, '@', LIGHT_ORANGE, "blacksmith", true);
+    blacksmith.description = Name::key("blacksmith_description");
+    blacksmith.npc = Some(Npc::Blacksmith { served: false });
+    game_objects.push(blacksmith);
+}
+
+// NOTICE: Vaults are sealed behind glass next to a corridor or room, the player has to shatter their way in
+fn make_vault(rooms: &[Rectangle], map: &mut Map, game_objects: &mut Vec<GameObject>, config: &Config, rng: &mut XorShiftRng) {
+    let (map_width, map_height) = map_size(map);
+    for _ in 0..VAULT_ATTEMPTS {
+        let vault = Rectangle::new(
+            rng.gen_range(1, map_width - VAULT_SIZE - 1),
+            rng.gen_range(1, map_height - VAULT_SIZE - 1),
+            VAULT_SIZE,
+            VAULT_SIZE,
+        );
+        let tiles: Vec<(i32, i32)> = (vault.x1..=vault.x2)
+            .flat_map(|x| (vault.y1..=vault.y2).map(move |y| (x, y)))
+            .collect();
+        let is_ring = |&(x, y): &(i32, i32)| x == vault.x1 || x == vault.x2 || y == vault.y1 || y == vault.y2;
+        let in_rock = tiles.iter().all(|&(x, y)| map[(x, y)].blocked);
+        let overlaps_room = rooms.iter().any(|room| vault.is_intersecting(room));
+        let next_to_floor = tiles.iter().filter(|tile| is_ring(tile)).any(|&(x, y)| {
+            [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)].iter().any(|&(x, y)| {
+                x >= 0 && y >= 0 && x < map_width && y < map_height && !map[(x, y)].blocked
+            })
+        });
+        if !in_rock || overlaps_room || !next_to_floor {
+            continue;
+        }
+
+        for tile in &tiles {
+            map[(tile.0, tile.1)] = if is_ring(tile) { Tile::terrain(Terrain::GlassWall) } else { Tile::empty() };
+        }
+        let (center_x, center_y) = vault.center();
+        game_objects.push(new_warden(center_x, center_y));
+
+        let mut weapons: Vec<&WeaponDefinition> = config.content.weapons.iter().collect();
+        weapons.sort_by_key(|weapon| cmp::Reverse(weapon.power_bonus));
+        weapons.truncate(cmp::max(1, weapons.len() / 2));
+        if !weapons.is_empty() {
+            for &(x, y) in &[(vault.x1 + 1, vault.y1 + 1), (vault.x2 - 1, vault.y2 - 1)] {
+                let weapon = weapons[rng.gen_range(0, weapons.len())];
+                game_objects.push(new_weapon(x, y, weapon));
+            }
+        }
+        return;
+    }
+}
+
+fn new_warden(x: i32, y: i32) -> GameObject {
+    let mut warden = GameObject::new(x, y, 'W', FLAME, "warden", true);
+    warden.fighter = Some(Fighter {
+        max_hp: 20,
+        hp: 20,
+        defense: 2,
+        power: 6,
+        xp: 150,
+        on_death: DeathCallback::Monster,
+    });
+    warden.ai = Some(Ai::Basic);
+    warden.is_alive = true;
+    warden.description = Name::key("warden_description");
+    warden.species = "warden".into();
+    warden
+}
+
+// NOTICE: Locked gates seal off the stairs room, the key is carried by a monster or stashed elsewhere
+fn make_locked_gate(room: Rectangle, map: &mut Map, start: (i32, i32), game_objects: &mut Vec<GameObject>, rng: &mut XorShiftRng) {
+    let ring: Vec<(i32, i32)> = (room.x1..=room.x2)
+        .flat_map(|x| (room.y1..=room.y2).map(move |y| (x, y)))
+        .filter(|&(x, y)| x == room.x1 || x == room.x2 || y == room.y1 || y == room.y2)
+        .filter(|&(x, y)| !map[(x, y)].blocked)
+        .collect();
+    let previous: Vec<Tile> = ring.iter().map(|&(x, y)| map[(x, y)]).collect();
+    for &(x, y) in &ring {
+        map[(x, y)] = Tile::terrain(Terrain::LockedGate);
+    }
+
+    let reachable = reachable_tiles(map, start, false);
+    let (stairs_x, stairs_y) = room.center();
+    let holders: Vec<usize> = (0..game_objects.len())
+        .filter(|&id| game_objects[id].ai.is_some() && reachable[game_objects[id].x as usize][game_objects[id].y as usize])
+        .collect();
+    let (map_width, map_height) = map_size(map);
+    let stashes: Vec<(i32, i32)> = (0..map_width)
+        .flat_map(|x| (0..map_height).map(move |y| (x, y)))
+        .filter(|&(x, y)| {
+            reachable[x as usize][y as usize]
+                && map[(x, y)].terrain == Terrain::Floor
+                && (x, y) != start
+                && !game_objects.iter().any(|game_object| game_object.position() == (x, y))
+        })
+        .collect();
+
+    if reachable[stairs_x as usize][stairs_y as usize] || (holders.is_empty() && stashes.is_empty()) {
+        for (&(x, y), &tile) in ring.iter().zip(&previous) {
+            map[(x, y)] = tile;
+        }
+        return;
+    }
+    if !holders.is_empty() && (stashes.is_empty() || rng.gen()) {
+        game_objects[holders[rng.gen_range(0, holders.len())]].carries_key = true;
+    } else {
+        let (x, y) = stashes[rng.gen_range(0, stashes.len())];
+        game_objects.push(new_key(x, y));
+    }
+}
+
+pub(crate) fn new_key(x: i32, y: i32) -> GameObject {
+    let mut key = GameObject::new(x, y, 'k', GOLD, "key", false);
+    key.item = Some(Item::Key);
+    key.description = Name::key("key_description");
+    key
+}
+
+/// Tiles a walker can reach, opening doors and shattering glass on the way, unlocking gates if it has the key
+fn reachable_tiles(map: &Map, start: (i32, i32), through_gates: bool) -> Vec<Vec<bool>> {
+    let (map_width, map_height) = map_size(map);
+    let mut reachable = vec![vec![false; map_height as usize]; map_width as usize];
+    let mut to_visit = vec![start];
+
+    while let Some((x, y)) = to_visit.pop() {
+        if x < 0 || y < 0 || x >= map_width || y >= map_height {
+            continue;
+        }
+        let tile = &map[(x, y)];
+        let passable = match tile.terrain {
+            Terrain::ClosedDoor | Terrain::GlassWall => true,
+            Terrain::LockedGate => through_gates,
+            terrain => !tile.blocked && Movement::default().can_cross(terrain),
+        };
+        if reachable[x as usize][y as usize] || !passable {
+            continue;
+        }
+        reachable[x as usize][y as usize] = true;
+        to_visit.extend(&[(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+    }
+
+    reachable
+}
+
+/// The tiles the player reaches, failing when they start off the map or in a wall or when some floor is cut off
+fn player_reach(map: &Map, player: &GameObject) -> Result<Vec<Vec<bool>>, String> {
+    let (map_width, map_height) = map_size(map);
+    let (player_x, player_y) = player.position();
+    if player_x < 0 || player_y < 0 || player_x >= map_width || player_y >= map_height {
+        return Err(format!("Player starts outside of the map at ({}, {})", player_x, player_y));
+    }
+    if map[(player_x, player_y)].blocked {
+        return Err(format!("Player starts in a wall at ({}, {})", player_x, player_y));
+    }
+
+    let reachable = reachable_tiles(map, (player_x, player_y), true);
+    for x in 0..map_width {
+        for y in 0..map_height {
+            let tile = &map[(x, y)];
+            if !tile.blocked && tile.terrain == Terrain::Floor && !reachable[x as usize][y as usize] {
+                return Err(format!("Floor tile ({}, {}) is not connected to the player", x, y));
+            }
+        }
+    }
+    Ok(reachable)
+}
+
+pub fn validate_map(map: &Map, player: &GameObject, game_objects: &[GameObject]) -> Result<(), String> {
+    let reachable = player_reach(map, player)?;
+    let stairs = game_objects
+        .iter()
+        .find(|game_object| game_object.is_stairs)
+        .ok_or_else(|| "No stairs were placed".to_string())?;
+    if !reachable[stairs.x as usize][stairs.y as usize] {
+        return Err(format!("Stairs at ({}, {}) cannot be reached", stairs.x, stairs.y));
+    }
+
+    let has_gate = map.tiles().any(|tile| tile.terrain == Terrain::LockedGate);
+    if has_gate {
+        let reachable = reachable_tiles(map, player.position(), false);
+        let key_reachable = game_objects.iter().any(|game_object| {
+            (game_object.item == Some(Item::Key) || game_object.carries_key) && reachable[game_object.x as usize][game_object.y as usize]
+        });
+        if !key_reachable {
+            return Err("The key of the locked gate cannot be reached".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Arena caves have no stairs to reach, the player only has to start on a floor that joins all the others
+pub fn validate_cave(map: &Map, player: &GameObject) -> Result<(), String> {
+    player_reach(map, player).map(|_| ())
+}
+
+fn place_game_objects(room: Rectangle, map: &Map, player: &GameObject, game_objects: &mut Vec<GameObject>, config: &Config, rng: &mut XorShiftRng) {
+    let monster_count = rng.gen_range(0, config.max_room_monsters + 1);
+    for _ in 0..monster_count {
+        let x = rng.gen_range(room.x1 + 1, room.x2);
+        let y = rng.gen_range(room.y1 + 1, room.y2);
+        if !is_blocked(x, y, Movement::default(), map, game_objects) {
+            game_objects.push(new_monster(x, y, config, rng));
+        }
+    }
+
+    let mut item_count = rng.gen_range(0, config.max_room_items + 1);
+    if player.has_perk(Perk::Scavenger) {
+        item_count = cmp::max(item_count, rng.gen_range(0, config.max_room_items + 1));
+    }
+
+    for _ in 0..item_count {
+        let x = rng.gen_range(room.x1 + 1, room.x2);
+        let y = rng.gen_range(room.y1 + 1, room.y2);
+
+        if !is_blocked(x, y, Movement::default(), map, game_objects) {
+            game_objects.push(new_item(x, y, config, rng));
+        }
+    }
+}
+
+pub(crate) fn is_blocked(x: i32, y: i32, movement: Movement, map: &Map, game_objects: &[GameObject]) -> bool {
+    let tile = match map.get(x, y) {
+        Some(tile) => tile,
+        None => return true,
+    };
+    if tile.blocked || !movement.can_cross(tile.terrain) {
+        return true;
+    }
+
+    game_objects
+        .iter()
+        .any(|game_object| game_object.blocks && game_object.position() == (x, y))
+}
+
+/// Like `is_blocked`, with the player and the co-op partner standing in the way too
+pub(crate) fn blocks_movement(x: i32, y: i32, movement: Movement, game: &Game, game_objects: &[GameObject]) -> bool {
+    (game.player.blocks && game.player.position() == (x, y))
+        || partner_blocks(x, y, game)
+        || is_blocked(x, y, movement, &game.map, game_objects)
+}
+
+fn partner_blocks(x: i32, y: i32, game: &Game) -> bool {
+    game.partner.as_ref().is_some_and(|partner| partner.blocks && partner.position() == (x, y))
+}
+
+pub(crate) fn move_game_object_by(id: usize, dx: i32, dy: i32, game: &Game, game_objects: &mut [GameObject]) {
+    let (x, y) = game_objects[id].position();
+    if !blocks_movement(x + dx, y + dy, game_objects[id].movement(), game, game_objects) {
+        game_objects[id].set_position(x + dx, y + dy);
+    }
+}
+
+pub(crate) fn move_player_by(dx: i32, dy: i32, game: &mut Game, game_objects: &[GameObject]) {
+    let (x, y) = game.player.position();
+    if !is_blocked(x + dx, y + dy, game.player.movement(), &game.map, game_objects) && !partner_blocks(x + dx, y + dy, game) {
+        game.player.set_position(x + dx, y + dy);
+    }
+}
+
+// NOTICE: Doors, opened or smashed by monsters depending on their species
+pub(crate) fn set_terrain(x: i32, y: i32, terrain: Terrain, game: &mut Game) {
+    let explored = game.map[(x, y)].explored;
+    let tile = Tile { explored, ..Tile::terrain(terrain) };
+    game.map[(x, y)] = tile;
+    game.fov.set(x, y, !tile.block_sight, !tile.blocked);
+}
+
+pub(crate) fn monster_move_by(id: usize, dx: i32, dy: i32, game: &mut Game, game_objects: &mut [GameObject]) {
+    let (x, y) = (game_objects[id].x + dx, game_objects[id].y + dy);
+    if game.map.get(x, y).is_none_or(|tile| tile.terrain != Terrain::ClosedDoor) {
+        move_game_object_by(id, dx, dy, game, game_objects);
+        return;
+    }
+    let in_view = game.fov.is_in_fov(x, y);
+    let name = game_objects[id].display_name(&game.config.locale);
+    match game.config.content.door_handling(&game_objects[id].species) {
+        DoorHandling::Open => {
+            set_terrain(x, y, Terrain::OpenDoor, game);
+            if in_view {
+                game.messages.add(tr!(game.config.locale, "door_opened", monster = name), MessageCategory::Danger);
+            }
+        }
+        DoorHandling::Smash => {
+            set_terrain(x, y, Terrain::Rubble, game);
+            let key = if in_view { "door_smashed" } else { "door_smashed_unseen" };
+            game.messages.add(tr!(game.config.locale, key, monster = name), MessageCategory::Danger);
+        }
+        DoorHandling::Stopped => {}
+    }
+}
+
+pub(crate) fn move_toward(from: (i32, i32), to: (i32, i32)) -> Action {
+    let dx = to.0 - from.0;
+    let dy = to.1 - from.1;
+    let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
+
+    let dx = (dx as f32 / distance).round() as i32;
+    let dy = (dy as f32 / distance).round() as i32;
+    Action::Move(dx, dy)
+}
+
+pub(crate) fn close_door(game: &mut Game, game_objects: &[GameObject]) -> bool {
+    let (player_x, player_y) = game.player.position();
+    let door = (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| (player_x + dx, player_y + dy)))
+        .find(|&(x, y)| {
+            game.map.get(x, y).is_some_and(|tile| tile.terrain == Terrain::OpenDoor)
+                && (x, y) != (player_x, player_y)
+                && !game_objects.iter().any(|game_object| game_object.position() == (x, y))
+        });
+    match door {
+        Some((x, y)) => {
+            set_terrain(x, y, Terrain::ClosedDoor, game);
+            game.messages.add(tr!(game.config.locale, "door_closed"), MessageCategory::System);
+            true
+        }
+        None => {
+            game.messages.add(tr!(game.config.locale, "no_door"), MessageCategory::System);
+            false
+        }
+    }
+}
+
+pub(crate) fn initialise_fov(game: &mut Game, game_objects: &[GameObject]) {
+    let (map_width, map_height) = map_size(&game.map);
+    game.fov = FovMap::new(map_width, map_height);
+    for (x, y) in game.map.iter_coords() {
+        game.fov.set(
+            x,
+            y,
+            !game.map[(x, y)].block_sight,
+            !game.map[(x, y)].blocked,
+        );
+    }
+    compute_fov(game, game_objects);
+}
+
+pub(crate) fn compute_fov(game: &mut Game, game_objects: &[GameObject]) {
+    let player = &game.player;
+    game.fov.compute_fov(player.x, player.y, game.config.torch_radius, FOV_LIGHT_WALLS, FOV_ALGORITHM);
+    game.metrics.fov_recomputes += 1;
+
+    // NOTICE: A torch radius of 0 lets the player see the whole map, the torch then lights all of it evenly
+    let torch_radius = if game.config.torch_radius > 0 { game.config.torch_radius as f32 } else { f32::INFINITY };
+    game.lights = vec![Light { x: player.x, y: player.y, radius: torch_radius }];
+    for (x, y) in game.map.iter_coords() {
+        let tile = &mut game.map[(x, y)];
+        if game.fov.is_in_fov(x, y) && !tile.explored {
+            tile.explored = true;
+            game.stats.tiles_explored += 1;
+        }
+        if game.fov.is_in_fov(x, y) && tile.terrain == Terrain::Campfire {
+            game.lights.push(Light { x, y, radius: CAMPFIRE_LIGHT_RADIUS });
+        }
+    }
+
+    let fov = &game.fov;
+    game.remembered.retain(|memory| !fov.is_in_fov(memory.x, memory.y));
+    let see_invisible = sees_invisible(game);
+    let seen = game_objects.iter().filter(|game_object| {
+        game_object.fighter.is_none()
+            && !game_object.always_visible
+            && (!game_object.invisible || see_invisible)
+            && game.fov.is_in_fov(game_object.x, game_object.y)
+    });
+    let memories: Vec<RememberedObject> = seen
+        .map(|game_object| RememberedObject {
+            x: game_object.x,
+            y: game_object.y,
+            char: game_object.char,
+            color: game_object.color,
+        })
+        .collect();
+    game.remembered.extend(memories);
+}
@@ -0,0 +1,73 @@
+use super::*;
+use crate::ui::{Palette, VIEW_WIDTH};
+
+pub(crate) const COLOR_DARK_WALL: Color = Color { 
+    r: 111,
+    g: 103,
+    b: 118,
+};
+
+pub(crate) const COLOR_DARK_GROUND: Color = Color {
+    r: 154,
+    g: 154,
+    b: 151,
+};
+
+pub(crate) const COLOR_LIGHT_WALL: Color = Color {
+    r: 130,
+    g: 110,
+    b: 50,
+};
+
+pub(crate) const COLOR_LIGHT_GROUND: Color = Color {
+    r: 200,
+    g: 180,
+    b: 50,
+};
+
+/// How lit the edge of the torchlight stays, so tiles in view never fade into remembered ones
+pub(crate) const MIN_LIGHT: f32 = 0.3;
+
+impl Terrain {
+    /// Background colors out of and in view, as the palette draws them
+    pub fn colors(self, palette: &Palette) -> (Color, Color) {
+        if let Some(&(_, colors)) = palette.terrain.iter().find(|&&(terrain, _)| terrain == self) {
+            return colors;
+        }
+        match self {
+            Terrain::Floor => (COLOR_DARK_GROUND, COLOR_LIGHT_GROUND),
+            Terrain::Chasm => (Color { r: 10, g: 5, b: 15 }, Color { r: 25, g: 15, b: 35 }),
+            Terrain::DeepWater => (Color { r: 0, g: 20, b: 80 }, Color { r: 20, g: 60, b: 170 }),
+            Terrain::ClosedDoor | Terrain::OpenDoor => (Color { r: 60, g: 40, b: 10 }, Color { r: 130, g: 90, b: 30 }),
+            Terrain::Rubble => (Color { r: 50, g: 50, b: 60 }, Color { r: 110, g: 100, b: 90 }),
+            Terrain::LockedGate | Terrain::UnlockedGate => (Color { r: 40, g: 40, b: 50 }, Color { r: 90, g: 90, b: 110 }),
+            Terrain::GlassWall => (Color { r: 30, g: 60, b: 70 }, Color { r: 120, g: 200, b: 220 }),
+            Terrain::Campfire => (Color { r: 70, g: 30, b: 10 }, Color { r: 200, g: 90, b: 20 }),
+        }
+    }
+}
+
+impl Decal {
+    pub fn color(self) -> Color {
+        match self {
+            Decal::Blood => Color { r: 130, g: 0, b: 0 },
+            Decal::Scorch => Color { r: 20, g: 15, b: 10 },
+        }
+    }
+}
+
+/// First map column in view, scrolled to keep the player centered on levels wider than the view
+pub(crate) fn view_x(game: &Game) -> i32 {
+    let (map_width, _) = map_size(&game.map);
+    (game.player.x - VIEW_WIDTH / 2).clamp(0, cmp::max(map_width - VIEW_WIDTH, 0))
+}
+
+/// Where a tile is drawn on screen, if it is in view
+pub(crate) fn view_position((x, y): (i32, i32), game: &Game) -> Option<(i32, i32)> {
+    let screen_x = x - view_x(game);
+    if (0..VIEW_WIDTH).contains(&screen_x) && (0..MAP_HEIGHT).contains(&y) {
+        Some((screen_x, y))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,13 @@
+use super::*;
+use tcod::input::Mouse;
+use crate::ui::VIEW_WIDTH;
+
+/// The tile under the mouse, none when it is over the sidebar or the panel
+pub(crate) fn mouse_tile(mouse: Mouse, game: &Game) -> Option<(i32, i32)> {
+    let (x, y) = (mouse.cx as i32, mouse.cy as i32);
+    if (0..VIEW_WIDTH).contains(&x) && (0..MAP_HEIGHT).contains(&y) {
+        Some((x + view_x(game), y))
+    } else {
+        None
+    }
+}
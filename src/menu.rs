@@ -0,0 +1,528 @@
+use tcod::console::*;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::arena::start_arena;
+use crate::audio::{Sound, VolumeChannel};
+use crate::character::{class_menu, new_player};
+use crate::config::{CONFIG_FILE, Config, Locale, WindowMode};
+use crate::game::{GameOver, frame_limit, new_game};
+use crate::map::{Map, make_map};
+use crate::portable::KeyCode;
+use crate::portable::colors::*;
+use crate::save::{
+    SaveSlot, SlotChoice, daily_scores_screen, daily_seed, list_save_slots, load_autosave, load_daily_scores,
+    play_game_with_emergency_save, slot_choice, slot_dir, slot_page_options, today,
+};
+use crate::tutorial::start_tutorial;
+use crate::ui::{MAX_NAME_LENGTH, Palette, SCREEN_HEIGHT, SCREEN_WIDTH, Tcod, menu, msgbox, text_input, tile_colors};
+
+const FPS_CHOICES: [i32; 4] = [24, 30, 60, 144];
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MainMenuChoice {
+    NewGame,
+    DailyRun,
+    DailyScores,
+    Arena,
+    Tutorial,
+    Continue,
+    Options,
+    Quit,
+}
+
+fn volume_slider(tcod: &mut Tcod, channel: VolumeChannel, locale: &Locale) {
+    let width = 40;
+    let height = 4;
+    let bar_width = width - 2;
+
+    loop {
+        let mut window = Offscreen::new(width, height);
+        window.set_default_foreground(WHITE);
+        window.print_ex(0, 0, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "volume_slider"));
+        let volume = tcod.audio.volume(channel);
+        let filled = (volume * bar_width as f32).round() as i32;
+        window.set_default_background(LIGHT_BLUE);
+        window.rect(1, 2, filled, 1, false, BackgroundFlag::Screen);
+        window.set_default_background(DARKER_BLUE);
+        window.rect(1 + filled, 2, bar_width - filled, 1, false, BackgroundFlag::Screen);
+        window.print_ex(
+            width / 2,
+            2,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            format!("{}%", (volume * 100.0).round()),
+        );
+
+        let x = SCREEN_WIDTH / 2 - width / 2;
+        let y = SCREEN_HEIGHT / 2 - height / 2;
+        blit(&window, (0, 0), (width, height), &mut tcod.root, (x, y), 1.0, 1.0);
+        tcod.root.flush();
+
+        let key = tcod.root.wait_for_keypress(true);
+        match key.code {
+            KeyCode::Left => tcod.audio.change_volume(channel, -VOLUME_STEP),
+            KeyCode::Right => tcod.audio.change_volume(channel, VOLUME_STEP),
+            KeyCode::Enter | KeyCode::Escape => return,
+            _ => {}
+        }
+        if channel == VolumeChannel::Effects {
+            tcod.audio.play(Sound::Hit);
+        }
+    }
+}
+
+/// Sets one entry of the config file, keeping the rest of it and its comments as they are
+pub(crate) fn save_config_value<T: Serialize>(path: &str, key: &str, value: T) -> Result<(), Box<dyn Error>> {
+    let line = format!("{} = {}", key, toml::Value::try_from(value)?);
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let is_entry = |existing: &str| existing.split('=').next().is_some_and(|name| name.trim() == key);
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+    match lines.iter_mut().find(|existing| is_entry(existing)) {
+        Some(existing) => *existing = line,
+        None => lines.push(line),
+    }
+    fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+// NOTICE: Fonts are the PNG files of assets/fonts, named the way libtcod names its own: those with `_ro` in their
+// name have the ASCII codes in rows of 16, all others the 32 by 8 tcod layout
+const FONT_DIR: &str = "assets/fonts";
+// NOTICE: libtcod draws each glyph at the size of its font, scaled fonts are made once and kept here
+const SCALED_FONT_DIR: &str = "cache/fonts";
+pub(crate) const MAX_RENDER_SCALE: i32 = 3;
+
+fn font_path(font: &str) -> PathBuf {
+    Path::new(FONT_DIR).join(format!("{}.png", font))
+}
+
+/// The layout of a font and how many glyphs it has across and down
+fn font_layout(font: &str) -> (FontLayout, (i32, i32)) {
+    if font.contains("_ro") {
+        (FontLayout::AsciiInRow, (16, 16))
+    } else {
+        (FontLayout::Tcod, (32, 8))
+    }
+}
+
+fn list_fonts() -> Vec<String> {
+    let mut fonts: Vec<String> = fs::read_dir(FONT_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+                .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+                .collect()
+        })
+        .unwrap_or_default();
+    fonts.sort();
+    fonts
+}
+
+/// The size in pixels of a glyph of the font, read from the PNG header, None if the file is no readable font
+pub(crate) fn glyph_size(font: &str) -> Option<(i32, i32)> {
+    let mut header = [0; 24];
+    File::open(font_path(font)).and_then(|mut file| file.read_exact(&mut header)).ok()?;
+    if &header[..8] != b"\x89PNG\r\n\x1a\n" || &header[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]) as i32;
+    let height = u32::from_be_bytes([header[20], header[21], header[22], header[23]]) as i32;
+    let (_, (columns, rows)) = font_layout(font);
+    Some((width / columns, height / rows)).filter(|&(glyph_width, glyph_height)| glyph_width > 0 && glyph_height > 0)
+}
+
+/// The font file blown up `scale` times, made on first use
+fn scaled_font(font: &str, scale: i32) -> Result<PathBuf, String> {
+    if scale <= 1 {
+        return Ok(font_path(font));
+    }
+    let path = Path::new(SCALED_FONT_DIR).join(format!("{}@{}x.png", font, scale));
+    if path.exists() {
+        return Ok(path);
+    }
+    let image = tcod::image::Image::from_file(font_path(font)).map_err(|error| error.to_string())?;
+    let (width, height) = image.get_size();
+    let mut scaled = tcod::image::Image::new(width * scale, height * scale);
+    for y in 0..height * scale {
+        for x in 0..width * scale {
+            scaled.put_pixel(x, y, image.get_pixel(x / scale, y / scale));
+        }
+    }
+    fs::create_dir_all(SCALED_FONT_DIR).map_err(|error| error.to_string())?;
+    scaled.save(&path);
+    Ok(path)
+}
+
+pub(crate) fn init_root(font: &str, scale: i32, mode: WindowMode, vsync: bool) -> Root {
+    let (layout, (columns, rows)) = font_layout(font);
+    match (mode, glyph_size(font)) {
+        (WindowMode::Borderless, _) => {
+            let (width, height) = tcod::system::get_current_resolution();
+            tcod::system::force_fullscreen_resolution(width, height);
+        }
+        (_, Some((glyph_width, glyph_height))) => {
+            tcod::system::force_fullscreen_resolution(glyph_width * scale * SCREEN_WIDTH, glyph_height * scale * SCREEN_HEIGHT);
+        }
+        _ => {}
+    }
+    let path = scaled_font(font, scale).unwrap_or_else(|error| {
+        eprintln!("The {} font could not be scaled {} times: {}", font, scale, error);
+        font_path(font)
+    });
+    let mut initializer = Root::initializer();
+    initializer
+        .font(path, layout)
+        .font_type(FontType::Greyscale)
+        .font_dimensions(columns, rows)
+        .size(SCREEN_WIDTH, SCREEN_HEIGHT)
+        .title("Rust-rogue")
+        .fullscreen(mode != WindowMode::Windowed);
+    // NOTICE: Only the SDL renderer presents through SDL's own renderer, which syncs with the display on this hint
+    std::env::set_var("SDL_RENDER_VSYNC", if vsync { "1" } else { "0" });
+    if vsync {
+        initializer.renderer(Renderer::SDL).init()
+    } else {
+        initializer.init()
+    }
+}
+
+fn font_menu(tcod: &mut Tcod, locale: &Locale) {
+    let mut fonts = list_fonts();
+    fonts.truncate(9);
+    if let Some(index) = menu(&tr!(locale, "font_header"), &fonts, 40, tcod) {
+        let font = fonts.swap_remove(index);
+        reopen_window(tcod, font, tcod.render_scale, tcod.window_mode, locale);
+    }
+}
+
+// NOTICE: The window grows or shrinks with the glyphs, a font and scale too large for the screen are refused and
+// the ones that fit are kept in the config for the next launch
+pub(crate) fn reopen_window(tcod: &mut Tcod, font: String, scale: i32, mode: WindowMode, locale: &Locale) {
+    let (screen_width, screen_height) = tcod::system::get_current_resolution();
+    let too_large = |(glyph_width, glyph_height)| {
+        glyph_width * scale * SCREEN_WIDTH > screen_width || glyph_height * scale * SCREEN_HEIGHT > screen_height
+    };
+    match glyph_size(&font) {
+        Some(glyph) if too_large(glyph) => {
+            msgbox(&tr!(locale, "font_too_large", font = font, scale = scale), 40, tcod);
+        }
+        Some(_) => {
+            tcod.root = init_root(&font, scale, mode, tcod.vsync);
+            let saved = save_config_value(CONFIG_FILE, "font", &font)
+                .and_then(|()| save_config_value(CONFIG_FILE, "render_scale", scale))
+                .and_then(|()| save_config_value(CONFIG_FILE, "window_mode", mode));
+            tcod.font = font;
+            tcod.render_scale = scale;
+            tcod.window_mode = mode;
+            if mode != WindowMode::Windowed {
+                tcod.fullscreen_mode = mode;
+            }
+            if let Err(error) = saved {
+                msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+            }
+        }
+        None => msgbox(&tr!(locale, "font_unreadable", font = font), 40, tcod),
+    }
+}
+
+pub(crate) fn options_menu(tcod: &mut Tcod, locale: &Locale) {
+    while !tcod.root.window_closed() {
+        tcod.root.clear();
+        let sound_state = if tcod.audio.muted {
+            tr!(locale, "sound_off")
+        } else {
+            tr!(locale, "sound_on")
+        };
+        let health_bars_state = if tcod.health_bars {
+            tr!(locale, "health_bars_on")
+        } else {
+            tr!(locale, "health_bars_off")
+        };
+        let screen_motion_state = if tcod.screen_motion {
+            tr!(locale, "motion_on")
+        } else {
+            tr!(locale, "motion_off")
+        };
+        let labels = [
+            tr!(locale, "options_volume", volume = (tcod.audio.volume * 100.0).round()),
+            tr!(locale, "options_music_volume", volume = (tcod.audio.music_volume * 100.0).round()),
+            tr!(locale, "options_sound", state = sound_state),
+            tr!(locale, "options_health_bars", state = health_bars_state),
+            tr!(locale, "options_screen_motion", state = screen_motion_state),
+            tr!(locale, "options_movement_keys", scheme = tcod.movement_keys.name(locale)),
+            tr!(locale, "options_palette", palette = tcod.palette.kind.name(locale)),
+            tr!(locale, "options_display"),
+            tr!(locale, "options_back"),
+        ];
+        match menu(&tr!(locale, "options_header"), &labels, 40, tcod) {
+            Some(0) => volume_slider(tcod, VolumeChannel::Effects, locale),
+            Some(1) => volume_slider(tcod, VolumeChannel::Music, locale),
+            Some(2) => tcod.audio.toggle_mute(),
+            Some(3) => {
+                tcod.health_bars = !tcod.health_bars;
+                if let Err(error) = save_config_value(CONFIG_FILE, "monster_health_bars", tcod.health_bars) {
+                    msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+                }
+            }
+            Some(4) => {
+                tcod.screen_motion = !tcod.screen_motion;
+                if let Err(error) = save_config_value(CONFIG_FILE, "screen_motion", tcod.screen_motion) {
+                    msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+                }
+            }
+            Some(5) => {
+                tcod.movement_keys = tcod.movement_keys.next();
+                if let Err(error) = save_config_value(CONFIG_FILE, "movement_keys", tcod.movement_keys) {
+                    msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+                }
+            }
+            Some(6) => {
+                tcod.palette = Palette::new(tcod.palette.kind.next());
+                tcod.map_cells.clear();
+                if let Err(error) = save_config_value(CONFIG_FILE, "palette", tcod.palette.kind) {
+                    msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+                }
+            }
+            Some(7) => display_menu(tcod, locale),
+            _ => break,
+        }
+    }
+}
+
+// NOTICE: Window settings, each change is kept in the config for the next launch
+fn display_menu(tcod: &mut Tcod, locale: &Locale) {
+    while !tcod.root.window_closed() {
+        tcod.root.clear();
+        let vsync_state = if tcod.vsync { tr!(locale, "vsync_on") } else { tr!(locale, "vsync_off") };
+        let labels = [
+            tr!(locale, "options_font", font = tcod.font),
+            tr!(locale, "options_scale", scale = tcod.render_scale),
+            tr!(locale, "options_window_mode", mode = tcod.window_mode.name(locale)),
+            tr!(locale, "options_fps", fps = tcod.fps_limit),
+            tr!(locale, "options_vsync", state = vsync_state),
+            tr!(locale, "options_back"),
+        ];
+        match menu(&tr!(locale, "options_display_header"), &labels, 40, tcod) {
+            Some(0) => font_menu(tcod, locale),
+            Some(1) => {
+                let scale = tcod.render_scale % MAX_RENDER_SCALE + 1;
+                reopen_window(tcod, tcod.font.clone(), scale, tcod.window_mode, locale);
+            }
+            Some(2) => {
+                reopen_window(tcod, tcod.font.clone(), tcod.render_scale, tcod.window_mode.next(), locale);
+            }
+            Some(3) => {
+                tcod.fps_limit = FPS_CHOICES.iter().copied().find(|&fps| fps > tcod.fps_limit).unwrap_or(FPS_CHOICES[0]);
+                tcod::system::set_fps(frame_limit(tcod.fps_limit, tcod.vsync));
+                if let Err(error) = save_config_value(CONFIG_FILE, "fps_limit", tcod.fps_limit) {
+                    msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+                }
+            }
+            Some(4) => {
+                tcod.vsync = !tcod.vsync;
+                tcod::system::set_fps(frame_limit(tcod.fps_limit, tcod.vsync));
+                reopen_window(tcod, tcod.font.clone(), tcod.render_scale, tcod.window_mode, locale);
+                if let Err(error) = save_config_value(CONFIG_FILE, "vsync", tcod.vsync) {
+                    msgbox(&tr!(locale, "config_save_failed", error = error), 40, tcod);
+                }
+            }
+            _ => break,
+        }
+    }
+}
+
+// NOTICE: Title screen, the logo over a dungeon lit by a wandering torch
+const TITLE_LOGO: [&str; 5] = [
+    "####  #   #  #### #####     ####   ###   #### #   # #####",
+    "#   # #   # #       #       #   # #   # #     #   # #    ",
+    "####  #   #  ###    #       ####  #   # #  ## #   # #### ",
+    "#  #  #   #     #   #       #  #  #   # #   # #   # #    ",
+    "#   #  ###  ####    #       #   #  ###   ####  ###  #####",
+];
+const TITLE_LOGO_Y: i32 = 7;
+const TITLE_TORCH_RADIUS: f32 = 12.0;
+
+pub(crate) struct TitleScreen {
+    map: Map,
+    subtitle: String,
+}
+
+impl TitleScreen {
+    pub fn new(config: &Config) -> Self {
+        let backdrop_config = Config {
+            map_width: SCREEN_WIDTH,
+            map_height: SCREEN_HEIGHT,
+            ..config.clone()
+        };
+        let mut player = new_player();
+        TitleScreen {
+            map: make_map(&mut player, &mut vec![], &backdrop_config, &mut rand::weak_rng()),
+            subtitle: tr!(config.locale, "title"),
+        }
+    }
+}
+
+pub(crate) fn render_title_screen(title_screen: &TitleScreen, root: &mut Root, palette: &Palette) {
+    let time = tcod::system::get_elapsed_time().as_secs_f32();
+    let torch = (
+        SCREEN_WIDTH as f32 / 2.0 + (SCREEN_WIDTH as f32 / 2.0 - 8.0) * (time * 0.13).sin(),
+        SCREEN_HEIGHT as f32 / 2.0 + (SCREEN_HEIGHT as f32 / 2.0 - 6.0) * (time * 0.21).sin(),
+    );
+    let radius = TITLE_TORCH_RADIUS + (time * 7.0).sin() * 0.5;
+    for x in 0..SCREEN_WIDTH {
+        for y in 0..SCREEN_HEIGHT {
+            let (dark, light) = tile_colors(&title_screen.map[(x, y)], palette);
+            let distance = ((x as f32 - torch.0).powi(2) + (y as f32 - torch.1).powi(2)).sqrt();
+            let lit = (1.0 - distance / radius).max(0.0);
+            let color = lerp(lerp(BLACK, dark, 0.25), light, lit * 0.8);
+            root.put_char_ex(x, y, ' ', BLACK, color);
+        }
+    }
+
+    let logo_x = SCREEN_WIDTH / 2 - TITLE_LOGO[0].len() as i32 / 2;
+    for (row, line) in TITLE_LOGO.iter().enumerate() {
+        for (column, _) in line.char_indices().filter(|&(_, c)| c == '#') {
+            let y = TITLE_LOGO_Y + row as i32;
+            root.set_char_background(logo_x + column as i32 + 1, y + 1, BLACK, BackgroundFlag::Set);
+            root.set_char_background(logo_x + column as i32, y, palette.color(LIGHT_YELLOW), BackgroundFlag::Set);
+        }
+    }
+    root.set_default_foreground(palette.color(LIGHT_YELLOW));
+    root.print_ex(
+        SCREEN_WIDTH / 2,
+        TITLE_LOGO_Y + TITLE_LOGO.len() as i32 + 2,
+        BackgroundFlag::None,
+        TextAlignment::Center,
+        &title_screen.subtitle,
+    );
+}
+
+/// Shows the main menu, unless the game that just ended already asked for a new game or to quit
+pub(crate) fn main_menu(tcod: &mut Tcod, config: &Config, mut outcome: GameOver) {
+    let mut title_screen = Some(TitleScreen::new(config));
+    while !tcod.root.window_closed() {
+        tcod.root.set_default_background(BLACK);
+        tcod.root.clear();
+
+        let slots = list_save_slots();
+
+        let choice = match outcome {
+            GameOver::Quit => break,
+            GameOver::NewGame => Some(MainMenuChoice::NewGame),
+            GameOver::MainMenu => main_menu_choice(tcod, config, &slots, &mut title_screen),
+        };
+        outcome = GameOver::MainMenu;
+        match choice {
+            Some(MainMenuChoice::NewGame) => {
+                if let Some(player_name) = prompt_player_name(tcod, config, &slots) {
+                    if let Some(class) = class_menu(tcod, &config.locale) {
+                        let (mut game, mut game_objects) = new_game(&player_name, class, config);
+                        outcome = play_game_with_emergency_save(tcod, &mut game, &mut game_objects);
+                    }
+                }
+            }
+            Some(MainMenuChoice::DailyRun) => {
+                let date = today();
+                if let Some(player_name) = prompt_player_name(tcod, config, &slots) {
+                    if load_daily_scores().iter().any(|score| score.date == date && score.player_name == player_name) {
+                        msgbox(&tr!(config.locale, "daily_already_played", name = player_name), 30, tcod);
+                    } else if let Some(class) = class_menu(tcod, &config.locale) {
+                        let daily_config = Config { seed: Some(daily_seed(&date)), ..config.clone() };
+                        let (mut game, mut game_objects) = new_game(&player_name, class, &daily_config);
+                        game.daily = Some(date);
+                        outcome = play_game_with_emergency_save(tcod, &mut game, &mut game_objects);
+                    }
+                }
+            }
+            Some(MainMenuChoice::DailyScores) => daily_scores_screen(tcod, config),
+            Some(MainMenuChoice::Arena) => {
+                if let Some(player_name) = prompt_player_name(tcod, config, &slots) {
+                    if let Some(class) = class_menu(tcod, &config.locale) {
+                        let (mut game, mut game_objects) = new_game(&player_name, class, config);
+                        start_arena(&mut game, &mut game_objects);
+                        outcome = play_game_with_emergency_save(tcod, &mut game, &mut game_objects);
+                    }
+                }
+            }
+            Some(MainMenuChoice::Tutorial) => {
+                if let Some(player_name) = prompt_player_name(tcod, config, &slots) {
+                    if let Some(class) = class_menu(tcod, &config.locale) {
+                        let (mut game, mut game_objects) = new_game(&player_name, class, config);
+                        start_tutorial(&mut game, &mut game_objects);
+                        outcome = play_game_with_emergency_save(tcod, &mut game, &mut game_objects);
+                    }
+                }
+            }
+            Some(MainMenuChoice::Continue) => {
+                let mut first = 0;
+                loop {
+                    let options = slot_page_options(&slots, first, &config.locale);
+                    let option = match menu(&tr!(config.locale, "choose_slot"), &options, 50, tcod) {
+                        Some(option) => option,
+                        None => break,
+                    };
+                    match slot_choice(option, first, slots.len()) {
+                        SlotChoice::Page(page) => first = page,
+                        SlotChoice::Slot(slot_index) => {
+                            match load_autosave(&slots[slot_index].player_name, config) {
+                                Some((mut game, mut game_objects)) => {
+                                    outcome = play_game_with_emergency_save(tcod, &mut game, &mut game_objects);
+                                }
+                                None => {
+                                    msgbox(&tr!(config.locale, "save_load_failed"), 24, tcod);
+                                }
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+            Some(MainMenuChoice::Options) => options_menu(tcod, &config.locale),
+            Some(MainMenuChoice::Quit) => break,
+            _ => {}
+        }
+    }
+}
+
+/// Asks for the name of a new character, turning away names that already have a save slot
+fn prompt_player_name(tcod: &mut Tcod, config: &Config, slots: &[SaveSlot]) -> Option<String> {
+    let player_name = text_input(&tr!(config.locale, "enter_name"), MAX_NAME_LENGTH, 30, &mut tcod.root)?;
+    if slots.iter().any(|slot| slot.player_name == player_name) || slot_dir(&player_name).exists() {
+        msgbox(&tr!(config.locale, "name_taken", name = player_name), 30, tcod);
+        return None;
+    }
+    Some(player_name)
+}
+
+fn main_menu_choice(tcod: &mut Tcod, config: &Config, slots: &[SaveSlot], title_screen: &mut Option<TitleScreen>) -> Option<MainMenuChoice> {
+    let mut choices = vec![MainMenuChoice::NewGame, MainMenuChoice::DailyRun, MainMenuChoice::DailyScores, MainMenuChoice::Arena, MainMenuChoice::Tutorial];
+    if !slots.is_empty() {
+        choices.push(MainMenuChoice::Continue);
+    }
+    choices.push(MainMenuChoice::Options);
+    choices.push(MainMenuChoice::Quit);
+
+    let labels: Vec<String> = choices
+        .iter()
+        .map(|choice| match choice {
+            MainMenuChoice::NewGame => tr!(config.locale, "menu_new_game"),
+            MainMenuChoice::DailyRun => tr!(config.locale, "menu_daily_run"),
+            MainMenuChoice::DailyScores => tr!(config.locale, "menu_daily_scores"),
+            MainMenuChoice::Arena => tr!(config.locale, "menu_arena"),
+            MainMenuChoice::Tutorial => tr!(config.locale, "menu_tutorial"),
+            MainMenuChoice::Continue => tr!(config.locale, "menu_continue"),
+            MainMenuChoice::Options => tr!(config.locale, "menu_options"),
+            MainMenuChoice::Quit => tr!(config.locale, "menu_quit"),
+        })
+        .collect();
+
+    tcod.title_screen = title_screen.take();
+    let choice = menu("", &labels, 24, tcod);
+    *title_screen = tcod.title_screen.take();
+    choice.map(|index| choices[index])
+}
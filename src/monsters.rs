@@ -0,0 +1,588 @@
+use std::{cmp, mem};
+use rand::{Rng, XorShiftRng};
+use serde::{Deserialize, Serialize};
+use crate::character::{Skill, train};
+use crate::combat::{
+    Ai, DamageType, DeathCallback, Fighter, RangedAttack, StatusEffect, Target, fire_projectile, ranged_hit,
+};
+use crate::config::{Ability, AffixDefinition, Config, Name, UniqueDefinition};
+use crate::controls::Action;
+use crate::game::{Game, MessageCategory, execute_action};
+use crate::map::{Map, Movement, Terrain, blocks_movement, move_toward};
+use crate::object::GameObject;
+use crate::portable::Line;
+use crate::portable::colors::*;
+
+const NOTICE_DISTANCE: f32 = 9.0;
+const STEALTH_PER_LEVEL: f32 = 1.5;
+const RANGED_KEEP_DISTANCE: f32 = 3.0;
+const UNIQUE_CHANCE: f32 = 0.25;
+const UNAFFIXED_WEIGHT: u32 = 60;
+const SHAMAN_MANA: i32 = 10;
+const SPELL_RANGE: f32 = 6.0;
+const MAX_SUMMONED_ALLIES: usize = 3;
+const FIREBOLT_DAMAGE: i32 = 4;
+const HEAL_ALLY_AMOUNT: i32 = 5;
+
+// NOTICE: Monster variants, the deeper the level the more likely the stronger ones
+pub(crate) fn apply_affixes<R: Rng>(level: u32, config: &Config, rng: &mut R, game_objects: &mut [GameObject]) {
+    for game_object in game_objects.iter_mut().filter(|o| o.ai.is_some()) {
+        let affixes: Vec<&AffixDefinition> = config
+            .content
+            .affixes
+            .iter()
+            .filter(|affix| affix.species.contains(&game_object.species) && affix.weight(level) > 0)
+            .collect();
+        let total_weight = UNAFFIXED_WEIGHT + affixes.iter().map(|affix| affix.weight(level)).sum::<u32>();
+        let mut roll = rng.gen_range(0, total_weight);
+        let affix = affixes.into_iter().find(|affix| {
+            if roll < affix.weight(level) {
+                true
+            } else {
+                roll -= affix.weight(level);
+                false
+            }
+        });
+        if let Some(affix) = affix {
+            game_object.name = Name::key("affixed_monster")
+                .with("affix", Name::key(&affix.name))
+                .with("monster", mem::take(&mut game_object.name));
+            if let Some(fighter) = game_object.fighter.as_mut() {
+                fighter.max_hp += affix.max_hp_bonus;
+                fighter.hp += affix.max_hp_bonus;
+                fighter.power += affix.power_bonus;
+                fighter.defense += affix.defense_bonus;
+            }
+            game_object.abilities.extend(affix.ability);
+        }
+    }
+}
+
+// NOTICE: Named elites, each one spawns at most once per run
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Unique {
+    pub(crate) drop: String,
+    announced: bool,
+}
+
+pub(crate) fn place_unique(game: &mut Game, game_objects: &mut [GameObject], rng: &mut XorShiftRng) {
+    if rng.gen::<f32>() >= UNIQUE_CHANCE {
+        return;
+    }
+    let candidates: Vec<UniqueDefinition> = game
+        .config
+        .content
+        .uniques
+        .iter()
+        .filter(|unique| unique.min_level <= game.dungeon_level && !game.spawned_uniques.contains(&unique.name))
+        .filter(|unique| game_objects.iter().any(|o| o.ai.is_some() && o.species == unique.species))
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+    let unique = &candidates[rng.gen_range(0, candidates.len())];
+    let hosts: Vec<usize> = (0..game_objects.len())
+        .filter(|&id| game_objects[id].ai.is_some() && game_objects[id].species == unique.species)
+        .collect();
+    let monster = &mut game_objects[hosts[rng.gen_range(0, hosts.len())]];
+    monster.name = Name::key(&unique.name);
+    monster.description = Name::key(&format!("{}_description", unique.name));
+    monster.char = unique.glyph;
+    monster.color = unique.color;
+    if let Some(fighter) = monster.fighter.as_mut() {
+        fighter.max_hp = unique.max_hp;
+        fighter.hp = unique.max_hp;
+        fighter.defense = unique.defense;
+        fighter.power = unique.power;
+    }
+    monster.unique = Some(Unique {
+        drop: unique.drop.clone(),
+        announced: false,
+    });
+    game.spawned_uniques.push(unique.name.clone());
+}
+
+pub(crate) fn announce_uniques(game: &mut Game, game_objects: &mut [GameObject]) {
+    for game_object in game_objects.iter_mut() {
+        let in_fov = game.fov.is_in_fov(game_object.x, game_object.y);
+        if let Some(unique) = game_object.unique.as_mut() {
+            if in_fov && !unique.announced && game_object.fighter.is_some() {
+                unique.announced = true;
+                game.messages.add(
+                    tr!(game.config.locale, "unique_spotted", monster = game_object.display_name(&game.config.locale)),
+                    MessageCategory::Danger,
+                );
+            }
+        }
+    }
+}
+
+pub(crate) fn in_line_of_sight(from: (i32, i32), to: (i32, i32), map: &Map) -> bool {
+    Line::new(from, to)
+        .take_while(|&position| position != to)
+        .all(|(x, y)| !map[(x, y)].block_sight)
+}
+
+pub(crate) fn turn_abilities(monster_id: usize, game: &mut Game, game_objects: &mut [GameObject]) {
+    for ability in game_objects[monster_id].abilities(&game.config.content) {
+        if let Ability::Regeneration { amount } = ability {
+            let burning = game_objects[monster_id]
+                .statuses
+                .iter()
+                .any(|status| matches!(status, StatusEffect::Burning { .. }));
+            if !burning {
+                game_objects[monster_id].heal(amount);
+            }
+        }
+    }
+}
+
+pub(crate) fn new_monster<R: Rng>(x: i32, y: i32, config: &Config, rng: &mut R) -> GameObject {
+    let dice = rng.gen::<f32>();
+    let species = if dice < 0.55 {
+        "orc"
+    } else if dice < 0.7 {
+        "troll"
+    } else if dice < 0.82 {
+        "archer"
+    } else if dice < 0.92 {
+        "spitter"
+    } else if dice < 0.96 {
+        "shaman"
+    } else {
+        "ghost"
+    };
+    new_monster_of(species, x, y, config)
+}
+
+#[cfg(feature = "window")]
+pub(crate) const MONSTER_SPECIES: [&str; 6] = ["orc", "troll", "archer", "spitter", "shaman", "ghost"];
+
+/// Anything but the other species makes an orc
+pub(crate) fn new_monster_of(species: &str, x: i32, y: i32, config: &Config) -> GameObject {
+    let mut monster = match species {
+        "troll" => {
+            let mut troll = GameObject::new(x, y, 't', DARKER_GREEN, "troll", true);
+            troll.fighter = Some(Fighter {
+                max_hp: 16,
+                hp: 16,
+                defense: 1,
+                power: 4,
+                xp: 100,
+                on_death: DeathCallback::Monster,
+            });
+            troll.description = Name::key("troll_description");
+            troll.species = "troll".into();
+
+            troll
+        }
+        "archer" => {
+            let mut archer = GameObject::new(x, y, 'a', LIGHT_SEPIA, "archer", true);
+            archer.fighter = Some(Fighter {
+                max_hp: 6,
+                hp: 6,
+                defense: 0,
+                power: 1,
+                xp: 30,
+                on_death: DeathCallback::Monster,
+            });
+            archer.ranged = Some(RangedAttack {
+                range: 6,
+                damage: 2,
+                damage_type: DamageType::Physical,
+            });
+            archer.ai = Some(Ai::Ranged);
+            archer.description = Name::key("archer_description");
+            archer.species = "archer".into();
+
+            archer
+        }
+        "spitter" => {
+            let mut spitter = GameObject::new(x, y, 's', LIME, "spitter", true);
+            spitter.fighter = Some(Fighter {
+                max_hp: 8,
+                hp: 8,
+                defense: 0,
+                power: 1,
+                xp: 35,
+                on_death: DeathCallback::Monster,
+            });
+            spitter.ranged = Some(RangedAttack {
+                range: 4,
+                damage: 3,
+                damage_type: DamageType::Acid,
+            });
+            spitter.ai = Some(Ai::Ranged);
+            spitter.description = Name::key("spitter_description");
+            spitter.species = "spitter".into();
+
+            spitter
+        }
+        "shaman" => {
+            let mut shaman = GameObject::new(x, y, 'S', LIGHT_MAGENTA, "shaman", true);
+            shaman.fighter = Some(Fighter {
+                max_hp: 8,
+                hp: 8,
+                defense: 0,
+                power: 2,
+                xp: 50,
+                on_death: DeathCallback::Monster,
+            });
+            shaman.caster = Some(Caster {
+                mana: SHAMAN_MANA,
+                max_mana: SHAMAN_MANA,
+            });
+            shaman.ai = Some(Ai::Caster);
+            shaman.description = Name::key("shaman_description");
+            shaman.species = "shaman".into();
+
+            shaman
+        }
+        "ghost" => {
+            let mut ghost = GameObject::new(x, y, 'G', LIGHTEST_GREY, "ghost", true);
+            ghost.fighter = Some(Fighter {
+                max_hp: 6,
+                hp: 6,
+                defense: 0,
+                power: 3,
+                xp: 45,
+                on_death: DeathCallback::Monster,
+            });
+            ghost.invisible = true;
+            ghost.description = Name::key("ghost_description");
+            ghost.species = "ghost".into();
+
+            ghost
+        }
+        _ => {
+            let mut orc = GameObject::new(x, y, 'o', DESATURATED_GREEN, "orc", true);
+            orc.fighter = Some(Fighter {
+                max_hp: 10,
+                hp: 10,
+                defense: 0,
+                power: 3,
+                xp: 35,
+                on_death: DeathCallback::Monster,
+            });
+            orc.description = Name::key("orc_description");
+            orc.species = "orc".into();
+
+            orc
+        }
+    };
+    monster.is_alive = true;
+    monster.ai.get_or_insert(Ai::Basic);
+    monster.movement = config.content.movement(&monster.species);
+    monster
+}
+
+pub(crate) fn ai_take_turn(monster_id: usize, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    use Ai::*;
+    if let Some(ai) = game_objects[monster_id].ai.take() {
+        let (new_ai, action) = match ai {
+            Basic => ai_basic(monster_id, game, game_objects),
+            Ranged => ai_ranged(monster_id, game, game_objects),
+            Caster => ai_caster(monster_id, game, game_objects),
+            Confused {
+                previous_ai,
+                num_turns,
+            } => ai_confused(monster_id, game, game_objects, previous_ai, num_turns),
+            Frozen {
+                previous_ai,
+                num_turns,
+            } => ai_frozen(monster_id, game, game_objects, previous_ai, num_turns),
+        };
+        execute_action(Target::Object(monster_id), action, game, game_objects);
+        game_objects[monster_id].ai = Some(new_ai);
+    }
+}
+
+fn ai_frozen(monster_id: usize, game: &mut Game, game_objects: &[GameObject], previous_ai: Box<Ai>, num_turns: i32) -> (Ai, Action) {
+    if num_turns >= 0 {
+        let ai = Ai::Frozen {
+            previous_ai,
+            num_turns: num_turns - 1,
+        };
+        (ai, Action::Wait)
+    } else {
+        game.messages.add(
+            tr!(game.config.locale, "no_longer_frozen", monster = game_objects[monster_id].display_name(&game.config.locale)),
+            MessageCategory::Combat,
+        );
+        (*previous_ai, Action::Wait)
+    }
+}
+
+// NOTICE: Ranged monsters back off when the player gets close, and shoot when in range
+fn ai_ranged(monster_id: usize, game: &Game, game_objects: &[GameObject]) -> (Ai, Action) {
+    let monster = &game_objects[monster_id];
+    let (monster_x, monster_y) = monster.position();
+    if !game.fov.is_in_fov(monster_x, monster_y) || !game.player.is_alive {
+        return (Ai::Ranged, Action::Wait);
+    }
+
+    let (player_x, player_y) = game.player.position();
+    let distance = monster.distance_to(&game.player);
+    let action = match monster.ranged {
+        Some(_) if distance < RANGED_KEEP_DISTANCE => {
+            let (dx, dy) = ((monster_x - player_x).signum(), (monster_y - player_y).signum());
+            let (x, y) = (monster_x + dx, monster_y + dy);
+            let cornered = game.map.get(x, y).is_none_or(|tile| tile.terrain != Terrain::ClosedDoor) && blocks_movement(x, y, monster.movement(), game, game_objects);
+            if cornered && distance < 2.0 { Action::Attack(Target::Player) } else { Action::Move(dx, dy) }
+        }
+        Some(ranged) if distance <= ranged.range as f32 => Action::Fire(player_x, player_y),
+        _ => move_toward((monster_x, monster_y), (player_x, player_y)),
+    };
+    (Ai::Ranged, action)
+}
+
+pub(crate) fn monster_fire(monster_id: usize, target: (i32, i32), game: &mut Game, game_objects: &mut [GameObject]) {
+    let ranged = match game_objects[monster_id].ranged {
+        Some(ranged) => ranged,
+        None => return,
+    };
+    let start = game_objects[monster_id].position();
+    let (_, hit) = fire_projectile(start, target, '*', ranged.damage_type.color(), game, game_objects);
+    let projectile = tr!(game.config.locale, ranged.damage_type.projectile_key());
+    match hit {
+        Some(target) => ranged_hit(target, &projectile, ranged.damage, ranged.damage_type, game, game_objects),
+        None => game.messages.add(tr!(game.config.locale, "projectile_miss", projectile = projectile), MessageCategory::Combat),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Caster {
+    pub(crate) mana: i32,
+    pub(crate) max_mana: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Spell {
+    HealAlly,
+    SummonRat,
+    Firebolt,
+}
+
+impl Spell {
+    const ALL: [Spell; 3] = [Spell::HealAlly, Spell::SummonRat, Spell::Firebolt];
+
+    pub fn cost(self) -> i32 {
+        match self {
+            Spell::HealAlly => 4,
+            Spell::SummonRat => 6,
+            Spell::Firebolt => 3,
+        }
+    }
+}
+
+fn wounded_ally(caster_id: usize, game: &Game, game_objects: &[GameObject]) -> Option<usize> {
+    game_objects
+        .iter()
+        .enumerate()
+        .filter(|&(_, game_object)| {
+            game_object.ai.is_some()
+                && game.fov.is_in_fov(game_object.x, game_object.y)
+                && game_object.distance_to(&game_objects[caster_id]) <= SPELL_RANGE
+        })
+        .filter_map(|(id, game_object)| game_object.fighter.map(|f| (id, f.max_hp - f.hp)))
+        .filter(|&(_, missing_hp)| missing_hp > 0)
+        .max_by_key(|&(_, missing_hp)| missing_hp)
+        .map(|(id, _)| id)
+}
+
+/// How much casting `spell` is worth right now, zero meaning it is pointless
+fn spell_utility(spell: Spell, caster_id: usize, game: &Game, game_objects: &[GameObject]) -> i32 {
+    let caster = &game_objects[caster_id];
+    match spell {
+        Spell::HealAlly => wounded_ally(caster_id, game, game_objects)
+            .and_then(|ally_id| game_objects[ally_id].fighter)
+            .map_or(0, |fighter| 10 * (fighter.max_hp - fighter.hp) / fighter.max_hp),
+        Spell::SummonRat => {
+            let allies = game_objects
+                .iter()
+                .enumerate()
+                .filter(|&(id, game_object)| id != caster_id && game_object.ai.is_some() && game_object.distance_to(caster) <= SPELL_RANGE)
+                .count();
+            if allies < MAX_SUMMONED_ALLIES { 4 - allies as i32 } else { 0 }
+        }
+        Spell::Firebolt => {
+            if caster.distance_to(&game.player) <= SPELL_RANGE { 5 } else { 0 }
+        }
+    }
+}
+
+pub(crate) fn cast_spell(spell: Spell, caster_id: usize, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let caster_name = game_objects[caster_id].display_name(&game.config.locale);
+    let caster_position = game_objects[caster_id].position();
+    match spell {
+        Spell::HealAlly => {
+            if let Some(ally_id) = wounded_ally(caster_id, game, game_objects) {
+                game_objects[ally_id].heal(HEAL_ALLY_AMOUNT);
+                game.messages.add(
+                    tr!(
+                        game.config.locale,
+                        "spell_heal_ally",
+                        caster = caster_name,
+                        ally = game_objects[ally_id].display_name(&game.config.locale)
+                    ),
+                    MessageCategory::Combat,
+                );
+            }
+        }
+        Spell::SummonRat => {
+            let (x, y) = caster_position;
+            let free_tile = (-1..=1)
+                .flat_map(|dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+                .find(|&(x, y)| !blocks_movement(x, y, Movement::default(), game, game_objects));
+            if let Some((rat_x, rat_y)) = free_tile {
+                game_objects.push(new_rat(rat_x, rat_y));
+                game.messages.add(tr!(game.config.locale, "spell_summon_rat", caster = caster_name), MessageCategory::Danger);
+            }
+        }
+        Spell::Firebolt => {
+            let target = game.player.position();
+            let (_, hit) = fire_projectile(caster_position, target, '*', DamageType::Fire.color(), game, game_objects);
+            let projectile = tr!(game.config.locale, DamageType::Fire.projectile_key());
+            match hit {
+                Some(target) => ranged_hit(target, &projectile, FIREBOLT_DAMAGE, DamageType::Fire, game, game_objects),
+                None => game.messages.add(tr!(game.config.locale, "projectile_miss", projectile = projectile), MessageCategory::Combat),
+            }
+        }
+    }
+}
+
+fn new_rat(x: i32, y: i32) -> GameObject {
+    let mut rat = GameObject::new(x, y, 'r', LIGHT_SEPIA, "rat", true);
+    rat.fighter = Some(Fighter {
+        max_hp: 3,
+        hp: 3,
+        defense: 0,
+        power: 2,
+        xp: 10,
+        on_death: DeathCallback::Monster,
+    });
+    rat.ai = Some(Ai::Basic);
+    rat.is_alive = true;
+    rat.description = Name::key("rat_description");
+    rat.species = "rat".into();
+    rat
+}
+
+// NOTICE: Casters pick the most useful spell they can afford each turn, mana regenerates slowly
+fn ai_caster(monster_id: usize, game: &mut Game, game_objects: &mut [GameObject]) -> (Ai, Action) {
+    let (monster_x, monster_y) = game_objects[monster_id].position();
+    let caster = match game_objects[monster_id].caster {
+        Some(caster) => caster,
+        None => return ai_basic(monster_id, game, game_objects),
+    };
+
+    let mut mana = cmp::min(caster.mana + 1, caster.max_mana);
+    let mut action = Action::Wait;
+    if game.fov.is_in_fov(monster_x, monster_y) && game.player.is_alive {
+        let best_spell = Spell::ALL
+            .iter()
+            .filter(|spell| spell.cost() <= mana)
+            .map(|&spell| (spell, spell_utility(spell, monster_id, game, game_objects)))
+            .filter(|&(_, utility)| utility > 0)
+            .max_by_key(|&(_, utility)| utility);
+
+        match best_spell {
+            Some((spell, _)) => {
+                mana -= spell.cost();
+                action = Action::Cast(spell);
+            }
+            None => {
+                action = ai_basic(monster_id, game, game_objects).1;
+            }
+        }
+    }
+
+    if let Some(ref mut caster) = game_objects[monster_id].caster {
+        caster.mana = mana;
+    }
+    (Ai::Caster, action)
+}
+
+fn ai_basic(monster_id: usize, game: &mut Game, game_objects: &mut [GameObject]) -> (Ai, Action) {
+    let (monster_x, monster_y) = game_objects[monster_id].position();
+    let mut action = Action::Wait;
+    if game.fov.is_in_fov(monster_x, monster_y) && notices_player(monster_id, game, game_objects) {
+        let (target, (target_x, target_y)) = closest_hero((monster_x, monster_y), game);
+        game_objects[monster_id].last_seen_player = Some((target_x, target_y));
+        if game_objects[monster_id].distance(target_x, target_y) >= 2.0 {
+            action = move_toward((monster_x, monster_y), (target_x, target_y));
+        } else if target.game_object(game, game_objects).fighter.is_some_and(|f| f.hp > 0) {
+            action = Action::Attack(target);
+        }
+    } else if let Some(target) = game_objects[monster_id].heard_noise {
+        if (monster_x, monster_y) == target {
+            game_objects[monster_id].heard_noise = None;
+        } else {
+            action = move_toward((monster_x, monster_y), target);
+        }
+    }
+    (Ai::Basic, action)
+}
+
+/// The player, or the co-op partner when it stands closer and is still alive
+fn closest_hero((x, y): (i32, i32), game: &Game) -> (Target, (i32, i32)) {
+    match game.partner {
+        Some(ref partner) if partner.is_alive && partner.distance(x, y) < game.player.distance(x, y) => (Target::Partner, partner.position()),
+        _ => (Target::Player, game.player.position()),
+    }
+}
+
+/// Monsters unaware of the player only spot them up close, the closer the stealthier they are
+fn notices_player(monster_id: usize, game: &mut Game, game_objects: &[GameObject]) -> bool {
+    if game_objects[monster_id].last_seen_player.is_some() {
+        return true;
+    }
+    let stealth = game.skills.level(Skill::Stealth) as f32 * STEALTH_PER_LEVEL;
+    let noticed = game_objects[monster_id].distance_to(&game.player) <= NOTICE_DISTANCE - stealth;
+    if !noticed {
+        train(Skill::Stealth, 1, game);
+    }
+    noticed
+}
+
+fn ai_confused(monster_id: usize, game: &mut Game, game_objects: &[GameObject], previous_ai: Box<Ai>, num_turns: i32) -> (Ai, Action) {
+    if num_turns >= 0 {
+        let dx = game.rng.gen_range(-1, 2);
+        let dy = game.rng.gen_range(-1, 2);
+        let ai = Ai::Confused {
+            previous_ai,
+            num_turns: num_turns - 1,
+        };
+        (ai, Action::Move(dx, dy))
+    } else {
+        game.messages.add(
+            tr!(game.config.locale, "no_longer_confused", monster = game_objects[monster_id].display_name(&game.config.locale)),
+            MessageCategory::Combat,
+        );
+        (*previous_ai, Action::Wait)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::open_room;
+
+    #[test]
+    fn monsters_close_in_then_attack() {
+        let (mut game, mut game_objects) = open_room((5, 2));
+        if let Some(ref mut fighter) = game.player.fighter {
+            fighter.defense = 0;
+        }
+
+        ai_take_turn(0, &mut game, &mut game_objects);
+        assert_eq!(game_objects[0].position(), (4, 2));
+        ai_take_turn(0, &mut game, &mut game_objects);
+        assert_eq!(game_objects[0].position(), (3, 2));
+        let hp = game.player.fighter.unwrap().hp;
+        ai_take_turn(0, &mut game, &mut game_objects);
+        assert_eq!(game_objects[0].position(), (3, 2));
+        assert!(game.player.fighter.unwrap().hp < hp);
+    }
+}
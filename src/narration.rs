@@ -0,0 +1,112 @@
+use std::{cmp, process, thread};
+use std::sync::mpsc::{self, Sender};
+use crate::combat::sees_invisible;
+use crate::config::Config;
+use crate::game::Game;
+use crate::object::GameObject;
+use crate::ui::Tcod;
+
+// NOTICE: Accessibility output, receives every message & surroundings description as plain text
+pub(crate) trait Narrator {
+    fn speak(&mut self, text: &str);
+}
+
+struct StdoutNarrator;
+
+impl Narrator for StdoutNarrator {
+    fn speak(&mut self, text: &str) {
+        println!("{}", text);
+    }
+}
+
+// NOTICE: The command runs on its own thread, one line at a time, so lines never talk over each other
+struct CommandNarrator {
+    lines: Sender<String>,
+}
+
+impl CommandNarrator {
+    pub fn new(command: String) -> Self {
+        let (lines, pending) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in pending {
+                if let Err(error) = process::Command::new(&command).arg(line).status() {
+                    eprintln!("Could not run narrator {}: {}", command, error);
+                }
+            }
+        });
+        CommandNarrator { lines }
+    }
+}
+
+impl Narrator for CommandNarrator {
+    fn speak(&mut self, text: &str) {
+        let _ = self.lines.send(text.to_string());
+    }
+}
+
+pub(crate) fn new_narrator(config: &Config) -> Option<Box<dyn Narrator>> {
+    if !config.accessibility {
+        return None;
+    }
+    match config.narrator_command {
+        Some(ref command) => Some(Box::new(CommandNarrator::new(command.clone()))),
+        None => Some(Box::new(StdoutNarrator)),
+    }
+}
+
+pub(crate) fn direction_key(dx: i32, dy: i32) -> &'static str {
+    match (dx.signum(), dy.signum()) {
+        (0, -1) => "direction_north",
+        (1, -1) => "direction_north_east",
+        (1, 0) => "direction_east",
+        (1, 1) => "direction_south_east",
+        (0, 1) => "direction_south",
+        (-1, 1) => "direction_south_west",
+        (-1, 0) => "direction_west",
+        (-1, -1) => "direction_north_west",
+        _ => "direction_here",
+    }
+}
+
+fn describe_surroundings(game: &Game, game_objects: &[GameObject]) -> String {
+    let locale = &game.config.locale;
+    let (player_x, player_y) = game.player.position();
+    let see_invisible = sees_invisible(game);
+
+    let mut visible: Vec<_> = game_objects
+        .iter()
+        .filter(|game_object| game.fov.is_in_fov(game_object.x, game_object.y))
+        .filter(|game_object| !game_object.invisible || see_invisible)
+        .collect();
+    visible.sort_by_key(|game_object| cmp::max((game_object.x - player_x).abs(), (game_object.y - player_y).abs()));
+
+    if visible.is_empty() {
+        return tr!(locale, "nothing_in_sight");
+    }
+
+    visible
+        .iter()
+        .map(|game_object| {
+            let dx = game_object.x - player_x;
+            let dy = game_object.y - player_y;
+            let direction = tr!(locale, direction_key(dx, dy));
+            match cmp::max(dx.abs(), dy.abs()) {
+                0 => tr!(locale, "surroundings_here", name = game_object.display_name(locale)),
+                distance => tr!(locale, "surroundings_object", name = game_object.display_name(locale), distance = distance, direction = direction),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn narrate_turn(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject], narrated_messages: &mut u64, describe: bool) {
+    if let Some(ref mut narrator) = tcod.narrator {
+        for text in game.messages.added_since(*narrated_messages) {
+            narrator.speak(text);
+        }
+        if describe {
+            narrator.speak(&describe_surroundings(game, game_objects));
+        }
+    }
+    *narrated_messages = game.messages.added();
+}
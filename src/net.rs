@@ -0,0 +1,98 @@
+use std::thread;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use crate::config::Locale;
+use crate::game::Game;
+use crate::save::RunSummary;
+
+/// Spectator broadcasts, state exports, the leaderboard screen and co-op play of the window
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+const LEADERBOARD_TIMEOUT_SECS: u64 = 5;
+// NOTICE: The leaderboard only ever sends its best runs, a longer answer is refused rather than read whole
+const LEADERBOARD_RESPONSE_BYTES: u64 = 256 * 1024;
+
+/// A leaderboard exchange running on its own thread, so the frames keep coming while it waits on the network
+pub(crate) struct Pending<T> {
+    receiver: Receiver<T>,
+    result: Option<T>,
+}
+
+impl<T: Send + 'static> Pending<T> {
+    fn spawn(request: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || sender.send(request()).ok());
+        Pending { receiver, result: None }
+    }
+
+    /// The answer once it came, None while the request is still under way
+    pub fn poll(&mut self) -> Option<&T> {
+        if self.result.is_none() {
+            self.result = self.receiver.try_recv().ok();
+        }
+        self.result.as_ref()
+    }
+}
+
+pub(crate) fn submit_run(url: &str, game: &Game) -> Pending<Result<(), String>> {
+    let url = url.to_string();
+    let summary = serde_json::to_string(&RunSummary::new(game));
+    Pending::spawn(move || {
+        let summary = summary.map_err(|error| error.to_string())?;
+        http_request("POST", &url, Some(&summary)).map(|_| ()).map_err(|error| error.to_string())
+    })
+}
+
+/// What became of the run sent to the leaderboard, None when it wasn't sent
+pub(crate) fn submission_status(submission: &mut Option<Pending<Result<(), String>>>, locale: &Locale) -> Option<String> {
+    Some(match submission.as_mut()?.poll().cloned() {
+        None => tr!(locale, "leaderboard_sending"),
+        Some(Ok(())) => tr!(locale, "leaderboard_sent"),
+        Some(Err(error)) => tr!(locale, "leaderboard_failed", error = error),
+    })
+}
+
+// NOTICE: Just enough HTTP for the leaderboard, plain http:// over HTTP/1.0 so the body is never chunked
+fn http_request(method: &str, url: &str, body: Option<&str>) -> Result<String, Box<dyn Error>> {
+    let address = url.strip_prefix("http://").ok_or("only http:// leaderboard endpoints are supported")?;
+    let (host, path) = match address.find('/') {
+        Some(index) => address.split_at(index),
+        None => (address, "/"),
+    };
+    let socket_address = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+    let socket_address = socket_address.to_socket_addrs()?.next().ok_or("the leaderboard host could not be resolved")?;
+    let timeout = Duration::from_secs(LEADERBOARD_TIMEOUT_SECS);
+    let mut stream = TcpStream::connect_timeout(&socket_address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let body = body.unwrap_or_default();
+    write!(
+        stream,
+        "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        host,
+        body.len(),
+        body,
+    )?;
+    let mut response = String::new();
+    let read = (&mut stream).take(LEADERBOARD_RESPONSE_BYTES + 1).read_to_string(&mut response)?;
+    if read as u64 > LEADERBOARD_RESPONSE_BYTES {
+        return Err("the leaderboard sent too long a response".into());
+    }
+
+    let (head, body) = response.split_once("\r\n\r\n").ok_or("the leaderboard sent an invalid response")?;
+    let status = head.split_whitespace().nth(1).and_then(|status| status.parse::<u16>().ok()).unwrap_or(0);
+    if !(200..300).contains(&status) {
+        return Err(format!("the leaderboard answered with status {}", status).into());
+    }
+    Ok(body.to_string())
+}
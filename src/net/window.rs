@@ -0,0 +1,406 @@
+use super::*;
+use tcod::console::*;
+use std::{cmp, fs};
+use std::io::{self, BufRead, BufReader};
+use std::net::{Shutdown, TcpListener};
+use std::path::PathBuf;
+use std::sync::mpsc::{Sender, TryRecvError};
+use serde::{Deserialize, Serialize};
+use crate::bot::Observation;
+use crate::character::{class_menu, new_partner};
+use crate::combat::dispatch_events;
+use crate::config::Config;
+use crate::controls::{Action, Command, PlayerAction, find_command, poll_input};
+use crate::game::{GameOver, MessageCategory, monsters_turn, new_game, partner_step, place_partner};
+use crate::map::compute_fov;
+use crate::object::GameObject;
+use crate::portable::KeyCode;
+use crate::portable::colors::*;
+use crate::save::{play_game_with_emergency_save, restore_game, slot_dir};
+use crate::ui::{MAX_NAME_LENGTH, SCREEN_HEIGHT, SCREEN_WIDTH, Tcod, msgbox, render_all, text_input};
+
+const BROADCAST_WRITE_TIMEOUT_MS: u64 = 200;
+const COOP_CONNECT_TIMEOUT_SECS: u64 = 5;
+const COOP_WRITE_TIMEOUT_SECS: u64 = 5;
+// NOTICE: The host's messages hold the whole game, the cap leaves room for a deep dungeon's worth of levels
+const COOP_LINE_BYTES: u64 = 16 * 1024 * 1024;
+const LEADERBOARD_ENTRIES: usize = 10;
+
+/// Streams the screen as ANSI text to the spectators connected over telnet
+// NOTICE: Spectators only ever receive, anything they type is never read
+pub(crate) struct Broadcast {
+    listener: TcpListener,
+    spectators: Vec<TcpStream>,
+    /// Sent to each spectator as they connect
+    greeting: &'static str,
+}
+
+/// Only this machine can connect unless `public`, then anyone who can reach it can
+fn listen_address(public: bool) -> &'static str {
+    if public {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    }
+}
+
+impl Broadcast {
+    pub fn new(port: u16, public: bool, greeting: &'static str) -> io::Result<Self> {
+        let listener = TcpListener::bind((listen_address(public), port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Broadcast {
+            listener,
+            spectators: vec![],
+            greeting,
+        })
+    }
+
+    /// Takes in the spectators who connected since the last call, true when there were any
+    pub fn accept(&mut self) -> bool {
+        let mut joined = false;
+        while let Ok((mut stream, _)) = self.listener.accept() {
+            // NOTICE: A spectator too slow to keep up is dropped rather than holding up the game
+            let ready = stream.set_nonblocking(false).is_ok()
+                && stream.set_write_timeout(Some(Duration::from_millis(BROADCAST_WRITE_TIMEOUT_MS))).is_ok()
+                && stream.write_all(self.greeting.as_bytes()).is_ok();
+            if ready {
+                self.spectators.push(stream);
+                joined = true;
+            }
+        }
+        joined
+    }
+
+    pub fn send(&mut self, screen: &str) {
+        self.spectators.retain_mut(|stream| stream.write_all(screen.as_bytes()).is_ok());
+    }
+}
+
+// NOTICE: For overlays & analysis tools, the file always holds the latest turn while sockets get one JSON line per turn
+pub(crate) enum StateExport {
+    File(PathBuf),
+    Socket(Broadcast),
+}
+
+impl StateExport {
+    /// `target` is a file path, or tcp:PORT to serve the state to whoever connects
+    pub fn new(target: &str, public: bool) -> Result<Self, Box<dyn Error>> {
+        match target.strip_prefix("tcp:") {
+            Some(port) => Ok(StateExport::Socket(Broadcast::new(port.parse()?, public, "")?)),
+            None => Ok(StateExport::File(PathBuf::from(target))),
+        }
+    }
+
+    pub fn accept(&mut self) -> bool {
+        match self {
+            StateExport::File(_) => false,
+            StateExport::Socket(broadcast) => broadcast.accept(),
+        }
+    }
+
+    pub fn write(&mut self, observation: &Observation) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string(observation)?;
+        match self {
+            StateExport::File(path) => {
+                let tmp_path = path.with_extension("tmp");
+                fs::write(&tmp_path, json)?;
+                fs::rename(&tmp_path, path)?;
+            }
+            StateExport::Socket(broadcast) => broadcast.send(&format!("{}\n", json)),
+        }
+        Ok(())
+    }
+}
+
+/// The leaderboard's best runs, fetched on their own thread, `leaderboard_header` followed by a line for each
+pub(crate) fn leaderboard_lines(url: &str, locale: &Locale) -> Pending<Vec<String>> {
+    let (url, locale) = (url.to_string(), locale.clone());
+    Pending::spawn(move || leaderboard_rows(&url, &locale))
+}
+
+fn leaderboard_rows(url: &str, locale: &Locale) -> Vec<String> {
+    let mut lines = vec![tr!(locale, "leaderboard_header")];
+    let runs = http_request("GET", url, None).and_then(|body| Ok(serde_json::from_str::<Vec<RunSummary>>(&body)?));
+    match runs {
+        Ok(ref runs) if runs.is_empty() => lines.push(tr!(locale, "leaderboard_empty")),
+        Ok(mut runs) => {
+            runs.sort_by_key(|run| cmp::Reverse(run.score));
+            for (rank, run) in runs.iter().take(LEADERBOARD_ENTRIES).enumerate() {
+                lines.push(tr!(
+                    locale,
+                    "leaderboard_row",
+                    rank = rank + 1,
+                    name = run.player_name,
+                    class = run.class.name(locale),
+                    score = run.score,
+                    level = run.depth,
+                    turns = run.turns,
+                ));
+            }
+        }
+        Err(error) => lines.push(tr!(locale, "leaderboard_failed", error = error)),
+    }
+    lines
+}
+
+/// What the host and the partner's client send each other in a co-op game, one JSON line per message
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum CoopMessage {
+    /// The whole game, sent by the host after each change, with whether the partner is to act
+    State { snapshot: String, your_turn: bool },
+    /// The partner's action for its turn
+    Act(Action),
+}
+
+// NOTICE: Lines are read & written on their own threads, so waiting on the other side never holds up the frame loop
+pub(crate) struct CoopLink {
+    stream: TcpStream,
+    incoming: Receiver<CoopMessage>,
+    outgoing: Sender<String>,
+    pub(crate) connected: bool,
+}
+
+impl CoopLink {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        // NOTICE: A partner who stopped reading ends the link once a write is stuck this long
+        stream.set_write_timeout(Some(Duration::from_secs(COOP_WRITE_TIMEOUT_SECS)))?;
+        let mut writer = stream.try_clone()?;
+        let (outgoing, lines) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in lines {
+                if writer.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                // NOTICE: A line is read no further than the cap, a peer that never ends its line loses the link
+                // instead of growing the buffer
+                let read = match (&mut reader).take(COOP_LINE_BYTES + 1).read_line(&mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => read,
+                };
+                if read as u64 > COOP_LINE_BYTES {
+                    break;
+                }
+                if let Ok(message) = serde_json::from_str(&line) {
+                    if sender.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+            reader.get_ref().shutdown(Shutdown::Both).ok();
+        });
+        Ok(CoopLink {
+            stream,
+            incoming,
+            outgoing,
+            connected: true,
+        })
+    }
+
+    pub fn send(&mut self, message: &CoopMessage) -> Result<(), Box<dyn Error>> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        if self.outgoing.send(line).is_err() {
+            self.connected = false;
+            return Err("the connection was lost".into());
+        }
+        Ok(())
+    }
+
+    pub fn receive(&mut self) -> Option<CoopMessage> {
+        match self.incoming.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.connected = false;
+                None
+            }
+        }
+    }
+}
+
+impl Drop for CoopLink {
+    fn drop(&mut self) {
+        // NOTICE: Also ends the reading & writing threads, which hold their own handles on the socket
+        self.stream.shutdown(Shutdown::Both).ok();
+    }
+}
+
+// NOTICE: The host keeps the only real game, the partner's client sends its actions and gets the whole game back
+pub(crate) fn host_coop(tcod: &mut Tcod, port: u16, public: bool, config: &Config) -> GameOver {
+    let locale = &config.locale;
+    let player_name = match text_input(&tr!(locale, "enter_name"), MAX_NAME_LENGTH, 30, &mut tcod.root) {
+        Some(player_name) => player_name,
+        None => return GameOver::MainMenu,
+    };
+    if slot_dir(&player_name).exists() {
+        msgbox(&tr!(locale, "name_taken", name = player_name), 30, tcod);
+        return GameOver::MainMenu;
+    }
+    let class = match class_menu(tcod, locale) {
+        Some(class) => class,
+        None => return GameOver::MainMenu,
+    };
+    let link = match wait_for_partner(tcod, port, public, locale) {
+        Ok(Some(link)) => link,
+        Ok(None) => return GameOver::MainMenu,
+        Err(error) => {
+            msgbox(&tr!(locale, "coop_host_failed", error = error), 40, tcod);
+            return GameOver::MainMenu;
+        }
+    };
+
+    let (mut game, mut game_objects) = new_game(&player_name, class, config);
+    game.partner = Some(new_partner());
+    place_partner(&mut game, &game_objects);
+    tcod.coop = Some(link);
+    let outcome = play_game_with_emergency_save(tcod, &mut game, &mut game_objects);
+    tcod.coop = None;
+    outcome
+}
+
+/// None when the host gave up waiting with Escape
+fn wait_for_partner(tcod: &mut Tcod, port: u16, public: bool, locale: &Locale) -> Result<Option<CoopLink>, Box<dyn Error>> {
+    let listener = TcpListener::bind((listen_address(public), port))?;
+    listener.set_nonblocking(true)?;
+    while !tcod.root.window_closed() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(Some(CoopLink::new(stream)?));
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {}
+            Err(error) => return Err(error.into()),
+        }
+        tcod.root.set_default_background(BLACK);
+        tcod.root.clear();
+        tcod.root.set_default_foreground(WHITE);
+        let text = tr!(locale, "coop_waiting", port = port);
+        tcod.root.print_ex(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2, BackgroundFlag::None, TextAlignment::Center, text);
+        tcod.root.flush();
+        poll_input(tcod);
+        if tcod.key.code == KeyCode::Escape {
+            return Ok(None);
+        }
+    }
+    Ok(None)
+}
+
+/// Sends the whole game to the partner's client, a failed send shows up as a lost connection
+pub(crate) fn sync_partner(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let link = match tcod.coop {
+        Some(ref mut link) => link,
+        None => return,
+    };
+    if let Ok(snapshot) = serde_json::to_string(&(game, game_objects)) {
+        let your_turn = game.partner_turn.is_some();
+        link.send(&CoopMessage::State { snapshot, your_turn }).ok();
+    }
+}
+
+/// While the partner is to act the host can only open the escape menu
+pub(crate) fn partner_input(tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) -> PlayerAction {
+    match tcod.coop.as_mut().and_then(CoopLink::receive) {
+        // NOTICE: Anything but moving & waiting from the other side is ignored, the client is not trusted with more
+        Some(CoopMessage::Act(action @ (Action::Move(..) | Action::Wait))) => partner_step(game, game_objects, action),
+        _ if tcod.key.code == KeyCode::Escape => PlayerAction::Exit,
+        _ => PlayerAction::DidntTakeTurn,
+    }
+}
+
+/// The game goes on solo, the monsters get the turn they were waiting on the partner for
+pub(crate) fn partner_left(game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    game.partner = None;
+    game.messages.add(tr!(game.config.locale, "coop_partner_left"), MessageCategory::System);
+    if let Some(turn_cost) = game.partner_turn.take() {
+        monsters_turn(turn_cost, game, game_objects);
+        dispatch_events(game, game_objects);
+    }
+}
+
+/// Shows the game from the partner's side, swapping it with the player and seeing through its eyes
+fn partner_view(game: &mut Game, game_objects: &[GameObject]) {
+    if let Some(ref mut partner) = game.partner {
+        std::mem::swap(&mut game.player, partner);
+    }
+    compute_fov(game, game_objects);
+}
+
+/// Gives up on a host that doesn't answer rather than freezing the window
+fn connect_to_host(address: &str) -> io::Result<TcpStream> {
+    let socket_address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the host could not be resolved"))?;
+    TcpStream::connect_timeout(&socket_address, Duration::from_secs(COOP_CONNECT_TIMEOUT_SECS))
+}
+
+// NOTICE: The client only renders what the host sends and answers with moves & waits on its turns
+pub(crate) fn join_coop(tcod: &mut Tcod, address: &str, config: &Config) -> GameOver {
+    let locale = &config.locale;
+    let mut link = match connect_to_host(address).and_then(CoopLink::new) {
+        Ok(link) => link,
+        Err(error) => {
+            msgbox(&tr!(locale, "coop_join_failed", error = error), 40, tcod);
+            return GameOver::MainMenu;
+        }
+    };
+    let mut view: Option<(Game, Vec<GameObject>)> = None;
+    let mut your_turn = false;
+    tcod.map_cells.clear();
+
+    while !tcod.root.window_closed() {
+        while let Some(message) = link.receive() {
+            if let CoopMessage::State { snapshot, your_turn: partner_turn } = message {
+                if let Ok((mut game, game_objects)) = restore_game(&snapshot, config) {
+                    partner_view(&mut game, &game_objects);
+                    view = Some((game, game_objects));
+                }
+                your_turn = partner_turn;
+            }
+        }
+        if !link.connected {
+            msgbox(&tr!(locale, "coop_host_left"), 40, tcod);
+            break;
+        }
+
+        poll_input(tcod);
+        let command = find_command(tcod.key, tcod.movement_keys);
+        let action = match command {
+            Some(Command::Exit) => break,
+            Some(Command::Move(dx, dy)) => Some(Action::Move(dx, dy)),
+            Some(Command::Wait) => Some(Action::Wait),
+            _ => None,
+        };
+        if let Some(action) = action.filter(|_| your_turn) {
+            your_turn = link.send(&CoopMessage::Act(action)).is_err();
+        }
+
+        let status = match view {
+            Some((ref game, ref game_objects)) => {
+                tcod.panel.set_default_background(BLACK);
+                tcod.panel.clear();
+                render_all(tcod, game, game_objects);
+                if your_turn { "coop_your_turn" } else { "coop_host_turn" }
+            }
+            None => {
+                tcod.root.set_default_background(BLACK);
+                tcod.root.clear();
+                "coop_connecting"
+            }
+        };
+        tcod.root.set_default_foreground(WHITE);
+        tcod.root.print_ex(SCREEN_WIDTH / 2, 0, BackgroundFlag::None, TextAlignment::Center, tr!(locale, status));
+        tcod.root.flush();
+    }
+    tcod.map_cells.clear();
+    GameOver::MainMenu
+}
@@ -0,0 +1,347 @@
+use std::cmp;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::character::{Class, Perk, Skill, train};
+use crate::combat::{
+    Ai, Blow, COMBAT_NOISE_RADIUS, DeathCallback, Fighter, GameEvent, RangedAttack, StatusEffect,
+    combat_message,
+};
+use crate::config::{Ability, Config, Content, Locale, Name, OnHit};
+use crate::game::{Game, MessageCategory};
+use crate::items::{
+    Equipment, Gem, Item, RUBY_FIRE_DAMAGE, Slot, equipped_weapon, gem_bonus, wear_equipment,
+    worn_defense_bonus,
+};
+use crate::map::{Decal, Movement, leave_decal};
+use crate::monsters::{Caster, Unique};
+use crate::portable::colors::*;
+#[cfg(feature = "window")]
+use tcod::console::*;
+#[cfg(feature = "window")]
+use crate::ui::Palette;
+
+pub(crate) const SHAKE_MS: u32 = 80;
+const BURN_TURN_COUNT: i32 = 3;
+const BLOODLUST_HEAL: i32 = 2;
+const EVASION_PER_LEVEL: f32 = 0.05;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameObject {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) char: char,
+    pub(crate) color: Color,
+    pub(crate) name: Name,
+    pub(crate) blocks: bool,
+    pub(crate) always_visible: bool,
+    #[serde(default)]
+    pub(crate) is_stairs: bool,
+    #[serde(default)]
+    pub(crate) description: Name,
+    pub(crate) is_alive: bool,
+    pub(crate) fighter: Option<Fighter>,
+    pub(crate) ai: Option<Ai>,
+    pub(crate) item: Option<Item>,
+    #[serde(default)]
+    pub(crate) equipment: Option<Equipment>,
+    #[serde(default)]
+    pub(crate) statuses: Vec<StatusEffect>,
+    #[serde(default)]
+    pub(crate) ranged: Option<RangedAttack>,
+    #[serde(default)]
+    pub(crate) caster: Option<Caster>,
+    #[serde(default)]
+    pub(crate) species: String,
+    #[serde(default)]
+    pub(crate) unique: Option<Unique>,
+    #[serde(default)]
+    pub(crate) abilities: Vec<Ability>,
+    #[serde(default)]
+    pub(crate) invisible: bool,
+    #[serde(default)]
+    pub(crate) last_attack_turn: Option<u32>,
+    #[serde(default)]
+    pub(crate) movement: Movement,
+    #[serde(default)]
+    pub(crate) last_seen_player: Option<(i32, i32)>,
+    #[serde(default)]
+    pub(crate) heard_noise: Option<(i32, i32)>,
+    #[serde(default)]
+    pub(crate) carries_key: bool,
+    #[serde(default)]
+    pub(crate) perks: Vec<Perk>,
+    #[serde(default)]
+    pub(crate) freshness: Option<i32>,
+    #[serde(default)]
+    pub(crate) npc: Option<Npc>,
+    #[serde(default)]
+    pub(crate) id: Option<EntityId>,
+}
+
+/// Stays the same for the object's whole life, unlike its index in the object list
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) struct EntityId(pub(crate) u64);
+
+/// Friendly characters offering a service when the player walks into them
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Npc {
+    Blacksmith { served: bool },
+}
+
+impl Default for GameObject {
+    fn default() -> Self {
+        GameObject::new(0, 0, ' ', BLACK, "", false)
+    }
+}
+
+impl GameObject {
+    /// `name` is the locale key of the object's name
+    pub fn new(x: i32, y: i32, char: char, color: Color, name: &str, blocks: bool) -> Self {
+        GameObject {
+            x,
+            y,
+            char,
+            color,
+            name: Name::key(name),
+            blocks,
+            always_visible: false,
+            is_stairs: false,
+            description: Name::default(),
+            is_alive: false,
+            fighter: None,
+            ai: None,
+            item: None,
+            equipment: None,
+            statuses: vec![],
+            ranged: None,
+            caster: None,
+            species: String::new(),
+            unique: None,
+            abilities: vec![],
+            invisible: false,
+            last_attack_turn: None,
+            movement: Movement::default(),
+            last_seen_player: None,
+            heard_noise: None,
+            carries_key: false,
+            perks: vec![],
+            freshness: None,
+            npc: None,
+            id: None,
+        }
+    }
+
+    /// The name shown to the player, with the enchantment of equipment composed in
+    pub fn display_name(&self, locale: &Locale) -> String {
+        match self.equipment {
+            Some(equipment) if equipment.enchantment > 0 => {
+                tr!(locale, "enchanted_name", level = equipment.enchantment, item = self.name.translate(locale))
+            }
+            _ => self.name.translate(locale),
+        }
+    }
+
+    pub fn has_perk(&self, perk: Perk) -> bool {
+        self.perks.contains(&perk)
+    }
+
+    pub fn weight(&self, config: &Config) -> i32 {
+        match (self.item, self.equipment) {
+            (_, Some(equipment)) => equipment.weight,
+            (Some(Item::Heal), None) => config.potion_weight,
+            (Some(_), None) => config.item_weight,
+            (None, None) => 0,
+        }
+    }
+
+    #[cfg(feature = "window")]
+    pub fn draw(&self, con: &mut dyn Console, palette: &Palette) {
+        con.set_default_foreground(palette.color(self.color));
+        con.put_char(self.x, self.y, self.char, BackgroundFlag::None);
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn set_position(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Innate movement, levitating lets anything fly for a while
+    pub fn movement(&self) -> Movement {
+        let levitating = self
+            .statuses
+            .iter()
+            .any(|status| matches!(status, StatusEffect::Levitating { .. }));
+        Movement {
+            flying: self.movement.flying || levitating,
+            ..self.movement
+        }
+    }
+
+    /// Abilities of the species along with the ones given by affixes
+    pub fn abilities(&self, content: &Content) -> Vec<Ability> {
+        let mut abilities = content.abilities(&self.species).to_vec();
+        abilities.extend(self.abilities.iter().cloned());
+        abilities
+    }
+
+    pub fn distance_to(&self, other: &GameObject) -> f32 {
+        let dx = other.x - self.x;
+        let dy = other.y - self.y;
+        ((dx.pow(2) + dy.pow(2)) as f32).sqrt()
+    }
+
+    pub fn distance(&self, x: i32, y: i32) -> f32 {
+        (((x - self.x).pow(2) + (y - self.y).pow(2)) as f32).sqrt()
+    }
+
+    pub fn take_damage(&mut self, damage: i32, game: &mut Game) {
+        if let Some(fighter) = self.fighter.as_mut() {
+            if damage > 0 {
+                fighter.hp -= damage;
+                leave_decal(&mut game.map, (self.x, self.y), Decal::Blood);
+                game.events.push(GameEvent::DamageDealt {
+                    amount: damage,
+                    to_player: fighter.on_death == DeathCallback::Player,
+                });
+            }
+        }
+        // NOTICE: Blows landing on what already died, like a second status ticking on a dead player, don't kill it again
+        if let Some(fighter) = self.fighter {
+            if fighter.hp <= 0 && self.is_alive {
+                self.is_alive = false;
+                fighter.on_death.callback(self, game);
+            }
+        }
+    }
+
+    /// Returns the direction to push the target in when a knockback weapon hit it
+    pub fn attack(&mut self, target: &mut GameObject, game: &mut Game) -> Option<(i32, i32)> {
+        let attacker_is_player = self.fighter.is_some_and(|f| f.on_death == DeathCallback::Player);
+        let weapon = if attacker_is_player { equipped_weapon(&game.inventory) } else { None };
+        let skill = if attacker_is_player { game.skills.level(Skill::Melee) } else { 0 };
+        let brawler = if self.has_perk(Perk::Brawler) { 1 } else { 0 };
+        let thick_skin = if target.has_perk(Perk::ThickSkin) { 1 } else { 0 };
+        let target_is_player = target.fighter.is_some_and(|f| f.on_death == DeathCallback::Player);
+        let armor = if target_is_player { worn_defense_bonus(&game.inventory) } else { 0 };
+        let onyx = if attacker_is_player { gem_bonus(&game.inventory, Gem::Onyx) } else { 0 };
+        let power = self.fighter.map_or(0, |f| f.power) + weapon.map_or(0, |w| w.effective_power_bonus()) + skill + brawler + onyx;
+        let defense = target.fighter.map_or(0, |f| f.defense) + armor + thick_skin;
+        let mut damage = power - defense;
+        let backstab = attacker_is_player && game.class == Class::Rogue && target.last_seen_player.is_none();
+        if backstab && damage > 0 {
+            damage *= 2;
+            game.messages.add(
+                tr!(game.config.locale, "backstab", target = target.display_name(&game.config.locale)),
+                MessageCategory::Combat,
+            );
+            game.shake = SHAKE_MS;
+        }
+        self.last_attack_turn = Some(game.turn);
+        if target_is_player && damage > 0 {
+            train(Skill::Evasion, 1, game);
+            let evasion = game.skills.level(Skill::Evasion) as f32 * EVASION_PER_LEVEL;
+            if game.rng.gen::<f32>() < evasion {
+                game.messages.add(
+                    tr!(game.config.locale, "dodged", attacker = self.display_name(&game.config.locale)),
+                    MessageCategory::Combat,
+                );
+                return None;
+            }
+        }
+        if damage > 0 {
+            if attacker_is_player {
+                train(Skill::Melee, 1, game);
+            }
+            if target_is_player {
+                game.killer = Some(self.display_name(&game.config.locale));
+            }
+            let (attacker, defender) = (self.display_name(&game.config.locale), target.display_name(&game.config.locale));
+            let blow = Blow { attacker: &attacker, target: &defender, power, defense, damage, backstab };
+            game.messages.add(
+                combat_message(&blow, &game.config),
+                if target_is_player { MessageCategory::Danger } else { MessageCategory::Combat },
+            );
+            game.events.push(GameEvent::NoiseEmitted { position: target.position(), radius: COMBAT_NOISE_RADIUS });
+            // NOTICE: A blow taking a quarter of the player's health or more shakes the screen
+            if target_is_player && target.fighter.is_some_and(|fighter| damage * 4 >= fighter.max_hp) {
+                game.shake = SHAKE_MS;
+            }
+            target.take_damage(damage, game);
+            if attacker_is_player && game.class == Class::Fighter && !target.is_alive {
+                self.heal(BLOODLUST_HEAL);
+                game.messages.add(tr!(game.config.locale, "bloodlust", amount = BLOODLUST_HEAL), MessageCategory::Combat);
+            }
+
+            for ability in self.abilities(&game.config.content) {
+                if let Ability::Venomous { turns } = ability {
+                    if target.is_alive {
+                        target.statuses.retain(|status| !matches!(status, StatusEffect::Poisoned { .. }));
+                        target.statuses.push(StatusEffect::Poisoned { turns });
+                        game.messages.add(
+                            tr!(game.config.locale, "poisoned", target = target.display_name(&game.config.locale)),
+                            MessageCategory::Combat,
+                        );
+                    }
+                }
+            }
+
+            if attacker_is_player {
+                wear_equipment(Slot::Weapon, game);
+            } else if target_is_player {
+                wear_equipment(Slot::Armor, game);
+            }
+
+            let rubies = weapon.map_or(0, |w| w.gems.iter().filter(|&&gem| gem == Some(Gem::Ruby)).count() as i32);
+            if rubies > 0 && target.is_alive {
+                let fire = rubies * RUBY_FIRE_DAMAGE;
+                game.messages.add(
+                    tr!(game.config.locale, "gem_fire", target = target.display_name(&game.config.locale), damage = fire),
+                    MessageCategory::Combat,
+                );
+                target.take_damage(fire, game);
+                leave_decal(&mut game.map, (target.x, target.y), Decal::Scorch);
+            }
+
+            match weapon.and_then(|w| w.effective_on_hit()) {
+                Some(OnHit::Flaming) if target.is_alive => {
+                    target.statuses.retain(|status| !matches!(status, StatusEffect::Burning { .. }));
+                    target.statuses.push(StatusEffect::Burning { turns: BURN_TURN_COUNT });
+                    game.messages.add(
+                        tr!(game.config.locale, "catches_fire", target = target.display_name(&game.config.locale)),
+                        MessageCategory::Combat,
+                    );
+                }
+                Some(OnHit::Vampiric) => {
+                    let amount = cmp::max(1, damage / 2);
+                    self.heal(amount);
+                    game.messages.add(
+                        tr!(game.config.locale, "drains_life", attacker = self.display_name(&game.config.locale), amount = amount),
+                        MessageCategory::Combat,
+                    );
+                }
+                Some(OnHit::Knockback) if target.is_alive => {
+                    return Some(((target.x - self.x).signum(), (target.y - self.y).signum()));
+                }
+                _ => {}
+            }
+        } else {
+            let (attacker, defender) = (self.display_name(&game.config.locale), target.display_name(&game.config.locale));
+            let blow = Blow { attacker: &attacker, target: &defender, power, defense, damage: 0, backstab };
+            game.messages.add(combat_message(&blow, &game.config), MessageCategory::Combat);
+        }
+        None
+    }
+
+    pub fn heal(&mut self, amount: i32) {
+        if let Some(ref mut fighter) = self.fighter {
+            fighter.hp += amount;
+            if fighter.hp > fighter.max_hp {
+                fighter.hp = fighter.max_hp;
+            }
+        }
+    }
+}
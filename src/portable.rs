@@ -0,0 +1,11 @@
+// NOTICE: What the game logic shares with the renderers from tcod: colors, key codes, lines & field of view. Window
+// builds use tcod's own, builds without the `window` feature get these stand-ins so the terminal game links no libtcod
+#[cfg(feature = "window")]
+pub(crate) use tcod::{colors, input::KeyCode, line::Line, map::{FovAlgorithm, Map as FovMap}};
+
+/// Colors, key codes, lines and field of view written out for builds without tcod
+#[cfg(not(feature = "window"))]
+mod stand_in;
+
+#[cfg(not(feature = "window"))]
+pub(crate) use self::stand_in::*;
@@ -0,0 +1,188 @@
+// NOTICE: Only the terminal front end draws with some of these, the game logic alone leaves them unused
+#[cfg_attr(not(feature = "terminal"), allow(dead_code))]
+pub(crate) mod colors {
+    use std::ops::{Add, Mul};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct Color {
+        pub r: u8,
+        pub g: u8,
+        pub b: u8,
+    }
+
+    impl Color {
+        pub const fn new(r: u8, g: u8, b: u8) -> Self {
+            Color { r, g, b }
+        }
+    }
+
+    impl Add for Color {
+        type Output = Color;
+
+        fn add(self, other: Color) -> Color {
+            Color::new(self.r.saturating_add(other.r), self.g.saturating_add(other.g), self.b.saturating_add(other.b))
+        }
+    }
+
+    impl Mul<f32> for Color {
+        type Output = Color;
+
+        fn mul(self, factor: f32) -> Color {
+            let scale = |component: u8| (f32::from(component) * factor).clamp(0.0, 255.0) as u8;
+            Color::new(scale(self.r), scale(self.g), scale(self.b))
+        }
+    }
+
+    pub fn lerp(from: Color, to: Color, coefficient: f32) -> Color {
+        let mix = |from: u8, to: u8| (f32::from(from) + (f32::from(to) - f32::from(from)) * coefficient) as u8;
+        Color::new(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b))
+    }
+
+    pub const BLACK: Color = Color::new(0, 0, 0);
+    pub const DARK_GREY: Color = Color::new(95, 95, 95);
+    pub const GREY: Color = Color::new(127, 127, 127);
+    pub const LIGHT_GREY: Color = Color::new(159, 159, 159);
+    pub const LIGHTEST_GREY: Color = Color::new(223, 223, 223);
+    pub const WHITE: Color = Color::new(255, 255, 255);
+    pub const LIGHT_SEPIA: Color = Color::new(158, 134, 100);
+    pub const GOLD: Color = Color::new(229, 191, 0);
+
+    pub const RED: Color = Color::new(255, 0, 0);
+    pub const DARK_RED: Color = Color::new(191, 0, 0);
+    pub const DARKER_RED: Color = Color::new(127, 0, 0);
+    pub const LIGHT_RED: Color = Color::new(255, 63, 63);
+    pub const FLAME: Color = Color::new(255, 63, 0);
+    pub const ORANGE: Color = Color::new(255, 127, 0);
+    pub const LIGHT_ORANGE: Color = Color::new(255, 159, 63);
+    pub const LIGHT_YELLOW: Color = Color::new(255, 255, 63);
+    pub const LIME: Color = Color::new(191, 255, 0);
+    pub const GREEN: Color = Color::new(0, 255, 0);
+    pub const DARKER_GREEN: Color = Color::new(0, 127, 0);
+    pub const LIGHT_GREEN: Color = Color::new(63, 255, 63);
+    pub const DESATURATED_GREEN: Color = Color::new(63, 127, 63);
+    pub const LIGHT_CYAN: Color = Color::new(63, 255, 255);
+    pub const LIGHT_AZURE: Color = Color::new(63, 159, 255);
+    pub const LIGHT_BLUE: Color = Color::new(63, 63, 255);
+    pub const VIOLET: Color = Color::new(127, 0, 255);
+    pub const LIGHT_VIOLET: Color = Color::new(159, 63, 255);
+    pub const LIGHT_MAGENTA: Color = Color::new(255, 63, 207);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum KeyCode {
+    Escape,
+    Enter,
+    Up,
+    Down,
+    Left,
+    Right,
+    F3,
+    F12,
+}
+
+/// The cells from `start` to `end` as Bresenham draws them, `end` included and `start` left out
+pub(crate) struct Line {
+    position: (i32, i32),
+    end: (i32, i32),
+    delta: (i32, i32),
+    step: (i32, i32),
+    error: i32,
+}
+
+impl Line {
+    pub fn new(start: (i32, i32), end: (i32, i32)) -> Self {
+        let delta = ((end.0 - start.0).abs(), -(end.1 - start.1).abs());
+        let step = ((end.0 - start.0).signum(), (end.1 - start.1).signum());
+        Line { position: start, end, delta, step, error: delta.0 + delta.1 }
+    }
+}
+
+impl Iterator for Line {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<(i32, i32)> {
+        if self.position == self.end {
+            return None;
+        }
+        let doubled = 2 * self.error;
+        if doubled >= self.delta.1 {
+            self.error += self.delta.1;
+            self.position.0 += self.step.0;
+        }
+        if doubled <= self.delta.0 {
+            self.error += self.delta.0;
+            self.position.1 += self.step.1;
+        }
+        Some(self.position)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FovAlgorithm {
+    Basic,
+}
+
+/// Casts rays from the origin to every cell on the edge of its radius, the way libtcod's basic algorithm does
+pub(crate) struct FovMap {
+    width: i32,
+    height: i32,
+    transparent: Vec<bool>,
+    in_fov: Vec<bool>,
+}
+
+impl FovMap {
+    pub fn new(width: i32, height: i32) -> Self {
+        let cells = (width * height) as usize;
+        FovMap { width, height, transparent: vec![false; cells], in_fov: vec![false; cells] }
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, transparent: bool, _walkable: bool) {
+        let index = self.index(x, y);
+        self.transparent[index] = transparent;
+    }
+
+    /// A radius of 0 casts the rays as far as the edges of the map
+    pub fn compute_fov(&mut self, x: i32, y: i32, radius: i32, light_walls: bool, _algorithm: FovAlgorithm) {
+        self.in_fov.iter_mut().for_each(|cell| *cell = false);
+        if !self.contains(x, y) {
+            return;
+        }
+        let (min_x, max_x, min_y, max_y) = if radius > 0 {
+            (x - radius, x + radius, y - radius, y + radius)
+        } else {
+            (0, self.width - 1, 0, self.height - 1)
+        };
+        let edge = (min_x..=max_x)
+            .flat_map(|edge_x| vec![(edge_x, min_y), (edge_x, max_y)])
+            .chain((min_y..=max_y).flat_map(|edge_y| vec![(min_x, edge_y), (max_x, edge_y)]));
+        for target in edge {
+            for (cell_x, cell_y) in Line::new((x, y), target) {
+                let (dx, dy) = (cell_x - x, cell_y - y);
+                if !self.contains(cell_x, cell_y) || (radius > 0 && dx * dx + dy * dy > radius * radius) {
+                    break;
+                }
+                let index = self.index(cell_x, cell_y);
+                if !self.transparent[index] {
+                    self.in_fov[index] |= light_walls;
+                    break;
+                }
+                self.in_fov[index] = true;
+            }
+        }
+        let origin = self.index(x, y);
+        self.in_fov[origin] = true;
+    }
+
+    pub fn is_in_fov(&self, x: i32, y: i32) -> bool {
+        self.contains(x, y) && self.in_fov[self.index(x, y)]
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+}
@@ -0,0 +1,134 @@
+use std::iter;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use crate::config::Config;
+use crate::game::Game;
+use crate::map::{initialise_fov, map_size};
+use crate::object::GameObject;
+use crate::portable::colors::*;
+
+/// Save slots, autosaves, replays, morgue files and daily scores, for both front ends
+#[cfg(any(feature = "window", feature = "terminal"))]
+mod frontend;
+/// The window's replay viewer, daily scores screen, emergency save, undo snapshots, screenshots and map exports
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(any(feature = "window", feature = "terminal"))]
+pub(crate) use self::frontend::*;
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+// NOTICE: Folders and files the game writes saves, morgue files and exports to
+const SAVE_DIR: &str = "saves";
+const AUTOSAVE_ROTATIONS: usize = 3;
+
+/// What the player last knew to be on an explored tile: an object in view or remembered
+fn export_object(x: i32, y: i32, game: &Game, game_objects: &[GameObject]) -> Option<(char, Color)> {
+    let visible = game.fov.is_in_fov(x, y);
+    let mut in_view: Vec<&GameObject> = iter::once(&game.player)
+        .chain(game.partner.iter())
+        .chain(game_objects.iter())
+        .filter(|game_object| game_object.position() == (x, y) && (visible || game_object.always_visible) && !game_object.invisible)
+        .collect();
+    in_view.sort_by_key(|game_object| game_object.blocks);
+    if let Some(game_object) = in_view.last() {
+        return Some((game_object.char, game_object.color));
+    }
+    game.remembered.iter().find(|memory| (memory.x, memory.y) == (x, y)).map(|memory| (memory.char, memory.color))
+}
+
+pub(crate) fn export_lines(game: &Game, game_objects: &[GameObject]) -> Vec<String> {
+    let (map_width, map_height) = map_size(&game.map);
+    (0..map_height)
+        .map(|y| {
+            (0..map_width)
+                .map(|x| {
+                    let tile = &game.map[(x, y)];
+                    if !tile.explored {
+                        return ' ';
+                    }
+                    let terrain = tile.terrain.glyph().unwrap_or(if tile.block_sight { '#' } else { '.' });
+                    export_object(x, y, game, game_objects).map_or(terrain, |(glyph, _)| glyph)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub(crate) fn slot_dir(slot: &str) -> PathBuf {
+    Path::new(SAVE_DIR).join(slot)
+}
+
+fn autosave_path(slot: &str, rotation: usize) -> PathBuf {
+    slot_dir(slot).join(format!("autosave_{}.json", rotation))
+}
+
+pub(crate) fn load_game(path: &Path, config: &Config) -> Result<(Game, Vec<GameObject>), Box<dyn Error>> {
+    let mut json_save_state = String::new();
+    let mut file = File::open(path)?;
+    file.read_to_string(&mut json_save_state)?;
+    restore_game(&json_save_state, config)
+}
+
+pub(crate) fn restore_game(json_save_state: &str, config: &Config) -> Result<(Game, Vec<GameObject>), Box<dyn Error>> {
+    let (mut game, mut game_objects) = serde_json::from_str::<(Game, Vec<GameObject>)>(json_save_state)?;
+    // NOTICE: Saves from before the player got its own field have it first in the object list
+    if game.player.fighter.is_none() && !game_objects.is_empty() {
+        game.player = game_objects.remove(0);
+    }
+    game.config = config.clone();
+    initialise_fov(&mut game, &game_objects);
+    Ok((game, game_objects))
+}
+
+pub(crate) fn load_autosave(slot: &str, config: &Config) -> Option<(Game, Vec<GameObject>)> {
+    // NOTICE: The newest autosave may be half-written after a crash, fall back on older rotations
+    (0..AUTOSAVE_ROTATIONS).find_map(|rotation| load_game(&autosave_path(slot, rotation), config).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use serde::Serialize;
+    use crate::character::Class;
+    use crate::game::{new_game, next_level};
+
+    fn json<T: Serialize>(value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    #[test]
+    fn runs_with_the_same_seed_share_deeper_levels() {
+        let config = Config { seed: Some(11), ..Config::default() };
+        let (mut first, mut first_objects) = new_game("First", Class::default(), &config);
+        let (mut second, mut second_objects) = new_game("Second", Class::default(), &config);
+        // NOTICE: Fights and loot draw from the game's RNG, more of them in one run than in the other
+        for _ in 0..100 {
+            second.rng.gen::<u32>();
+        }
+        next_level(&mut first, &mut first_objects);
+        next_level(&mut second, &mut second_objects);
+
+        assert_eq!(json(&first.map), json(&second.map));
+        assert_eq!(json(&first_objects), json(&second_objects));
+    }
+
+    #[test]
+    fn loaded_games_roll_what_the_saved_one_would_have() {
+        let config = Config { seed: Some(5), ..Config::default() };
+        let (mut game, game_objects) = new_game("Tester", Class::default(), &config);
+        for _ in 0..37 {
+            game.rng.gen::<f32>();
+        }
+        let save = serde_json::to_string(&(&game, &game_objects)).unwrap();
+        let (mut loaded, _) = restore_game(&save, &config).unwrap();
+
+        let rolls: Vec<u32> = (0..10).map(|_| game.rng.gen_range(0, 1000)).collect();
+        let loaded_rolls: Vec<u32> = (0..10).map(|_| loaded.rng.gen_range(0, 1000)).collect();
+        assert_eq!(rolls, loaded_rolls);
+    }
+}
@@ -0,0 +1,492 @@
+use super::*;
+use std::{cmp, fs};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Component;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde::de::IgnoredAny;
+use crate::character::Class;
+use crate::combat::dispatch_events;
+use crate::config::Locale;
+use crate::controls::Action;
+use crate::game::{assign_entity_ids, step};
+use crate::tutorial::advance_tutorial;
+use crate::ui::CellView;
+
+const MORGUE_DIR: &str = "morgue";
+
+// NOTICE: Menus pick their options with the keys 1 to 9, the slot chooser keeps the ninth to turn the page
+const SLOTS_PER_PAGE: usize = 8;
+
+const REPLAY_KEYFRAME_ACTIONS: usize = 100;
+
+const DAILY_SCORES_FILE: &str = "daily_scores.json";
+
+/// Rows of cells as 24-bit ANSI text from the home position, without a newline after the last row.
+pub(crate) fn ansi_cells(cells: &[CellView], width: usize) -> String {
+    let mut screen = String::from("\x1b[H");
+    for (row, line) in cells.chunks(width).enumerate() {
+        if row > 0 {
+            screen.push_str("\r\n");
+        }
+        let mut colors = None;
+        for cell in line {
+            let (foreground, background) = (cell.foreground, cell.background);
+            if colors != Some((foreground, background)) {
+                screen.push_str(&format!(
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m",
+                    foreground.r, foreground.g, foreground.b, background.r, background.g, background.b,
+                ));
+                colors = Some((foreground, background));
+            }
+            screen.push(if cell.glyph.is_control() { ' ' } else { cell.glyph });
+        }
+        screen.push_str("\x1b[0m");
+    }
+    screen
+}
+
+pub(crate) fn write_morgue(game: &Game) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(MORGUE_DIR)?;
+    let path = Path::new(MORGUE_DIR).join(format!(
+        "{}-{}.txt",
+        game.player_name,
+        Local::now().format("%Y%m%d-%H%M%S"),
+    ));
+
+    let locale = &game.config.locale;
+    let mut morgue = tr!(locale, "morgue_header", name = game.player_name, level = game.dungeon_level, turns = game.turn);
+    morgue.push_str("\n\n");
+    for line in game.stats.lines(game.turn, locale) {
+        morgue.push_str(&line);
+        morgue.push('\n');
+    }
+    morgue.push('\n');
+    morgue.push_str(&tr!(locale, "morgue_last_messages"));
+    morgue.push('\n');
+    let last_messages: Vec<_> = game.messages.iter().rev().take(10).collect();
+    for message in last_messages.into_iter().rev() {
+        morgue.push_str(&message.text());
+        morgue.push('\n');
+    }
+
+    let mut file = File::create(&path)?;
+    file.write_all(morgue.as_bytes())?;
+    Ok(path)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DailyScore {
+    pub(crate) date: String,
+    pub(crate) player_name: String,
+    pub(crate) class: Class,
+    pub(crate) score: u32,
+    pub(crate) dungeon_level: u32,
+    pub(crate) turns: u32,
+}
+
+pub(crate) fn load_daily_scores() -> Vec<DailyScore> {
+    File::open(DAILY_SCORES_FILE)
+        .ok()
+        .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn record_daily_score(game: &Game) -> Result<(), Box<dyn Error>> {
+    let mut scores = load_daily_scores();
+    scores.push(DailyScore {
+        date: game.daily.clone().unwrap_or_default(),
+        player_name: game.player_name.clone(),
+        class: game.class,
+        score: game.stats.score(),
+        dungeon_level: game.dungeon_level,
+        turns: game.turn,
+    });
+    let mut file = File::create(DAILY_SCORES_FILE)?;
+    file.write_all(serde_json::to_string(&scores)?.as_bytes())?;
+    Ok(())
+}
+
+/// A finished run as sent to the online leaderboard, and as its best runs come back
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RunSummary {
+    pub(crate) player_name: String,
+    #[serde(default)]
+    pub(crate) class: Class,
+    #[serde(default)]
+    seed: u64,
+    pub(crate) score: u32,
+    pub(crate) depth: u32,
+    pub(crate) turns: u32,
+    #[serde(default)]
+    conduct: Vec<String>,
+}
+
+impl RunSummary {
+    pub fn new(game: &Game) -> Self {
+        RunSummary {
+            player_name: game.player_name.clone(),
+            class: game.class,
+            seed: game.seed,
+            score: game.stats.score(),
+            depth: game.stats.deepest_level,
+            turns: game.turn,
+            conduct: conduct_flags(game).into_iter().map(String::from).collect(),
+        }
+    }
+}
+
+/// How the run was played, so the leaderboard can tell a pacifist or a wizard mode run from the others
+fn conduct_flags(game: &Game) -> Vec<&'static str> {
+    let flags = [
+        ("pacifist", game.stats.total_kills() == 0),
+        ("no_items", game.stats.items_used == 0),
+        ("daily", game.daily.is_some()),
+        ("arena", game.arena.is_some()),
+        ("wizard", game.config.wizard),
+    ];
+    flags.iter().filter(|&&(_, kept)| kept).map(|&(flag, _)| flag).collect()
+}
+
+pub(crate) struct SaveSlot {
+    pub(crate) player_name: String,
+    pub(crate) dungeon_level: u32,
+    pub(crate) saved_at: DateTime<Local>,
+}
+
+#[derive(Deserialize)]
+struct SaveSummary {
+    player_name: String,
+    dungeon_level: u32,
+}
+
+/// Removes a save slot, only ever a folder right under `SAVE_DIR` whatever the slot's name holds
+pub(crate) fn delete_slot(slot: &str) {
+    let mut components = Path::new(slot).components();
+    let single_folder = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+    let dir = slot_dir(slot);
+    if single_folder && dir.is_dir() {
+        fs::remove_dir_all(dir).ok();
+    }
+}
+
+pub(crate) fn save_game(path: &Path, game: &Game, game_objects: &[GameObject]) -> Result<(), Box<dyn Error>> {
+    let save_data = serde_json::to_string(&(game, game_objects))?;
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(save_data.as_bytes())?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// A recorded run, the game as it was at each keyframe and the actions taken in between
+#[derive(Debug, Default)]
+pub(crate) struct Replay {
+    pub(crate) entries: Vec<ReplayEntry>,
+    /// How many of the entries are already in the replay file
+    saved: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum ReplayEntry {
+    Keyframe(String),
+    Action(Action),
+}
+
+/// Runs one recorded action the way a frame of `play_game` does
+pub(crate) fn replay_action(action: Action, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    assign_entity_ids(game, game_objects);
+    step(game, game_objects, action);
+    dispatch_events(game, game_objects);
+    advance_tutorial(game, game_objects);
+    game.taken_actions.clear();
+    game.sounds.clear();
+    game.projectiles.clear();
+}
+
+// NOTICE: Perks, wizard commands & the like change the game outside of `step` and raise `changed_outside_step`, the
+// recorder then compares the real game with a shadow one replaying the recorded actions and saves a keyframe whenever
+// the two stop matching
+pub(crate) struct ReplayRecorder {
+    pub(crate) replay: Replay,
+    shadow: Option<(Game, Vec<GameObject>)>,
+    actions_since_keyframe: usize,
+}
+
+impl ReplayRecorder {
+    pub fn new(replay: Replay) -> Self {
+        ReplayRecorder {
+            replay,
+            shadow: None,
+            actions_since_keyframe: 0,
+        }
+    }
+
+    pub fn keyframe(&mut self, game: &mut Game, game_objects: &[GameObject]) {
+        self.shadow = None;
+        self.actions_since_keyframe = 0;
+        if let Some(snapshot) = keyframe_snapshot(game, game_objects) {
+            self.shadow = restore_game(&snapshot, &game.config).ok();
+            self.replay.entries.push(ReplayEntry::Keyframe(snapshot));
+        }
+    }
+
+    /// Takes the actions of the last frame, the shadow is only compared with the real game after a change outside of `step`
+    pub fn record(&mut self, game: &mut Game, game_objects: &[GameObject]) {
+        let actions = std::mem::take(&mut game.taken_actions);
+        let changed_outside_step = std::mem::take(&mut game.changed_outside_step);
+        if actions.is_empty() && !changed_outside_step {
+            return;
+        }
+        let in_step = match self.shadow {
+            Some((ref mut shadow_game, ref mut shadow_objects)) => {
+                for &action in &actions {
+                    replay_action(action, shadow_game, shadow_objects);
+                    self.replay.entries.push(ReplayEntry::Action(action));
+                }
+                assign_entity_ids(shadow_game, shadow_objects);
+                !changed_outside_step || {
+                    let real = keyframe_snapshot(game, game_objects);
+                    real.is_some() && real == keyframe_snapshot(shadow_game, shadow_objects)
+                }
+            }
+            None => false,
+        };
+        self.actions_since_keyframe += actions.len();
+        if !in_step || self.actions_since_keyframe >= REPLAY_KEYFRAME_ACTIONS {
+            self.keyframe(game, game_objects);
+        }
+    }
+}
+
+/// The game as a keyframe stores it, without the maps of the levels already left so they aren't saved over and over
+fn keyframe_snapshot(game: &mut Game, game_objects: &[GameObject]) -> Option<String> {
+    let past_levels = std::mem::take(&mut game.past_levels);
+    let snapshot = serde_json::to_string(&(&*game, game_objects)).ok();
+    game.past_levels = past_levels;
+    snapshot
+}
+
+fn replay_path(slot: &str) -> PathBuf {
+    slot_dir(slot).join("replay.jsonl")
+}
+
+// NOTICE: The replay file holds one entry per line, a save only appends the entries recorded since the one before and
+// a replay the file doesn't hold yet is written out whole
+pub(crate) fn save_replay(slot: &str, replay: &mut Replay) -> Result<(), Box<dyn Error>> {
+    write_replay(&replay_path(slot), replay)
+}
+
+fn write_replay(path: &Path, replay: &mut Replay) -> Result<(), Box<dyn Error>> {
+    let mut lines = String::new();
+    for entry in &replay.entries[replay.saved..] {
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+    if replay.saved == 0 {
+        let tmp_path = path.with_extension("jsonl.tmp");
+        fs::write(&tmp_path, lines)?;
+        fs::rename(&tmp_path, path)?;
+    } else {
+        OpenOptions::new().append(true).open(path)?.write_all(lines.as_bytes())?;
+    }
+    replay.saved = replay.entries.len();
+    Ok(())
+}
+
+pub(crate) fn load_replay(slot: &str) -> Option<Replay> {
+    read_replay(&replay_path(slot))
+}
+
+fn read_replay(path: &Path) -> Option<Replay> {
+    let file = File::open(path).ok()?;
+    let mut entries = vec![];
+    for line in BufReader::new(file).lines() {
+        match line.ok().and_then(|line| serde_json::from_str(&line).ok()) {
+            Some(entry) => entries.push(entry),
+            // NOTICE: A line cut short by a crash ends the replay, the file is written out whole at the next save
+            None => return Some(Replay { entries, saved: 0 }),
+        }
+    }
+    let saved = entries.len();
+    Some(Replay { entries, saved })
+}
+
+/// Whether the turn just taken lands on the autosave interval
+pub(crate) fn autosave_due(game: &Game) -> bool {
+    game.config.autosave_interval > 0 && game.turn.is_multiple_of(game.config.autosave_interval)
+}
+
+pub(crate) fn autosave(game: &Game, game_objects: &[GameObject]) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(slot_dir(&game.player_name))?;
+    // NOTICE: Daily runs keep a single save so older rotations can't be used to undo a death
+    let rotations = if game.daily.is_some() { 1 } else { AUTOSAVE_ROTATIONS };
+    for rotation in (1..rotations).rev() {
+        let previous_path = autosave_path(&game.player_name, rotation - 1);
+        if previous_path.exists() {
+            fs::rename(&previous_path, autosave_path(&game.player_name, rotation))?;
+        }
+    }
+    save_game(&autosave_path(&game.player_name, 0), game, game_objects)
+}
+
+fn read_save_slot(slot: &str) -> Option<SaveSlot> {
+    (0..AUTOSAVE_ROTATIONS).find_map(|rotation| {
+        let path = autosave_path(slot, rotation);
+        let saved_at = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+        let file = File::open(&path).ok()?;
+        let (summary, _) = serde_json::from_reader::<_, (SaveSummary, IgnoredAny)>(BufReader::new(file)).ok()?;
+        Some(SaveSlot {
+            player_name: summary.player_name,
+            dungeon_level: summary.dungeon_level,
+            saved_at: saved_at.into(),
+        })
+    })
+}
+
+pub(crate) fn list_save_slots() -> Vec<SaveSlot> {
+    let mut slots: Vec<SaveSlot> = match fs::read_dir(SAVE_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| read_save_slot(&entry.file_name().to_string_lossy()))
+            .collect(),
+        Err(_) => vec![],
+    };
+    slots.sort_by_key(|slot| cmp::Reverse(slot.saved_at));
+    slots
+}
+
+/// What was picked on a page of the slot chooser
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SlotChoice {
+    /// The index of the chosen slot
+    Slot(usize),
+    /// The index of the first slot on the page to turn to
+    Page(usize),
+}
+
+/// The options of the slot chooser page showing the slots from `first` on, when the slots don't all fit on a
+/// page the last option turns to the next one, or back to the first from the last page
+pub(crate) fn slot_page_options(slots: &[SaveSlot], first: usize, locale: &Locale) -> Vec<String> {
+    let mut options: Vec<String> = slots
+        .iter()
+        .skip(first)
+        .take(SLOTS_PER_PAGE)
+        .map(|slot| {
+            tr!(
+                locale,
+                "save_slot",
+                name = slot.player_name,
+                level = slot.dungeon_level,
+                date = slot.saved_at.format("%Y-%m-%d %H:%M"),
+            )
+        })
+        .collect();
+    if slots.len() > SLOTS_PER_PAGE {
+        let remaining = slots.len() - first - options.len();
+        options.push(if remaining == 0 {
+            tr!(locale, "slot_page_first")
+        } else {
+            tr!(locale, "slot_page_next", count = remaining)
+        });
+    }
+    options
+}
+
+/// What picking `option` on the slot chooser page showing the slots from `first` on does
+pub(crate) fn slot_choice(option: usize, first: usize, slot_count: usize) -> SlotChoice {
+    let next_page = first + SLOTS_PER_PAGE;
+    if option < SLOTS_PER_PAGE && first + option < slot_count {
+        SlotChoice::Slot(first + option)
+    } else if next_page < slot_count {
+        SlotChoice::Page(next_page)
+    } else {
+        SlotChoice::Page(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+    use crate::game::{MapNote, MessageCategory, new_game, next_level};
+
+    fn json<T: Serialize>(value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    #[test]
+    fn saves_keep_the_log_explored_tiles_and_notes() {
+        let config = Config { seed: Some(3), ..Config::default() };
+        let (mut game, mut game_objects) = new_game("Tester", Class::default(), &config);
+        game.notes.push(MapNote { x: 1, y: 1, text: "first level".into() });
+        next_level(&mut game, &mut game_objects);
+        game.notes.push(MapNote { x: 2, y: 2, text: "second level".into() });
+        game.messages.add("A message worth keeping", MessageCategory::System);
+
+        let path = std::env::temp_dir().join(format!("rust_rogue_round_trip_{}.json", process::id()));
+        save_game(&path, &game, &game_objects).unwrap();
+        let (loaded, _) = load_game(&path, &config).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(json(&loaded.messages), json(&game.messages));
+        assert_eq!(json(&loaded.map), json(&game.map));
+        assert_eq!(json(&loaded.notes), json(&game.notes));
+        assert_eq!(json(&loaded.past_levels), json(&game.past_levels));
+        assert_eq!(loaded.past_levels[0].notes[0].text, "first level");
+        assert!(loaded.past_levels[0].map.iter_coords().any(|position| loaded.past_levels[0].map[position].explored));
+    }
+
+    #[test]
+    fn replay_saves_append_the_new_entries() {
+        let path = std::env::temp_dir().join(format!("rust_rogue_replay_{}.jsonl", process::id()));
+        let mut replay = Replay::default();
+        replay.entries.push(ReplayEntry::Keyframe("{}".into()));
+        replay.entries.push(ReplayEntry::Action(Action::PickUp));
+        write_replay(&path, &mut replay).unwrap();
+        replay.entries.push(ReplayEntry::Action(Action::Descend));
+        write_replay(&path, &mut replay).unwrap();
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        let loaded = read_replay(&path).unwrap();
+
+        fs::write(&path, "{\"Action\":\"PickUp\"}\n{\"Action\":").unwrap();
+        let torn = read_replay(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(lines, 3);
+        assert_eq!(loaded.entries.len(), 3);
+        assert_eq!(loaded.saved, 3);
+        assert!(matches!(loaded.entries[2], ReplayEntry::Action(Action::Descend)));
+        assert_eq!((torn.entries.len(), torn.saved), (1, 0));
+    }
+
+    #[test]
+    fn slot_chooser_pages_through_every_slot() {
+        let locale = Locale::default();
+        let slots: Vec<SaveSlot> = (0..20)
+            .map(|index| SaveSlot { player_name: format!("Hero {}", index), dungeon_level: 1, saved_at: Local::now() })
+            .collect();
+
+        let mut first = 0;
+        let mut reached = vec![];
+        loop {
+            let options = slot_page_options(&slots, first, &locale);
+            assert!(options.len() <= 9);
+            for option in 0..options.len() - 1 {
+                match slot_choice(option, first, slots.len()) {
+                    SlotChoice::Slot(index) => reached.push(index),
+                    SlotChoice::Page(_) => panic!("only the last option turns the page"),
+                }
+            }
+            match slot_choice(options.len() - 1, first, slots.len()) {
+                SlotChoice::Page(0) => break,
+                SlotChoice::Page(page) => first = page,
+                SlotChoice::Slot(index) => panic!("slot {} took the page turn's place", index),
+            }
+        }
+        assert_eq!(reached, (0..20).collect::<Vec<_>>());
+        assert_eq!(slot_page_options(&slots[..9], 0, &locale).len(), 9);
+        assert_eq!(slot_page_options(&slots[..8], 0, &locale).len(), 8);
+    }
+}
@@ -0,0 +1,363 @@
+use super::*;
+use tcod::console::*;
+use std::{cmp, fs};
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+use chrono::Local;
+use crate::controls::poll_input;
+use crate::game::{GameOver, play_game};
+use crate::net::leaderboard_lines;
+use crate::portable::KeyCode;
+use crate::ui::{CellView, Palette, SCREEN_WIDTH, Tcod, menu, msgbox, render_all, scroll_screen_loading, tile_color};
+
+const RECAP_GRAPH_WIDTH: i32 = 60;
+pub(crate) const RECAP_GRAPH_HEIGHT: i32 = 6;
+
+const EMERGENCY_SAVE_FILE: &str = "emergency_save.json";
+const EXPORT_DIR: &str = "exports";
+const SCREENSHOT_DIR: &str = "screenshots";
+const EXPORT_TILE_PIXELS: usize = 4;
+const UNDO_TURNS: usize = 10;
+const REPLAY_TURN_MS: u64 = 150;
+
+// NOTICE: Plays a recorded run back through the normal renderer, the controls and the turn slider sit over the top of the map
+pub(crate) fn replay_viewer(tcod: &mut Tcod, replay: &Replay, config: &Config) {
+    let last = match replay.entries.len().checked_sub(1) {
+        Some(last) => last,
+        None => return,
+    };
+    let mut position = 0;
+    let mut state = replay.state_at(position, config);
+    let mut paused = false;
+    let mut last_step = Instant::now();
+    tcod.map_cells.clear();
+
+    while !tcod.root.window_closed() {
+        let (game, game_objects) = match state {
+            Some((ref game, ref game_objects)) => (game, game_objects),
+            None => return,
+        };
+        tcod.panel.set_default_background(BLACK);
+        tcod.panel.clear();
+        render_all(tcod, game, game_objects);
+        render_replay_bar(tcod, game, position, last, paused);
+        tcod.root.flush();
+
+        poll_input(tcod);
+        let mut target = position;
+        match tcod.key.code {
+            KeyCode::Escape => break,
+            KeyCode::Spacebar => paused = !paused,
+            KeyCode::Right => {
+                paused = true;
+                target = position + 1;
+            }
+            KeyCode::Left => {
+                paused = true;
+                target = position.saturating_sub(1);
+            }
+            KeyCode::Home => target = 0,
+            KeyCode::End => target = last,
+            _ => {}
+        }
+        if tcod.mouse.lbutton_pressed && tcod.mouse.cy == 1 {
+            target = tcod.mouse.cx as usize * last / (SCREEN_WIDTH - 1) as usize;
+        }
+        if !paused && target == position && position < last && last_step.elapsed() >= Duration::from_millis(REPLAY_TURN_MS) {
+            target = position + 1;
+        }
+        let target = cmp::min(target, last);
+        if target != position {
+            // NOTICE: Playing forward runs the next action, anything else rebuilds from the closest keyframe
+            state = match (&replay.entries[target], state.take()) {
+                (&ReplayEntry::Action(action), Some((mut game, mut game_objects))) if target == position + 1 => {
+                    replay_action(action, &mut game, &mut game_objects);
+                    Some((game, game_objects))
+                }
+                _ => replay.state_at(target, config),
+            };
+            position = target;
+            last_step = Instant::now();
+        }
+    }
+    tcod.map_cells.clear();
+}
+
+fn render_replay_bar(tcod: &mut Tcod, game: &Game, position: usize, last: usize, paused: bool) {
+    let locale = &game.config.locale;
+    tcod.root.set_default_background(tcod.palette.color(DARKER_BLUE));
+    tcod.root.rect(0, 0, SCREEN_WIDTH, 2, true, BackgroundFlag::Set);
+    tcod.root.set_default_foreground(tcod.palette.color(LIGHT_YELLOW));
+    let status = if paused { "replay_paused" } else { "replay_playing" };
+    tcod.root.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, tr!(locale, status, turn = game.turn));
+    tcod.root.set_default_foreground(tcod.palette.color(LIGHT_GREY));
+    tcod.root.print_ex(SCREEN_WIDTH - 1, 0, BackgroundFlag::None, TextAlignment::Right, tr!(locale, "replay_controls"));
+    let handle = (position * (SCREEN_WIDTH - 1) as usize).checked_div(last).unwrap_or(0) as i32;
+    for x in 0..SCREEN_WIDTH {
+        let glyph = match x.cmp(&handle) {
+            cmp::Ordering::Less => '=',
+            cmp::Ordering::Equal => '|',
+            cmp::Ordering::Greater => '-',
+        };
+        tcod.root.put_char(x, 1, glyph, BackgroundFlag::None);
+    }
+    tcod.root.set_default_background(BLACK);
+}
+
+/// Columns of background cells, each one the highest value in its share of `values`
+pub(crate) fn render_sparkline(window: &mut Offscreen, (x, y): (i32, i32), values: &[i32]) {
+    let max = values.iter().copied().max().unwrap_or(0);
+    if max == 0 {
+        return;
+    }
+    let width = cmp::min(RECAP_GRAPH_WIDTH as usize, values.len());
+    for column in 0..width {
+        let start = column * values.len() / width;
+        let end = cmp::max(start + 1, (column + 1) * values.len() / width);
+        let value = values[start..end].iter().copied().max().unwrap_or(0);
+        let filled = (value * RECAP_GRAPH_HEIGHT + max - 1) / max;
+        for row in 0..RECAP_GRAPH_HEIGHT {
+            let color = if RECAP_GRAPH_HEIGHT - row <= filled { LIGHT_RED } else { DARKER_GREY };
+            window.set_char_background(x + column as i32, y + row, color, BackgroundFlag::Set);
+        }
+    }
+}
+
+/// The console's cells as ANSI text for terminals, with true color escapes wherever the colors change
+pub(crate) fn ansi_screen(console: &dyn Console) -> String {
+    let (width, height) = (console.width(), console.height());
+    let cells: Vec<CellView> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| CellView {
+            glyph: console.get_char(x, y),
+            foreground: console.get_char_foreground(x, y),
+            background: console.get_char_background(x, y),
+        })
+        .collect();
+    ansi_cells(&cells, width as usize) + "\r\n"
+}
+
+/// Captures the root console as it was last flushed, before the message confirming it is logged
+pub(crate) fn save_screenshot() -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(SCREENSHOT_DIR)?;
+    let path = Path::new(SCREENSHOT_DIR).join(format!("{}.png", Local::now().format("%Y%m%d-%H%M%S%.3f")));
+    tcod::system::save_screenshot(&path);
+    Ok(path)
+}
+
+// NOTICE: Explored map exports, for sharing seeds and reporting generation bugs
+pub(crate) fn export_map(png: bool, game: &Game, game_objects: &[GameObject], palette: &Palette) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(EXPORT_DIR)?;
+    let path = Path::new(EXPORT_DIR).join(format!(
+        "{}-{}-{}.txt",
+        game.player_name,
+        game.dungeon_level,
+        Local::now().format("%Y%m%d-%H%M%S"),
+    ));
+    let locale = &game.config.locale;
+    let mut text = tr!(
+        locale,
+        "export_summary",
+        name = game.player_name,
+        class = game.class.name(locale),
+        level = game.dungeon_level,
+        turns = game.turn,
+        seed = game.seed,
+    );
+    text.push_str("\n\n");
+    for line in export_lines(game, game_objects) {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    File::create(&path)?.write_all(text.as_bytes())?;
+
+    if png {
+        let (map_width, map_height) = map_size(&game.map);
+        let (width, height) = (map_width as usize * EXPORT_TILE_PIXELS, map_height as usize * EXPORT_TILE_PIXELS);
+        let mut pixels = vec![0; width * height * 3];
+        for x in 0..map_width {
+            for y in 0..map_height {
+                if !game.map[(x, y)].explored {
+                    continue;
+                }
+                let background = tile_color((x, y), game, palette);
+                let object = export_object(x, y, game, game_objects).map(|(_, color)| color);
+                for dx in 0..EXPORT_TILE_PIXELS {
+                    for dy in 0..EXPORT_TILE_PIXELS {
+                        // NOTICE: Objects fill the middle of their tile, the background shows around them
+                        let inner = (1..EXPORT_TILE_PIXELS - 1).contains(&dx) && (1..EXPORT_TILE_PIXELS - 1).contains(&dy);
+                        let color = if inner { object.unwrap_or(background) } else { background };
+                        let offset = ((y as usize * EXPORT_TILE_PIXELS + dy) * width + x as usize * EXPORT_TILE_PIXELS + dx) * 3;
+                        pixels[offset..offset + 3].copy_from_slice(&[color.r, color.g, color.b]);
+                    }
+                }
+            }
+        }
+        write_png(&path.with_extension("png"), width, height, &pixels)?;
+    }
+    Ok(path)
+}
+
+// NOTICE: A bare RGB PNG with stored deflate blocks, the exports stay small enough without compression
+fn write_png(path: &Path, width: usize, height: usize, rgb: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+    for row in rgb.chunks(width * 3) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = raw.chunks(0xFFFF).collect();
+    for (index, block) in blocks.iter().enumerate() {
+        let length = block.len() as u16;
+        zlib.push(if index + 1 == blocks.len() { 1 } else { 0 });
+        zlib.extend_from_slice(&length.to_le_bytes());
+        zlib.extend_from_slice(&(!length).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    let (a, b) = raw.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + u32::from(byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    zlib.extend_from_slice(&((b << 16) | a).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    for (kind, data) in [(b"IHDR", &header), (b"IDAT", &zlib), (b"IEND", &vec![])].iter() {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(*kind);
+        png.extend_from_slice(data);
+        let crc = png_crc(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    File::create(path)?.write_all(&png)?;
+    Ok(())
+}
+
+fn png_crc(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(0xFFFF_FFFF, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 })
+    })
+}
+
+pub(crate) fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// FNV-1a over the date, so every player gets the same dungeon on the same day
+pub(crate) fn daily_seed(date: &str) -> u64 {
+    date.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3))
+}
+
+pub(crate) fn daily_scores_screen(tcod: &mut Tcod, config: &Config) {
+    let locale = &config.locale;
+    let date = today();
+    let mut scores: Vec<DailyScore> = load_daily_scores().into_iter().filter(|score| score.date == date).collect();
+    scores.sort_by_key(|score| cmp::Reverse(score.score));
+    let mut lines = vec![tr!(locale, "daily_scores_header", date = date), String::new()];
+    if scores.is_empty() {
+        lines.push(tr!(locale, "daily_no_scores"));
+    }
+    for (rank, score) in scores.iter().enumerate() {
+        lines.push(tr!(
+            locale,
+            "daily_score_row",
+            rank = rank + 1,
+            name = score.player_name,
+            class = score.class.name(locale),
+            score = score.score,
+            level = score.dungeon_level,
+            turns = score.turns,
+        ));
+    }
+    let leaderboard = config.leaderboard_url.as_ref().map(|url| leaderboard_lines(url, locale));
+    if leaderboard.is_some() {
+        lines.push(String::new());
+        lines.push(tr!(locale, "leaderboard_loading"));
+    }
+    scroll_screen_loading(tcod, lines, &tr!(locale, "help_footer"), leaderboard);
+}
+
+pub(crate) fn play_game_with_emergency_save(tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) -> GameOver {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| play_game(tcod, game, game_objects)));
+    match result {
+        Ok(outcome) => outcome,
+        Err(panic_payload) => {
+            match save_game(Path::new(EMERGENCY_SAVE_FILE), game, game_objects) {
+                Ok(()) => eprintln!("Emergency save written to {}.", EMERGENCY_SAVE_FILE),
+                Err(error) => eprintln!("Emergency save failed: {}", error),
+            }
+            panic::resume_unwind(panic_payload);
+        }
+    }
+}
+
+pub(crate) fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        default_hook(panic_info);
+        eprintln!("The game crashed, attempting an emergency save to {}...", EMERGENCY_SAVE_FILE);
+    }));
+}
+
+pub(crate) fn restore_emergency_save(tcod: &mut Tcod, config: &Config) -> GameOver {
+    let emergency_save_path = Path::new(EMERGENCY_SAVE_FILE);
+    if !emergency_save_path.exists() {
+        return GameOver::MainMenu;
+    }
+
+    let choice = menu(
+        &tr!(config.locale, "emergency_restore"),
+        &[tr!(config.locale, "yes"), tr!(config.locale, "no")],
+        40,
+        tcod,
+    );
+    let loaded_game = if choice == Some(0) {
+        load_game(emergency_save_path, config).ok()
+    } else {
+        None
+    };
+    fs::remove_file(emergency_save_path).ok();
+
+    match loaded_game {
+        Some((mut game, mut game_objects)) => play_game_with_emergency_save(tcod, &mut game, &mut game_objects),
+        None if choice == Some(0) => {
+            msgbox(&tr!(config.locale, "emergency_load_failed"), 24, tcod);
+            GameOver::MainMenu
+        }
+        None => GameOver::MainMenu,
+    }
+}
+
+pub(crate) fn take_snapshot(game: &mut Game, game_objects: &[GameObject]) {
+    if let Ok(snapshot) = serde_json::to_string(&(&*game, game_objects)) {
+        game.snapshots.push_back(snapshot);
+        // NOTICE: The newest snapshot is the current state, the ones before it are the turns to undo
+        if game.snapshots.len() > UNDO_TURNS + 1 {
+            game.snapshots.pop_front();
+        }
+    }
+}
+
+impl Replay {
+    /// Rebuilds the game after the entry at `position`, playing the actions since the keyframe before it
+    pub fn state_at(&self, position: usize, config: &Config) -> Option<(Game, Vec<GameObject>)> {
+        let keyframe = (0..=position).rev().find(|&index| matches!(self.entries[index], ReplayEntry::Keyframe(_)))?;
+        let (mut game, mut game_objects) = match self.entries[keyframe] {
+            ReplayEntry::Keyframe(ref snapshot) => restore_game(snapshot, config).ok()?,
+            ReplayEntry::Action(_) => return None,
+        };
+        for entry in &self.entries[keyframe + 1..=position] {
+            if let ReplayEntry::Action(action) = *entry {
+                replay_action(action, &mut game, &mut game_objects);
+            }
+        }
+        Some((game, game_objects))
+    }
+}
@@ -0,0 +1,541 @@
+use crossterm::cursor::{Hide, Show};
+use crossterm::event::{
+    self as term_event, Event as TermEvent, KeyCode as TermKeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+};
+use crossterm::execute;
+use crossterm::terminal::{self as term, EnterAlternateScreen, LeaveAlternateScreen};
+use std::{cmp, iter};
+use std::error::Error;
+use std::io::{self, Write, stdout};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use crate::character::{Class, offer_perks, take_perk};
+use crate::combat::{dispatch_events, sees_invisible};
+use crate::config::{Config, Locale};
+use crate::controls::{Action, Command, KeyBinding, MovementKeys, PlayerAction, key_bindings};
+use crate::game::{Game, MessageCategory, assign_entity_ids, new_game, record_death, step};
+use crate::items::{
+    INVENTORY_WIDTH, carried_weight, carry_capacity, cycle_inventory_sort, inventory_rows, item_targets, quickslot_item,
+};
+use crate::map::{MAP_HEIGHT, map_size, view_position, view_x};
+use crate::net::submission_status;
+use crate::object::GameObject;
+use crate::portable::KeyCode;
+use crate::portable::colors::*;
+use crate::save::{
+    ReplayRecorder, SlotChoice, ansi_cells, autosave, autosave_due, list_save_slots, load_autosave, load_replay,
+    save_replay, slot_choice, slot_dir, slot_page_options,
+};
+use crate::tutorial::advance_tutorial;
+use crate::ui::{
+    BAR_WIDTH, BLANK_CELL, CellView, MAX_NAME_LENGTH, MSG_HEIGHT, MSG_WIDTH, MSG_X, MenuRow, PANEL_HEIGHT,
+    PANEL_Y, Palette, SCREEN_HEIGHT, SCREEN_WIDTH, SIDEBAR_WIDTH, VIEW_WIDTH, hp_color, is_valid_name_char,
+    location, panel_bars, sidebar_enemies, status_tags, tile_view,
+};
+
+const MENU_BACKGROUND: Color = Color { r: 20, g: 20, b: 30 };
+
+/// Plays in the launching terminal, starting with the run saved in the `load` slot when there is one
+pub fn run(config: &Config, load: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let (columns, rows) = term::size()?;
+    if i32::from(columns) < SCREEN_WIDTH || i32::from(rows) < SCREEN_HEIGHT {
+        let needed = format!("{}x{}", SCREEN_WIDTH, SCREEN_HEIGHT);
+        return Err(format!("the terminal is {}x{}, the game needs at least {}", columns, rows, needed).into());
+    }
+    term::enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, Hide)?;
+    // NOTICE: A panic would otherwise leave the shell in raw mode, the terminal is restored before it goes on
+    let result = panic::catch_unwind(AssertUnwindSafe(|| main_menu(config, load)));
+    execute!(stdout(), Show, LeaveAlternateScreen).ok();
+    term::disable_raw_mode().ok();
+    match result {
+        Ok(result) => Ok(result?),
+        Err(panic) => panic::resume_unwind(panic),
+    }
+}
+
+#[derive(Clone)]
+struct Screen {
+    cells: Vec<CellView>,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Screen { cells: vec![BLANK_CELL; (SCREEN_WIDTH * SCREEN_HEIGHT) as usize] }
+    }
+
+    fn cell(&mut self, x: i32, y: i32) -> Option<&mut CellView> {
+        if x >= 0 && y >= 0 && x < SCREEN_WIDTH && y < SCREEN_HEIGHT {
+            self.cells.get_mut((y * SCREEN_WIDTH + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn put(&mut self, x: i32, y: i32, glyph: char, foreground: Color) {
+        if let Some(cell) = self.cell(x, y) {
+            cell.glyph = glyph;
+            cell.foreground = foreground;
+        }
+    }
+
+    pub fn print(&mut self, x: i32, y: i32, text: &str, foreground: Color) {
+        for (offset, glyph) in text.chars().enumerate() {
+            self.put(x + offset as i32, y, glyph, foreground);
+        }
+    }
+
+    pub fn fill(&mut self, (x, y): (i32, i32), (width, height): (i32, i32), background: Color) {
+        for cell_y in y..y + height {
+            for cell_x in x..x + width {
+                if let Some(cell) = self.cell(cell_x, cell_y) {
+                    *cell = CellView { glyph: ' ', foreground: WHITE, background };
+                }
+            }
+        }
+    }
+
+    pub fn show(&self) -> io::Result<()> {
+        let mut out = stdout();
+        out.write_all(ansi_cells(&self.cells, SCREEN_WIDTH as usize).as_bytes())?;
+        out.flush()
+    }
+}
+
+/// Breaks text into lines of at most `width` characters, between words where it can
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+            while line.chars().count() > width {
+                let rest = line.chars().skip(width).collect();
+                lines.push(line.chars().take(width).collect());
+                line = rest;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn read_key() -> io::Result<KeyEvent> {
+    loop {
+        if let TermEvent::Key(key) = term_event::read()? {
+            if key.kind != KeyEventKind::Release {
+                return Ok(key);
+            }
+        }
+    }
+}
+
+/// Raw mode swallows Ctrl+C, it backs out like Escape does
+fn is_escape(key: KeyEvent) -> bool {
+    key.code == TermKeyCode::Esc || (key.code == TermKeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// The bound command of a key, Alt bindings are left out as terminals rarely pass them on
+fn command(key: KeyEvent, movement_keys: MovementKeys) -> Option<Command> {
+    if is_escape(key) {
+        return Some(Command::Exit);
+    }
+    let code = match key.code {
+        TermKeyCode::Up => Some(KeyCode::Up),
+        TermKeyCode::Down => Some(KeyCode::Down),
+        TermKeyCode::Left => Some(KeyCode::Left),
+        TermKeyCode::Right => Some(KeyCode::Right),
+        TermKeyCode::Enter => Some(KeyCode::Enter),
+        _ => None,
+    };
+    key_bindings(movement_keys)
+        .find(|(binding, _)| match *binding {
+            KeyBinding::Code(binding_code) => code == Some(binding_code) && !key.modifiers.contains(KeyModifiers::SHIFT),
+            KeyBinding::Shift(binding_code) => code == Some(binding_code) && key.modifiers.contains(KeyModifiers::SHIFT),
+            KeyBinding::Text(text) => matches!(key.code, TermKeyCode::Char(c) if text.chars().eq(iter::once(c))),
+            KeyBinding::Alt(_) => false,
+        })
+        .map(|&(_, command)| command)
+}
+
+/// Draws a window of lines centered over `background`
+fn window(background: &Screen, lines: &[(String, Color)], width: i32) -> Screen {
+    let mut screen = background.clone();
+    let height = lines.len() as i32;
+    let (x, y) = (SCREEN_WIDTH / 2 - width / 2, SCREEN_HEIGHT / 2 - height / 2);
+    screen.fill((x - 1, y - 1), (width + 2, height + 2), MENU_BACKGROUND);
+    for (line, (text, color)) in lines.iter().enumerate() {
+        screen.print(x, y + line as i32, text, *color);
+    }
+    screen
+}
+
+fn header_lines(header: &str, width: i32) -> Vec<(String, Color)> {
+    wrap(header.trim_end(), width as usize).into_iter().map(|line| (line, WHITE)).collect()
+}
+
+/// Same as the window's `menu_rows`, options are picked with their number and any other key dismisses the menu
+fn menu_rows(background: &Screen, header: &str, rows: &[MenuRow], width: i32) -> io::Result<Option<usize>> {
+    menu_rows_key(background, header, rows, width).map(|(index, _)| index)
+}
+
+/// Same as `menu_rows`, along with the key that was pressed
+fn menu_rows_key(background: &Screen, header: &str, rows: &[MenuRow], width: i32) -> io::Result<(Option<usize>, KeyEvent)> {
+    let mut lines = header_lines(header, width);
+    for row in rows {
+        lines.push(match row {
+            MenuRow::Heading(text) => (text.clone(), LIGHT_YELLOW),
+            MenuRow::Option(index, text) => (format!("({}) {}", index + 1, text), WHITE),
+        });
+    }
+    window(background, &lines, width).show()?;
+    let key = read_key()?;
+    let index = match key.code {
+        TermKeyCode::Char(c) => c.to_digit(10).map(|digit| (digit as usize).wrapping_sub(1)),
+        _ => None,
+    };
+    let index = index.filter(|index| rows.iter().any(|row| matches!(row, MenuRow::Option(option, _) if option == index)));
+    Ok((index, key))
+}
+
+fn menu<T: AsRef<str>>(background: &Screen, header: &str, options: &[T], width: i32) -> io::Result<Option<usize>> {
+    let rows: Vec<MenuRow> = options
+        .iter()
+        .enumerate()
+        .map(|(index, option)| MenuRow::Option(index, option.as_ref().to_string()))
+        .collect();
+    menu_rows(background, header, &rows, width)
+}
+
+fn msgbox(background: &Screen, text: &str, width: i32, locale: &Locale) -> io::Result<()> {
+    let mut lines = header_lines(text, width);
+    lines.push((String::new(), WHITE));
+    lines.push((tr!(locale, "terminal_continue"), GREY));
+    window(background, &lines, width).show()?;
+    read_key().map(|_| ())
+}
+
+fn text_input(background: &Screen, header: &str, max_length: usize, width: i32) -> io::Result<Option<String>> {
+    let mut text = String::new();
+    loop {
+        let mut lines = header_lines(header, width);
+        lines.push((String::new(), WHITE));
+        lines.push((format!("> {}_", text), WHITE));
+        window(background, &lines, width).show()?;
+
+        let key = read_key()?;
+        match key.code {
+            TermKeyCode::Enter if !text.trim().is_empty() => return Ok(Some(text.trim().to_string())),
+            _ if is_escape(key) => return Ok(None),
+            TermKeyCode::Backspace => {
+                text.pop();
+            }
+            TermKeyCode::Char(c) if text.chars().count() < max_length && is_valid_name_char(c) => text.push(c),
+            _ => {}
+        }
+    }
+}
+
+fn main_menu(config: &Config, load: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let locale = &config.locale;
+    let blank = Screen::new();
+    if let Some(slot) = load {
+        match load_autosave(slot, config) {
+            Some((mut game, mut game_objects)) => play_game(&mut game, &mut game_objects)?,
+            None => msgbox(&blank, &tr!(locale, "save_load_failed"), 30, locale)?,
+        }
+    }
+    loop {
+        let slots = list_save_slots();
+        let mut labels = vec![tr!(locale, "menu_new_game")];
+        if !slots.is_empty() {
+            labels.push(tr!(locale, "menu_continue"));
+        }
+        labels.push(tr!(locale, "menu_quit"));
+
+        let choice = menu(&blank, &tr!(locale, "title"), &labels, 30)?.map(|index| labels[index].clone());
+        match choice {
+            Some(label) if label == tr!(locale, "menu_new_game") => {
+                let player_name = match text_input(&blank, &tr!(locale, "enter_name"), MAX_NAME_LENGTH, 30)? {
+                    Some(player_name) => player_name,
+                    None => continue,
+                };
+                if slots.iter().any(|slot| slot.player_name == player_name) || slot_dir(&player_name).exists() {
+                    msgbox(&blank, &tr!(locale, "name_taken", name = player_name), 30, locale)?;
+                    continue;
+                }
+                let options: Vec<String> = Class::ALL
+                    .iter()
+                    .map(|class| format!("{}: {}", class.name(locale), class.description(locale)))
+                    .collect();
+                if let Some(index) = menu(&blank, &tr!(locale, "choose_class"), &options, 60)? {
+                    let (mut game, mut game_objects) = new_game(&player_name, Class::ALL[index], config);
+                    play_game(&mut game, &mut game_objects)?;
+                }
+            }
+            Some(label) if label == tr!(locale, "menu_continue") => {
+                let mut first = 0;
+                loop {
+                    let options = slot_page_options(&slots, first, locale);
+                    let option = match menu(&blank, &tr!(locale, "choose_slot"), &options, 50)? {
+                        Some(option) => option,
+                        None => break,
+                    };
+                    match slot_choice(option, first, slots.len()) {
+                        SlotChoice::Page(page) => first = page,
+                        SlotChoice::Slot(index) => {
+                            match load_autosave(&slots[index].player_name, config) {
+                                Some((mut game, mut game_objects)) => play_game(&mut game, &mut game_objects)?,
+                                None => msgbox(&blank, &tr!(locale, "save_load_failed"), 30, locale)?,
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+            Some(label) if label == tr!(locale, "menu_quit") => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+// NOTICE: The turn loop of the window's `play_game`, minus the commands that need a mouse or a window
+fn play_game(game: &mut Game, game_objects: &mut Vec<GameObject>) -> Result<(), Box<dyn Error>> {
+    let palette = Palette::new(game.config.palette);
+    let replay = if game.turn == 0 { None } else { load_replay(&game.player_name) };
+    let mut recorder = ReplayRecorder::new(replay.unwrap_or_default());
+    recorder.keyframe(game, game_objects);
+    loop {
+        let screen = render(game, game_objects, &palette);
+        screen.show()?;
+        let key = read_key()?;
+
+        let dungeon_level = game.dungeon_level;
+        assign_entity_ids(game, game_objects);
+        let mut player_action = match command(key, game.config.movement_keys) {
+            Some(Command::Move(dx, dy)) => step(game, game_objects, Action::Move(dx, dy)),
+            Some(Command::PickUp) => step(game, game_objects, Action::PickUp),
+            Some(Command::Inventory) => use_item(&screen, game, game_objects)?,
+            Some(Command::Wait) => step(game, game_objects, Action::Wait),
+            Some(Command::CloseDoor) => step(game, game_objects, Action::CloseDoor),
+            Some(Command::CastSpell) => step(game, game_objects, Action::CastSpell),
+            Some(Command::TakeStairs) => step(game, game_objects, Action::Descend),
+            Some(Command::Quickslot(slot)) => match quickslot_item(slot, game) {
+                Some(inventory_index) => step(game, game_objects, Action::UseItem(inventory_index)),
+                None => {
+                    game.messages.add(tr!(game.config.locale, "quickslot_empty", slot = slot + 1), MessageCategory::System);
+                    PlayerAction::DidntTakeTurn
+                }
+            },
+            Some(Command::Statistics) => {
+                let stats = game.stats.lines(game.turn, &game.config.locale).join("\n");
+                let text = format!("{}\n\n{}", tr!(game.config.locale, "statistics"), stats);
+                msgbox(&screen, &text, 40, &game.config.locale)?;
+                PlayerAction::DidntTakeTurn
+            }
+            Some(Command::SortInventory) => {
+                cycle_inventory_sort(game);
+                PlayerAction::DidntTakeTurn
+            }
+            Some(Command::Exit) => PlayerAction::Exit,
+            Some(_) => {
+                game.messages.add(tr!(game.config.locale, "terminal_unavailable"), MessageCategory::System);
+                PlayerAction::DidntTakeTurn
+            }
+            None => PlayerAction::DidntTakeTurn,
+        };
+        dispatch_events(game, game_objects);
+        advance_tutorial(game, game_objects);
+        game.sounds.clear();
+        game.projectiles.clear();
+        game.shake = 0;
+        while game.pending_perks > 0 && game.player.is_alive {
+            if !choose_perk(&render(game, game_objects, &palette), game)? {
+                player_action = PlayerAction::Exit;
+                break;
+            }
+        }
+        recorder.record(game, game_objects);
+
+        if !game.player.is_alive {
+            let morgue = record_death(game, &mut recorder.replay);
+            return death_screen(&render(game, game_objects, &palette), game, &morgue);
+        }
+
+        if player_action == PlayerAction::Exit {
+            let locale = &game.config.locale;
+            let labels = [tr!(locale, "escape_resume"), tr!(locale, "escape_save_quit")];
+            if menu(&screen, &tr!(locale, "escape_header"), &labels, 30)? == Some(1) {
+                match autosave(game, game_objects).and_then(|()| save_replay(&game.player_name, &mut recorder.replay)) {
+                    Ok(()) => return Ok(()),
+                    Err(error) => msgbox(&screen, &tr!(game.config.locale, "autosave_failed", error = error), 30, &game.config.locale)?,
+                }
+            }
+            continue;
+        }
+
+        let autosave_due = player_action == PlayerAction::TookTurn && autosave_due(game);
+        if autosave_due || game.dungeon_level != dungeon_level {
+            if let Err(error) = autosave(game, game_objects).and_then(|()| save_replay(&game.player_name, &mut recorder.replay)) {
+                game.messages.add(tr!(game.config.locale, "autosave_failed", error = error), MessageCategory::Danger);
+            }
+        }
+    }
+}
+
+fn use_item(screen: &Screen, game: &mut Game, game_objects: &mut Vec<GameObject>) -> io::Result<PlayerAction> {
+    let locale = &game.config.locale;
+    let rows = inventory_rows(&game.inventory, game.inventory_sort, locale);
+    let inventory_index = match menu_rows(screen, &tr!(locale, "inventory_header"), &rows, INVENTORY_WIDTH)? {
+        Some(inventory_index) if inventory_index < game.inventory.len() => inventory_index,
+        _ => return Ok(PlayerAction::DidntTakeTurn),
+    };
+    let targets = game.inventory[inventory_index]
+        .item
+        .and_then(|item| item_targets(item, &game.inventory))
+        .unwrap_or_default();
+    if targets.is_empty() {
+        return Ok(step(game, game_objects, Action::UseItem(inventory_index)));
+    }
+    let options: Vec<String> = targets.iter().map(|&target| game.inventory[target].display_name(&game.config.locale)).collect();
+    Ok(match menu(screen, &tr!(game.config.locale, "use_on_header"), &options, INVENTORY_WIDTH)? {
+        Some(choice) => step(game, game_objects, Action::UseItemOn(inventory_index, targets[choice])),
+        None => PlayerAction::DidntTakeTurn,
+    })
+}
+
+/// False when the player backed out with Escape to quit, the perk then stays pending
+fn choose_perk(screen: &Screen, game: &mut Game) -> io::Result<bool> {
+    let offered = offer_perks(game);
+    if offered.is_empty() {
+        return Ok(true);
+    }
+    let locale = &game.config.locale;
+    let rows: Vec<MenuRow> = offered
+        .iter()
+        .enumerate()
+        .map(|(index, perk)| MenuRow::Option(index, format!("{}: {}", perk.name(locale), perk.description(locale))))
+        .collect();
+    loop {
+        match menu_rows_key(screen, &tr!(locale, "choose_perk"), &rows, 60)? {
+            (Some(choice), _) => {
+                take_perk(offered[choice], game);
+                return Ok(true);
+            }
+            (None, key) if is_escape(key) => return Ok(false),
+            (None, _) => {}
+        }
+    }
+}
+
+fn death_screen(screen: &Screen, game: &mut Game, morgue: &Result<PathBuf, Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+    let locale = &game.config.locale;
+    let killer = game.killer.clone().unwrap_or_else(|| tr!(locale, "killer_unknown"));
+    let depth = match game.arena {
+        Some(ref arena) => tr!(locale, "recap_waves", waves = arena.waves_survived, turns = game.turn),
+        None => tr!(locale, "recap_depth", level = game.dungeon_level, turns = game.turn),
+    };
+    let morgue = match morgue {
+        Ok(path) => tr!(locale, "morgue_written", path = path.display()),
+        Err(error) => tr!(locale, "morgue_failed", error = error),
+    };
+    let mut lines = vec![tr!(locale, "player_died"), String::new(), tr!(locale, "recap_killer", killer = killer), depth, morgue];
+    lines.extend(submission_status(&mut game.leaderboard_submission, locale));
+    lines.push(String::new());
+    lines.extend(game.stats.lines(game.turn, locale));
+    msgbox(screen, &lines.join("\n"), 60, locale)?;
+    Ok(())
+}
+
+/// The window's map and panel, without the tooltips, animations and overlays
+fn render(game: &Game, game_objects: &[GameObject], palette: &Palette) -> Screen {
+    let mut screen = Screen::new();
+    let (map_width, map_height) = map_size(&game.map);
+    let left = view_x(game);
+    for y in 0..cmp::min(map_height, MAP_HEIGHT) {
+        for x in 0..cmp::min(map_width - left, VIEW_WIDTH) {
+            if let Some(cell) = screen.cell(x, y) {
+                *cell = tile_view((left + x, y), game, palette);
+            }
+        }
+    }
+
+    for memory in game.remembered.iter().filter(|memory| !game.fov.is_in_fov(memory.x, memory.y)) {
+        if let Some((x, y)) = view_position((memory.x, memory.y), game) {
+            screen.put(x, y, memory.char, lerp(palette.color(memory.color), BLACK, 0.5));
+        }
+    }
+    let see_invisible = sees_invisible(game);
+    let mut to_draw: Vec<_> = iter::once(&game.player)
+        .chain(game.partner.iter())
+        .chain(game_objects.iter())
+        .filter(|go| game.fov.is_in_fov(go.x, go.y) || (go.always_visible && game.map[(go.x, go.y)].explored))
+        .filter(|go| !go.invisible || see_invisible)
+        .collect();
+    to_draw.sort_by_key(|game_object| game_object.blocks);
+    for game_object in to_draw {
+        if let Some((x, y)) = view_position(game_object.position(), game) {
+            screen.put(x, y, game_object.char, palette.color(game_object.color));
+        }
+    }
+
+    let locale = &game.config.locale;
+    let bars = panel_bars(game, palette);
+    for (row, (name, value, maximum, color)) in bars.iter().enumerate() {
+        let y = PANEL_Y + 1 + row as i32;
+        let filled = if *maximum > 0 { value * BAR_WIDTH / maximum } else { 0 };
+        screen.fill((1, y), (BAR_WIDTH, 1), lerp(*color, BLACK, 0.6));
+        screen.fill((1, y), (filled.max(0), 1), *color);
+        let text = format!("{}: {}/{}", name, value, maximum);
+        screen.print(1 + BAR_WIDTH / 2 - text.chars().count() as i32 / 2, y, &text, WHITE);
+    }
+    let y = PANEL_Y + 1 + bars.len() as i32;
+
+    let weight = carried_weight(&game.inventory, &game.config);
+    let capacity = carry_capacity(&game.player);
+    let (load_color, load_key) = if weight > capacity { (ORANGE, "load_encumbered") } else { (WHITE, "load") };
+    screen.print(1, y, &tr!(locale, load_key, weight = weight, capacity = capacity), palette.color(load_color));
+    let level = match game.arena {
+        Some(ref arena) => tr!(locale, "arena_panel", wave = arena.wave),
+        None => location(game),
+    };
+    screen.print(1, y + 1, &level, WHITE);
+    for (x, _, tag, color) in status_tags(game) {
+        screen.print(x, PANEL_Y + PANEL_HEIGHT - 1, &tag, palette.color(color));
+    }
+
+    screen.print(VIEW_WIDTH + 1, 0, &tr!(locale, "sidebar_header"), palette.color(LIGHT_GREY));
+    let enemies = sidebar_enemies(game, game_objects);
+    if enemies.is_empty() {
+        screen.print(VIEW_WIDTH + 1, 2, &tr!(locale, "sidebar_empty"), palette.color(DARK_GREY));
+    }
+    for (index, enemy) in enemies.iter().enumerate().take_while(|&(index, _)| 3 + index as i32 * 3 < MAP_HEIGHT) {
+        let y = 2 + index as i32 * 3;
+        screen.put(VIEW_WIDTH + 1, y, enemy.char, palette.color(enemy.color));
+        let name: String = enemy.display_name(locale).chars().take((SIDEBAR_WIDTH - 4) as usize).collect();
+        screen.print(VIEW_WIDTH + 3, y, &name, WHITE);
+        let (hp, max_hp) = enemy.fighter.map_or((0, 0), |fighter| (fighter.hp, fighter.max_hp));
+        let filled = if max_hp > 0 { hp * (SIDEBAR_WIDTH - 2) / max_hp } else { 0 };
+        let color = hp_color(hp, max_hp, palette);
+        screen.fill((VIEW_WIDTH + 1, y + 1), (SIDEBAR_WIDTH - 2, 1), lerp(color, BLACK, 0.6));
+        screen.fill((VIEW_WIDTH + 1, y + 1), (filled.max(0), 1), color);
+    }
+
+    let mut y = PANEL_Y + MSG_HEIGHT as i32;
+    'messages: for message in game.messages.iter().rev() {
+        for line in wrap(&message.text(), MSG_WIDTH as usize).iter().rev() {
+            y -= 1;
+            if y < PANEL_Y {
+                break 'messages;
+            }
+            screen.print(MSG_X, y, line, palette.color(message.category.color()));
+        }
+    }
+    screen
+}
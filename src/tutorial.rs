@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use crate::character::Stats;
+use crate::combat::GameEvent;
+use crate::config::Config;
+use crate::controls::{Command, key_bindings};
+use crate::game::{Game, MessageCategory, place_partner, populate_level};
+use crate::items::Item;
+use crate::map::{initialise_fov, level_rng, make_map};
+use crate::monsters::announce_uniques;
+use crate::object::GameObject;
+
+/// The window's tutorial level and the highlight over what each step is about
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Tutorial {
+    step: usize,
+    origin: (i32, i32),
+}
+
+// NOTICE: Scripted events layered over the normal loop, each step prompts once and waits for its condition
+struct ScriptedStep {
+    prompt: &'static str,
+    command: Option<Command>,
+    /// What the window highlights while the step is on
+    #[cfg_attr(not(feature = "window"), allow(dead_code))]
+    target: fn(&GameObject) -> bool,
+    done: fn(&Game, &[GameObject]) -> bool,
+}
+
+const TUTORIAL_STEPS: &[ScriptedStep] = &[
+    ScriptedStep {
+        prompt: "tutorial_move",
+        command: None,
+        target: |_| false,
+        done: |game, _| game.tutorial.as_ref().is_none_or(|tutorial| game.player.position() != tutorial.origin),
+    },
+    ScriptedStep {
+        prompt: "tutorial_pick_up",
+        command: Some(Command::PickUp),
+        target: |game_object| game_object.item == Some(Item::Heal),
+        done: |_, game_objects| !game_objects.iter().any(|game_object| game_object.item == Some(Item::Heal)),
+    },
+    ScriptedStep {
+        prompt: "tutorial_use",
+        command: Some(Command::Inventory),
+        target: |_| false,
+        done: |game, _| game.stats.items_used > 0,
+    },
+    ScriptedStep {
+        prompt: "tutorial_attack",
+        command: None,
+        target: |game_object| game_object.ai.is_some(),
+        done: |game, _| game.stats.damage_dealt > 0,
+    },
+    ScriptedStep {
+        prompt: "tutorial_kill",
+        command: None,
+        target: |game_object| game_object.ai.is_some(),
+        done: |game, _| game.stats.total_kills() > 0,
+    },
+    // NOTICE: The last step ends when the stairs are taken, which finishes the tutorial
+    ScriptedStep {
+        prompt: "tutorial_stairs",
+        command: Some(Command::TakeStairs),
+        target: |game_object| game_object.is_stairs,
+        done: |_, _| false,
+    },
+];
+
+fn command_key(command: Command, config: &Config) -> String {
+    key_bindings(config.movement_keys)
+        .find(|&&(_, bound)| bound == command)
+        .map_or_else(String::new, |(binding, _)| binding.label(&config.locale))
+}
+
+fn tutorial_prompt(step: &ScriptedStep, config: &Config) -> String {
+    let key = step.command.map(|command| command_key(command, config)).unwrap_or_default();
+    tr!(config.locale, step.prompt, key = key)
+}
+
+pub(crate) fn advance_tutorial(game: &mut Game, game_objects: &[GameObject]) {
+    loop {
+        let step = match game.tutorial {
+            Some(ref tutorial) => tutorial.step,
+            None => return,
+        };
+        if !(TUTORIAL_STEPS[step].done)(game, game_objects) {
+            return;
+        }
+        if let Some(tutorial) = game.tutorial.as_mut() {
+            tutorial.step += 1;
+        }
+        game.messages.add(tutorial_prompt(&TUTORIAL_STEPS[step + 1], &game.config), MessageCategory::System);
+    }
+}
+
+pub(crate) fn tutorial_on_last_step(game: &Game) -> bool {
+    game.tutorial.as_ref().is_none_or(|tutorial| tutorial.step + 1 == TUTORIAL_STEPS.len())
+}
+
+/// Leaves the tutorial level for a fresh first level, statistics start over with it
+pub(crate) fn finish_tutorial(message: &str, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    game.tutorial = None;
+    #[cfg(feature = "window")]
+    {
+        game.activity = None;
+    }
+    game.messages.add(tr!(game.config.locale, message), MessageCategory::System);
+    if let Some(fighter) = game.player.fighter.as_mut() {
+        fighter.hp = fighter.max_hp;
+    }
+    game.stats = Stats {
+        deepest_level: 1,
+        ..Default::default()
+    };
+    game.events.push(GameEvent::LevelChanged { level: 1 });
+    let mut rng = level_rng(game.seed, 1);
+    game.map = make_map(&mut game.player, game_objects, &game.config, &mut rng);
+    place_partner(game, game_objects);
+    game.remembered.clear();
+    game.notes.clear();
+    populate_level(game, game_objects, &mut rng);
+    initialise_fov(game, game_objects);
+    announce_uniques(game, game_objects);
+}
@@ -0,0 +1,86 @@
+use super::*;
+use tcod::console::*;
+use crate::config::Name;
+use crate::items::new_consumable;
+use crate::map::{MAP_HEIGHT, MAP_WIDTH, Map, Rectangle, Tile, make_horizontal_tunnel, make_room, view_position};
+use crate::monsters::new_monster_of;
+use crate::portable::colors::*;
+use crate::ui::{Tcod, VIEW_WIDTH};
+
+/// Three rooms in a row: a potion to pick up and drink, a lone orc, then the stairs
+fn make_tutorial_map(player: &mut GameObject, game_objects: &mut Vec<GameObject>, config: &Config) -> Map {
+    // NOTICE: The rooms are laid out for the full size whatever the configured map size, the view and everything else
+    // read the size from the map itself
+    let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, Tile::wall());
+    game_objects.clear();
+
+    let rooms = [
+        Rectangle::new(8, 14, 12, 9),
+        Rectangle::new(34, 14, 12, 9),
+        Rectangle::new(60, 14, 12, 9),
+    ];
+    for room in &rooms {
+        make_room(*room, &mut map);
+    }
+    let (_, center_y) = rooms[0].center();
+    make_horizontal_tunnel(rooms[0].center().0, rooms[2].center().0, center_y, &mut map);
+
+    let (x, y) = rooms[0].center();
+    player.set_position(x - 3, y);
+    game_objects.push(new_consumable(x + 2, y, Item::Heal));
+
+    let (x, y) = rooms[1].center();
+    let mut orc = new_monster_of("orc", x, y, config);
+    orc.name = Name::key("tutorial_orc");
+    // NOTICE: Weaker than a dungeon orc, the player starts the tutorial wounded
+    if let Some(fighter) = orc.fighter.as_mut() {
+        fighter.max_hp = 6;
+        fighter.hp = 6;
+        fighter.power = 1;
+        fighter.xp = 10;
+    }
+    game_objects.push(orc);
+
+    let (x, y) = rooms[2].center();
+    let mut stairs = GameObject::new(x, y, '<', WHITE, "stairs", false);
+    stairs.description = Name::key("stairs_description");
+    stairs.always_visible = true;
+    stairs.is_stairs = true;
+    game_objects.push(stairs);
+
+    map
+}
+
+pub(crate) fn start_tutorial(game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    game.map = make_tutorial_map(&mut game.player, game_objects, &game.config);
+    // NOTICE: The level `new_game` generated is gone, a unique placed on it can still show up later
+    game.spawned_uniques.clear();
+    // NOTICE: Start wounded so the potion has something to heal
+    if let Some(fighter) = game.player.fighter.as_mut() {
+        fighter.hp = fighter.max_hp / 2;
+    }
+    game.tutorial = Some(Tutorial { step: 0, origin: game.player.position() });
+    game.messages.add(tr!(game.config.locale, "tutorial_welcome"), MessageCategory::System);
+    game.messages.add(tutorial_prompt(&TUTORIAL_STEPS[0], &game.config), MessageCategory::System);
+    initialise_fov(game, game_objects);
+}
+
+pub(crate) fn render_tutorial(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let step = match game.tutorial {
+        Some(ref tutorial) => &TUTORIAL_STEPS[tutorial.step],
+        None => return,
+    };
+    let locale = &game.config.locale;
+    for game_object in game_objects.iter().filter(|go| (step.target)(go) && game.fov.is_in_fov(go.x, go.y)) {
+        if let Some((x, y)) = view_position(game_object.position(), game) {
+            tcod.root.set_char_background(x, y, tcod.palette.color(DARK_YELLOW), BackgroundFlag::Set);
+        }
+    }
+    tcod.root.set_default_background(tcod.palette.color(DARKER_BLUE));
+    tcod.root.rect(0, 0, VIEW_WIDTH, 1, true, BackgroundFlag::Set);
+    tcod.root.set_default_foreground(tcod.palette.color(LIGHT_YELLOW));
+    tcod.root.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, tutorial_prompt(step, &game.config));
+    tcod.root.set_default_foreground(tcod.palette.color(LIGHT_GREY));
+    tcod.root.print_ex(VIEW_WIDTH - 1, 0, BackgroundFlag::None, TextAlignment::Right, tr!(locale, "tutorial_skip"));
+    tcod.root.set_default_background(BLACK);
+}
@@ -0,0 +1,87 @@
+use std::cmp;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::config::Locale;
+use crate::game::{Game, MessageCategory};
+
+/// Drawing both front ends share, palettes, map cells, the panel's bars and the sidebar's monsters
+#[cfg(any(feature = "window", feature = "terminal"))]
+mod frontend;
+/// The window's rendering, menus and screens
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(any(feature = "window", feature = "terminal"))]
+pub(crate) use self::frontend::*;
+#[cfg(feature = "window")]
+pub(crate) use self::window::*;
+
+pub(crate) const SCREEN_HEIGHT: i32 = 50;
+pub(crate) const PANEL_HEIGHT: i32 = 7;
+
+// NOTICE: Percentages of hit points under which the player is warned once, the panel border flashes under the last
+pub(crate) const LOW_HP_WARNINGS: [(i32, &str); 2] = [(50, "hp_warning_wounded"), (25, "hp_warning_badly_wounded")];
+
+pub(crate) const MSG_HEIGHT: usize = PANEL_HEIGHT as usize - 1;
+
+// NOTICE: Alternative palettes, colors picked from the Okabe-Ito colorblind-safe set
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteKind {
+    Default,
+    Deuteranopia,
+    Protanopia,
+    HighContrast,
+}
+
+impl PaletteKind {
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            PaletteKind::Default => tr!(locale, "palette_default"),
+            PaletteKind::Deuteranopia => tr!(locale, "palette_deuteranopia"),
+            PaletteKind::Protanopia => tr!(locale, "palette_protanopia"),
+            PaletteKind::HighContrast => tr!(locale, "palette_high_contrast"),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            PaletteKind::Default => PaletteKind::Deuteranopia,
+            PaletteKind::Deuteranopia => PaletteKind::Protanopia,
+            PaletteKind::Protanopia => PaletteKind::HighContrast,
+            PaletteKind::HighContrast => PaletteKind::Default,
+        }
+    }
+}
+
+impl FromStr for PaletteKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "default" => Ok(PaletteKind::Default),
+            "deuteranopia" => Ok(PaletteKind::Deuteranopia),
+            "protanopia" => Ok(PaletteKind::Protanopia),
+            "high_contrast" => Ok(PaletteKind::HighContrast),
+            _ => Err(format!("Unknown palette '{}'", value)),
+        }
+    }
+}
+
+pub(crate) fn hp_percent(game: &Game) -> i32 {
+    game.player.fighter.map_or(100, |fighter| fighter.hp * 100 / cmp::max(fighter.max_hp, 1))
+}
+
+/// Warns once for each of `LOW_HP_WARNINGS` the player's hit points fall under, healing above one rearms it
+pub(crate) fn warn_low_hp(game: &mut Game) {
+    if !game.player.is_alive {
+        return;
+    }
+    let percent = hp_percent(game);
+    let under = LOW_HP_WARNINGS.iter().filter(|&&(threshold, _)| percent < threshold).count();
+    if under > game.hp_warnings {
+        let (_, key) = LOW_HP_WARNINGS[under - 1];
+        game.messages.add(tr!(game.config.locale, key), MessageCategory::Danger);
+    }
+    game.hp_warnings = under;
+}
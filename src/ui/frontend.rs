@@ -0,0 +1,304 @@
+use super::*;
+use crate::combat::{experience_level, level_up_xp, sees_invisible};
+use crate::items::Hunger;
+use crate::map::{COLOR_DARK_GROUND, COLOR_DARK_WALL, COLOR_LIGHT_GROUND, COLOR_LIGHT_WALL, MIN_LIGHT, Terrain, Tile};
+use crate::object::GameObject;
+use crate::portable::colors::*;
+
+// NOTICE: General window settings, the map view sits left of the sidebar and above the panel
+pub(crate) const SCREEN_WIDTH: i32 = 80;
+pub(crate) const BAR_WIDTH: i32 = 20;
+pub(crate) const PANEL_Y: i32 = SCREEN_HEIGHT - PANEL_HEIGHT;
+pub(crate) const SIDEBAR_WIDTH: i32 = 20;
+pub(crate) const VIEW_WIDTH: i32 = SCREEN_WIDTH - SIDEBAR_WIDTH;
+
+// NOTICE: The depth each region of the dungeon starts at, named in the panel and on the banner of a new level
+const REGIONS: [(u32, &str); 4] = [(1, "region_halls"), (4, "region_catacombs"), (7, "region_caverns"), (10, "region_abyss")];
+
+// NOTICE: Panel messages bar
+pub(crate) const MSG_X: i32 = BAR_WIDTH + 2;
+pub(crate) const MSG_WIDTH: i32 = SCREEN_WIDTH - BAR_WIDTH - 2;
+
+pub struct Palette {
+    /// Which palette the options menu cycles on from
+    #[cfg(feature = "window")]
+    pub(crate) kind: PaletteKind,
+    dark_wall: Color,
+    dark_ground: Color,
+    light_wall: Color,
+    light_ground: Color,
+    wall_glyph: Option<char>,
+    floor_glyph: Option<char>,
+    remapped: Vec<(Color, Color)>,
+    /// Terrain backgrounds out of and in view replacing the ones of `Terrain::colors`
+    pub(crate) terrain: Vec<(Terrain, (Color, Color))>,
+}
+
+impl Palette {
+    pub fn new(kind: PaletteKind) -> Self {
+        let orange = Color { r: 230, g: 159, b: 0 };
+        let sky_blue = Color { r: 86, g: 180, b: 233 };
+        let bluish_green = Color { r: 0, g: 158, b: 115 };
+        let yellow = Color { r: 240, g: 228, b: 66 };
+        let blue = Color { r: 0, g: 114, b: 178 };
+        let vermillion = Color { r: 213, g: 94, b: 0 };
+        let reddish_purple = Color { r: 204, g: 121, b: 167 };
+
+        match kind {
+            PaletteKind::Default => Palette {
+                #[cfg(feature = "window")]
+                kind,
+                dark_wall: COLOR_DARK_WALL,
+                dark_ground: COLOR_DARK_GROUND,
+                light_wall: COLOR_LIGHT_WALL,
+                light_ground: COLOR_LIGHT_GROUND,
+                wall_glyph: None,
+                floor_glyph: None,
+                remapped: vec![],
+                terrain: vec![],
+            },
+            PaletteKind::Deuteranopia | PaletteKind::Protanopia => Palette {
+                #[cfg(feature = "window")]
+                kind,
+                dark_wall: Color { r: 40, g: 60, b: 90 },
+                dark_ground: Color { r: 110, g: 110, b: 110 },
+                light_wall: blue,
+                light_ground: Color { r: 200, g: 190, b: 150 },
+                wall_glyph: Some('#'),
+                floor_glyph: Some('.'),
+                remapped: vec![
+                    (DESATURATED_GREEN, sky_blue),
+                    (DARKER_GREEN, if kind == PaletteKind::Protanopia { yellow } else { vermillion }),
+                    (VIOLET, reddish_purple),
+                    (LIGHT_YELLOW, yellow),
+                    (RED, if kind == PaletteKind::Protanopia { orange } else { vermillion }),
+                    (GREEN, bluish_green),
+                    (ORANGE, orange),
+                    (LIGHT_RED, orange),
+                    (DARKER_RED, Color { r: 0, g: 60, b: 110 }),
+                    (DARK_RED, Color { r: 120, g: 120, b: 120 }),
+                ],
+                terrain: vec![
+                    (Terrain::Chasm, (Color { r: 5, g: 5, b: 5 }, Color { r: 30, g: 30, b: 30 })),
+                    (Terrain::DeepWater, (Color { r: 0, g: 40, b: 80 }, blue)),
+                    (Terrain::ClosedDoor, (Color { r: 70, g: 40, b: 60 }, reddish_purple)),
+                    (Terrain::OpenDoor, (Color { r: 70, g: 40, b: 60 }, reddish_purple)),
+                    (Terrain::Rubble, (Color { r: 50, g: 50, b: 50 }, Color { r: 120, g: 120, b: 120 })),
+                    (Terrain::LockedGate, (Color { r: 60, g: 55, b: 20 }, Color { r: 160, g: 150, b: 40 })),
+                    (Terrain::UnlockedGate, (Color { r: 60, g: 55, b: 20 }, Color { r: 160, g: 150, b: 40 })),
+                    (Terrain::GlassWall, (Color { r: 20, g: 60, b: 80 }, sky_blue)),
+                    (Terrain::Campfire, (Color { r: 90, g: 60, b: 0 }, orange)),
+                ],
+            },
+            PaletteKind::HighContrast => Palette {
+                #[cfg(feature = "window")]
+                kind,
+                dark_wall: Color { r: 110, g: 110, b: 110 },
+                dark_ground: Color { r: 30, g: 30, b: 30 },
+                light_wall: WHITE,
+                light_ground: BLACK,
+                wall_glyph: Some('#'),
+                floor_glyph: Some('.'),
+                remapped: vec![
+                    (DESATURATED_GREEN, Color { r: 0, g: 255, b: 0 }),
+                    (DARKER_GREEN, Color { r: 0, g: 255, b: 255 }),
+                    (VIOLET, Color { r: 255, g: 0, b: 255 }),
+                    (LIGHT_YELLOW, Color { r: 255, g: 255, b: 0 }),
+                    (LIGHT_GREY, WHITE),
+                    (DARKER_RED, BLACK),
+                ],
+                terrain: vec![
+                    (Terrain::Chasm, (Color { r: 60, g: 0, b: 60 }, Color { r: 130, g: 0, b: 130 })),
+                    (Terrain::DeepWater, (Color { r: 0, g: 0, b: 120 }, Color { r: 0, g: 0, b: 255 })),
+                    (Terrain::ClosedDoor, (Color { r: 100, g: 60, b: 0 }, Color { r: 200, g: 120, b: 0 })),
+                    (Terrain::OpenDoor, (Color { r: 100, g: 60, b: 0 }, Color { r: 200, g: 120, b: 0 })),
+                    (Terrain::Rubble, (Color { r: 70, g: 70, b: 70 }, Color { r: 150, g: 150, b: 150 })),
+                    (Terrain::LockedGate, (Color { r: 110, g: 110, b: 0 }, Color { r: 255, g: 255, b: 0 })),
+                    (Terrain::UnlockedGate, (Color { r: 110, g: 110, b: 0 }, Color { r: 255, g: 255, b: 0 })),
+                    (Terrain::GlassWall, (Color { r: 0, g: 110, b: 110 }, Color { r: 0, g: 255, b: 255 })),
+                    (Terrain::Campfire, (Color { r: 120, g: 30, b: 0 }, Color { r: 255, g: 80, b: 0 })),
+                ],
+            },
+        }
+    }
+
+    pub fn color(&self, color: Color) -> Color {
+        self.remapped
+            .iter()
+            .find(|&&(from, _)| from == color)
+            .map_or(color, |&(_, to)| to)
+    }
+
+    pub fn glyph_color(&self, background: Color, visible: bool) -> Color {
+        let luminance = 0.299 * background.r as f32 + 0.587 * background.g as f32 + 0.114 * background.b as f32;
+        match (visible, luminance > 128.0) {
+            (true, true) => BLACK,
+            (true, false) => WHITE,
+            (false, _) => GREY,
+        }
+    }
+}
+
+/// Green at full health, yellow at half and red as it runs out
+pub(crate) fn hp_color(hp: i32, max_hp: i32, palette: &Palette) -> Color {
+    let health = if max_hp > 0 { (hp as f32 / max_hp as f32).clamp(0.0, 1.0) } else { 0.0 };
+    if health >= 0.5 {
+        lerp(palette.color(LIGHT_YELLOW), palette.color(LIGHT_GREEN), (health - 0.5) * 2.0)
+    } else {
+        lerp(palette.color(LIGHT_RED), palette.color(LIGHT_YELLOW), health * 2.0)
+    }
+}
+
+/// The player's hunger and status tags along the bottom left of the panel, each with its column and tooltip
+pub(crate) fn status_tags(game: &Game) -> Vec<(i32, String, String, Color)> {
+    let locale = &game.config.locale;
+    let mut x = 1;
+    let mut tags = vec![];
+    let statuses = game.player.statuses.iter().map(|&status| {
+        let (tag, color) = status.tag(locale);
+        (tag, status.description(locale), color)
+    });
+    for (tag, description, color) in Hunger::of(game.satiety).tag(locale).into_iter().chain(statuses) {
+        let width = tag.chars().count() as i32;
+        if x + width > MSG_X - 1 {
+            break;
+        }
+        tags.push((x, description, tag, color));
+        x += width + 1;
+    }
+    tags
+}
+
+/// The bars at the top left of the panel, one for each pool the character has
+pub(crate) fn panel_bars(game: &Game, palette: &Palette) -> Vec<(String, i32, i32, Color)> {
+    let locale = &game.config.locale;
+    let mut bars = vec![];
+    if let Some(fighter) = game.player.fighter {
+        bars.push((tr!(locale, "hp"), fighter.hp, fighter.max_hp, hp_color(fighter.hp, fighter.max_hp, palette)));
+    }
+    if let Some(caster) = game.player.caster {
+        bars.push((tr!(locale, "mp"), caster.mana, caster.max_mana, palette.color(LIGHT_VIOLET)));
+    }
+    if let Some(fighter) = game.player.fighter {
+        let (level, xp) = experience_level(fighter.xp);
+        bars.push((tr!(locale, "xp_level", level = level), xp, level_up_xp(level), palette.color(LIGHT_BLUE)));
+    }
+    bars
+}
+
+/// Background colors of a tile out of and in view
+pub(crate) fn tile_colors(tile: &Tile, palette: &Palette) -> (Color, Color) {
+    match tile.terrain {
+        Terrain::Floor if tile.block_sight => (palette.dark_wall, palette.light_wall),
+        Terrain::Floor => (palette.dark_ground, palette.light_ground),
+        terrain => terrain.colors(palette),
+    }
+}
+
+/// How lit a tile is, from 0 past the reach of every light to 1 right on one
+fn brightness((x, y): (i32, i32), game: &Game) -> f32 {
+    game.lights
+        .iter()
+        .map(|light| {
+            let distance = (((x - light.x).pow(2) + (y - light.y).pow(2)) as f32).sqrt();
+            (1.0 - distance / light.radius).max(0.0)
+        })
+        .fold(0.0, f32::max)
+}
+
+/// The background of a tile as the map draws it, lit or remembered and stained by decals
+pub(crate) fn tile_color((x, y): (i32, i32), game: &Game, palette: &Palette) -> Color {
+    let tile = &game.map[(x, y)];
+    let visible = game.fov.is_in_fov(x, y);
+    let (dark, light) = tile_colors(tile, palette);
+    let color = if !visible {
+        dark
+    } else if game.config.light_falloff {
+        lerp(dark, light, MIN_LIGHT + (1.0 - MIN_LIGHT) * brightness((x, y), game))
+    } else {
+        light
+    };
+    match tile.decal {
+        Some(decal) => lerp(color, decal.color(), if visible { 0.5 } else { 0.3 }),
+        None => color,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct CellView {
+    pub(crate) glyph: char,
+    pub(crate) foreground: Color,
+    pub(crate) background: Color,
+}
+
+pub(crate) const BLANK_CELL: CellView = CellView { glyph: ' ', foreground: BLACK, background: BLACK };
+
+pub(crate) fn tile_view((x, y): (i32, i32), game: &Game, palette: &Palette) -> CellView {
+    if !game.map[(x, y)].explored {
+        return BLANK_CELL;
+    }
+    let visible = game.fov.is_in_fov(x, y);
+    let terrain = game.map[(x, y)].terrain;
+    let color = tile_color((x, y), game, palette);
+    let glyph = if terrain != Terrain::Floor {
+        terrain.glyph()
+    } else if game.map[(x, y)].block_sight {
+        palette.wall_glyph
+    } else if visible {
+        palette.floor_glyph
+    } else {
+        None
+    };
+    match glyph {
+        Some(glyph) => CellView { glyph, foreground: palette.glyph_color(color, visible), background: color },
+        None => CellView { glyph: ' ', foreground: color, background: color },
+    }
+}
+
+/// The monsters the player can see, invisible ones only with see invisible
+pub(crate) fn visible_enemies<'a>(game: &Game, game_objects: &'a [GameObject]) -> Vec<&'a GameObject> {
+    let see_invisible = sees_invisible(game);
+    game_objects
+        .iter()
+        .filter(|game_object| {
+            game_object.fighter.is_some()
+                && game_object.ai.is_some()
+                && (!game_object.invisible || see_invisible)
+                && game.fov.is_in_fov(game_object.x, game_object.y)
+        })
+        .collect()
+}
+
+/// The monsters in view, the closest first
+pub(crate) fn sidebar_enemies<'a>(game: &Game, game_objects: &'a [GameObject]) -> Vec<&'a GameObject> {
+    let (player_x, player_y) = game.player.position();
+    let mut enemies = visible_enemies(game, game_objects);
+    enemies.sort_by_key(|enemy| cmp::max((enemy.x - player_x).abs(), (enemy.y - player_y).abs()));
+    enemies
+}
+
+pub(crate) fn region_name(game: &Game) -> String {
+    let key = if game.tutorial.is_some() {
+        "region_tutorial"
+    } else {
+        REGIONS.iter().rev().find(|&&(depth, _)| game.dungeon_level >= depth).map_or(REGIONS[0].1, |&(_, key)| key)
+    };
+    tr!(game.config.locale, key)
+}
+
+/// "Depth: 3 (Catacombs)" for the panel
+pub(crate) fn location(game: &Game) -> String {
+    tr!(game.config.locale, "dungeon_level", level = game.dungeon_level, region = region_name(game))
+}
+
+/// A menu line, options keep the number they are picked with whatever line they are shown on
+pub(crate) enum MenuRow {
+    Heading(String),
+    Option(usize, String),
+}
+
+pub(crate) const MAX_NAME_LENGTH: usize = 20;
+
+pub(crate) fn is_valid_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == ' ' || c == '-' || c == '_'
+}
@@ -0,0 +1,1318 @@
+use super::*;
+use tcod::console::*;
+use tcod::input::{self, Event, Key, Mouse};
+use std::{fs, iter, thread};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::activity::MAX_NOTE_LENGTH;
+use crate::audio::Audio;
+use crate::character::{Perk, Skill};
+use crate::combat::{Ai, Fighter, sees_invisible};
+use crate::config::{OnHit, WindowMode};
+use crate::controls::{
+    Gamepad, HeldKey, MovementKeys, PAD_BINDINGS, PadInput, SKIP_TUTORIAL_KEY, SafetyPrompt, WIZARD_BINDINGS,
+    key_bindings,
+};
+use crate::game::GameOver;
+use crate::items::{
+    Gem, QUICKSLOT_COUNT, Slot, carried_weight, carry_capacity, equipped_weapon, gem_bonus, quickslot_item,
+    worn_defense_bonus,
+};
+use crate::map::{MAP_HEIGHT, map_size, mouse_tile, view_x};
+use crate::menu::{TitleScreen, options_menu, render_title_screen};
+use crate::narration::Narrator;
+use crate::net::{Broadcast, CoopLink, Pending, StateExport, submission_status};
+use crate::object::GameObject;
+use crate::portable::KeyCode;
+use crate::portable::colors::*;
+use crate::save::{
+    RECAP_GRAPH_HEIGHT, Replay, autosave, delete_slot, record_daily_score, render_sparkline, replay_viewer,
+};
+use crate::tutorial::render_tutorial;
+
+const TOOLTIP_WIDTH: i32 = 28;
+const COMPARE_PANEL_WIDTH: i32 = 24;
+const QUICKSLOT_WIDTH: i32 = 8;
+// NOTICE: Animations run on the clock rather than on frames, so they look the same at any frame rate
+const SHAKE_STEP_MS: u128 = 40;
+const LOW_HP_FLASH_MS: u128 = 400;
+const BANNER_MS: u64 = 2000;
+const BANNER_FADE_MS: u64 = 500;
+
+pub(crate) const MENU_POLL_MS: u64 = 15;
+
+pub(crate) struct Tcod {
+    pub(crate) root: Root,
+    pub(crate) con: Offscreen,
+    pub(crate) panel: Offscreen,
+    pub(crate) key: Key,
+    /// Key presses read but not handled yet, `key` takes one each frame
+    pub(crate) input_queue: VecDeque<Key>,
+    pub(crate) held_key: Option<HeldKey>,
+    pub(crate) key_repeat_delay: Duration,
+    pub(crate) key_repeat_interval: Duration,
+    pub(crate) movement_keys: MovementKeys,
+    pub(crate) mouse: Mouse,
+    pub(crate) narrator: Option<Box<dyn Narrator>>,
+    pub(crate) palette: Palette,
+    pub(crate) audio: Audio,
+    pub(crate) gamepad: Option<Gamepad>,
+    pub(crate) health_bars: bool,
+    pub(crate) skipped_prompts: Vec<SafetyPrompt>,
+    /// The font, scale and mode the window was opened with, see `init_root`
+    pub(crate) font: String,
+    pub(crate) render_scale: i32,
+    pub(crate) window_mode: WindowMode,
+    /// Where Alt+Enter goes from a window, the fullscreen mode picked last
+    pub(crate) fullscreen_mode: WindowMode,
+    /// Screen shake, panel flashes and projectile animations, off for players sensitive to motion
+    pub(crate) screen_motion: bool,
+    /// When the map stops shaking
+    pub(crate) shake_end: Option<Instant>,
+    /// The lines of the banner shown on entering a level, and when it goes away
+    pub(crate) banner: Option<(Vec<String>, Instant)>,
+    /// When travel or rest last took a step
+    pub(crate) activity_stepped: Instant,
+    pub(crate) fps_limit: i32,
+    pub(crate) vsync: bool,
+    pub(crate) debug_overlay: bool,
+    /// How long the previous frame took to render and flush, shown by the debug overlay
+    pub(crate) render_time: Duration,
+    /// What each map cell showed on the last frame, None once it must be drawn again
+    pub(crate) map_cells: Vec<Option<CellView>>,
+    /// Cells drawn over by objects, notes or health bars, redrawn on the next frame
+    pub(crate) overlay_cells: Vec<(i32, i32)>,
+    pub(crate) tiles_drawn: usize,
+    pub(crate) console_log: Vec<String>,
+    /// Drawn behind every menu frame while the main menu is up
+    pub(crate) title_screen: Option<TitleScreen>,
+    /// The connection to the partner's client while hosting a co-op game
+    pub(crate) coop: Option<CoopLink>,
+    pub(crate) broadcast: Option<Broadcast>,
+    pub(crate) state_export: Option<StateExport>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_bar<C: Console>(
+    panel: &mut C,
+    x: i32,
+    y: i32,
+    total_width: i32,
+    name: &str,
+    value: i32,
+    maximum: i32,
+    bar_color: Color,
+    back_color: Color,
+) {
+    let bar_width = (value as f32 / maximum as f32 * total_width as f32) as i32;
+    panel.set_default_background(back_color);
+    panel.rect(x, y, total_width, 1, false, BackgroundFlag::Screen);
+
+    panel.set_default_background(bar_color);
+    if bar_width > 0 {
+        panel.rect(x, y, bar_width, 1, false, BackgroundFlag::Screen);
+    }
+
+    panel.set_default_foreground(WHITE);
+    panel.print_ex(
+        x + total_width / 2,
+        y,
+        BackgroundFlag::None,
+        TextAlignment::Center,
+        format!("{}: {}/{}", name, value, maximum),
+    );
+}
+
+/// Whether the panel border shows red, it blinks unless screen motion is off
+fn low_hp_flash(tcod: &Tcod, game: &Game) -> bool {
+    let (lowest, _) = LOW_HP_WARNINGS[LOW_HP_WARNINGS.len() - 1];
+    let blink_on = (tcod::system::get_elapsed_time().as_millis() / LOW_HP_FLASH_MS).is_multiple_of(2);
+    game.player.is_alive && hp_percent(game) < lowest && (blink_on || !tcod.screen_motion)
+}
+
+// NOTICE: Only map cells whose view changed since the last frame are drawn, or the ones an overlay covered
+pub(crate) fn render_all(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let (map_width, map_height) = map_size(&game.map);
+    let cell_count = (map_width * map_height) as usize;
+    if tcod.map_cells.len() != cell_count {
+        tcod.map_cells = vec![None; cell_count];
+    }
+    for (x, y) in tcod.overlay_cells.drain(..) {
+        if x >= 0 && y >= 0 && x < map_width && y < map_height {
+            tcod.map_cells[(y * map_width + x) as usize] = None;
+        }
+    }
+
+    tcod.tiles_drawn = 0;
+    for y in 0..map_height {
+        for x in 0..map_width {
+            let view = tile_view((x, y), game, &tcod.palette);
+            let cell = &mut tcod.map_cells[(y * map_width + x) as usize];
+            if *cell != Some(view) {
+                *cell = Some(view);
+                tcod.con.put_char_ex(x, y, view.glyph, view.foreground, view.background);
+                tcod.tiles_drawn += 1;
+            }
+        }
+    }
+
+    let see_invisible = sees_invisible(game);
+    let mut to_draw: Vec<_> = iter::once(&game.player)
+        .chain(game.partner.iter())
+        .chain(game_objects.iter())
+        .filter(|go| {
+            game.fov.is_in_fov(go.x, go.y)
+                || (go.always_visible && game.map[(go.x, go.y)].explored)
+        })
+        .collect();
+    to_draw.sort_by_key(|o| o.blocks);
+
+    for note in &game.notes {
+        tcod.con.set_char_background(note.x, note.y, tcod.palette.color(DARK_YELLOW), BackgroundFlag::Set);
+        tcod.overlay_cells.push((note.x, note.y));
+    }
+
+    for memory in game.remembered.iter().filter(|memory| !game.fov.is_in_fov(memory.x, memory.y)) {
+        let color = lerp(tcod.palette.color(memory.color), BLACK, 0.5);
+        tcod.con.set_default_foreground(color);
+        tcod.con.put_char(memory.x, memory.y, memory.char, BackgroundFlag::None);
+        tcod.overlay_cells.push((memory.x, memory.y));
+    }
+
+    for game_object in &to_draw {
+        tcod.overlay_cells.push(game_object.position());
+        if !game_object.invisible || see_invisible {
+            game_object.draw(&mut tcod.con, &tcod.palette);
+        } else if game_object.last_attack_turn == Some(game.turn) {
+            tcod.con.set_default_foreground(tcod.palette.color(DARK_GREY));
+            tcod.con.put_char(game_object.x, game_object.y, '?', BackgroundFlag::None);
+        }
+    }
+
+    if tcod.health_bars {
+        for game_object in to_draw.iter().filter(|go| go.ai.is_some() && (!go.invisible || see_invisible)) {
+            if let Some(fighter) = game_object.fighter.filter(|fighter| fighter.hp < fighter.max_hp) {
+                render_health_bar(&mut tcod.con, game_object.position(), fighter, &tcod.palette, &mut tcod.overlay_cells);
+            }
+        }
+    }
+
+    tcod.root.set_default_foreground(WHITE);
+    if let Some(fighter) = game.player.fighter {
+        tcod.root.print_ex(
+            1,
+            SCREEN_HEIGHT - 2,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            tr!(game.config.locale, "hp_status", hp = fighter.hp, max_hp = fighter.max_hp),
+        );
+    }
+
+    let mut y = MSG_HEIGHT as i32;
+    for message in game.messages.iter().rev() {
+        let msg = message.text();
+        let msg_height = tcod.panel.get_height_rect(MSG_X, y, MSG_WIDTH, 0, &msg);
+        y -= msg_height;
+        if y < 0 {
+            break;
+        }
+        tcod.panel.set_default_foreground(tcod.palette.color(message.category.color()));
+        tcod.panel.print_rect(MSG_X, y, MSG_WIDTH, 0, msg);
+    }
+
+    let shake_left = tcod.shake_end.and_then(|end| end.checked_duration_since(Instant::now()));
+    if shake_left.is_none() {
+        tcod.shake_end = None;
+    }
+    let shake_offset = match shake_left {
+        Some(left) if (left.as_millis() / SHAKE_STEP_MS).is_multiple_of(2) => (1, 0),
+        Some(_) => (-1, 1),
+        None => (0, 0),
+    };
+    if shake_offset != (0, 0) {
+        tcod.root.set_default_background(BLACK);
+        tcod.root.rect(0, 0, VIEW_WIDTH, MAP_HEIGHT, true, BackgroundFlag::Set);
+    }
+    blit(
+        &tcod.con,
+        (view_x(game), 0),
+        (VIEW_WIDTH, MAP_HEIGHT),
+        &mut tcod.root,
+        shake_offset,
+        1.0,
+        1.0,
+    );
+
+    render_tutorial(tcod, game, game_objects);
+    render_tooltip(tcod, game, game_objects);
+
+    let bars = panel_bars(game, &tcod.palette);
+    for (row, (name, value, maximum, color)) in bars.iter().enumerate() {
+        render_bar(&mut tcod.panel, 1, 1 + row as i32, BAR_WIDTH, name, *value, *maximum, *color, lerp(*color, BLACK, 0.6));
+    }
+    let mut y = 1 + bars.len() as i32;
+
+    let weight = carried_weight(&game.inventory, &game.config);
+    let capacity = carry_capacity(&game.player);
+    let (load_color, load_key) = if weight > capacity {
+        (ORANGE, "load_encumbered")
+    } else {
+        (WHITE, "load")
+    };
+    tcod.panel.set_default_foreground(tcod.palette.color(load_color));
+    tcod.panel.print_ex(
+        1,
+        y,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        tr!(game.config.locale, load_key, weight = weight, capacity = capacity),
+    );
+    tcod.panel.set_default_foreground(WHITE);
+    y += 1;
+
+    tcod.panel.print_ex(
+        1,
+        y,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        match game.arena {
+            Some(ref arena) => tr!(game.config.locale, "arena_panel", wave = arena.wave),
+            None => location(game),
+        },
+    );
+
+    y += 1;
+
+    // NOTICE: A caster's three bars leave no room for the seed above the status tags
+    if game.config.wizard && y < PANEL_HEIGHT - 1 {
+        tcod.panel.print_ex(
+            1,
+            y,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            tr!(game.config.locale, "wizard_seed", seed = game.seed),
+        );
+    }
+
+    for slot in 0..QUICKSLOT_COUNT {
+        let x = MSG_X + slot as i32 * QUICKSLOT_WIDTH;
+        let (color, text) = match game.quickslots[slot].and_then(|item| quickslot_item(slot, game).map(|index| (item, index))) {
+            Some((item, index)) => {
+                let count = game.inventory.iter().filter(|game_object| game_object.item == Some(item)).count();
+                (game.inventory[index].color, format!("{}:{} x{}", slot + 1, game.inventory[index].char, count))
+            }
+            None => (DARK_GREY, format!("{}:-", slot + 1)),
+        };
+        tcod.panel.set_default_foreground(tcod.palette.color(color));
+        tcod.panel.print_ex(x, PANEL_HEIGHT - 1, BackgroundFlag::None, TextAlignment::Left, text);
+    }
+
+    // NOTICE: Hovering a status tag tells its turns left where the names under the mouse go
+    let (mouse_x, mouse_y) = (tcod.mouse.cx as i32, tcod.mouse.cy as i32);
+    let mut hovered_status = None;
+    for (x, description, tag, color) in status_tags(game) {
+        tcod.panel.set_default_foreground(tcod.palette.color(color));
+        tcod.panel.print_ex(x, PANEL_HEIGHT - 1, BackgroundFlag::None, TextAlignment::Left, &tag);
+        if mouse_y == PANEL_Y + PANEL_HEIGHT - 1 && (x..x + tag.chars().count() as i32).contains(&mouse_x) {
+            hovered_status = Some(description);
+        }
+    }
+
+    tcod.panel.set_default_foreground(tcod.palette.color(LIGHT_GREY));
+    tcod.panel.print_ex(
+        1,
+        0,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        match hovered_status {
+            Some(description) => description,
+            None => get_names_under_mouse(tcod.mouse, game, game_objects),
+        },
+    );
+
+    if low_hp_flash(tcod, game) {
+        let border = tcod.palette.color(DARK_RED);
+        for x in 0..SCREEN_WIDTH {
+            tcod.panel.set_char_background(x, 0, border, BackgroundFlag::Set);
+            tcod.panel.set_char_background(x, PANEL_HEIGHT - 1, border, BackgroundFlag::Set);
+        }
+        for y in 0..PANEL_HEIGHT {
+            tcod.panel.set_char_background(0, y, border, BackgroundFlag::Set);
+            tcod.panel.set_char_background(SCREEN_WIDTH - 1, y, border, BackgroundFlag::Set);
+        }
+    }
+
+    blit(
+        &tcod.panel,
+        (0, 0),
+        (SCREEN_WIDTH, PANEL_HEIGHT),
+        &mut tcod.root,
+        (0, PANEL_Y),
+        1.0,
+        1.0,
+    );
+
+    render_sidebar(tcod, game, game_objects);
+    render_banner(tcod);
+    if tcod.debug_overlay {
+        render_debug_overlay(tcod, game, game_objects);
+    }
+}
+
+// NOTICE: Right of the map, each monster in view takes a line for its glyph & name and one for its hit points
+fn render_sidebar(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let locale = &game.config.locale;
+    let x = VIEW_WIDTH;
+    tcod.root.set_default_background(BLACK);
+    tcod.root.rect(x, 0, SIDEBAR_WIDTH, MAP_HEIGHT, true, BackgroundFlag::Set);
+    tcod.root.set_default_foreground(tcod.palette.color(LIGHT_GREY));
+    tcod.root.print_ex(x + 1, 0, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "sidebar_header"));
+    let enemies = sidebar_enemies(game, game_objects);
+    if enemies.is_empty() {
+        tcod.root.set_default_foreground(tcod.palette.color(DARK_GREY));
+        tcod.root.print_rect(x + 1, 2, SIDEBAR_WIDTH - 2, 0, tr!(locale, "sidebar_empty"));
+    }
+    for (index, enemy) in enemies.iter().enumerate() {
+        let y = 2 + index as i32 * 3;
+        if y + 1 >= MAP_HEIGHT {
+            break;
+        }
+        tcod.root.set_default_foreground(tcod.palette.color(enemy.color));
+        tcod.root.put_char(x + 1, y, enemy.char, BackgroundFlag::None);
+        tcod.root.set_default_foreground(WHITE);
+        let name: String = enemy.display_name(locale).chars().take((SIDEBAR_WIDTH - 4) as usize).collect();
+        tcod.root.print_ex(x + 3, y, BackgroundFlag::None, TextAlignment::Left, name);
+        if let Some(fighter) = enemy.fighter {
+            let color = hp_color(fighter.hp, fighter.max_hp, &tcod.palette);
+            let name = tr!(locale, "hp");
+            render_bar(&mut tcod.root, x + 1, y + 1, SIDEBAR_WIDTH - 2, &name, fighter.hp, fighter.max_hp, color, lerp(color, BLACK, 0.6));
+        }
+    }
+}
+
+pub(crate) fn show_level_banner(tcod: &mut Tcod, game: &Game) {
+    if game.arena.is_some() {
+        return;
+    }
+    let lines = vec![tr!(game.config.locale, "banner_depth", level = game.dungeon_level), region_name(game)];
+    tcod.banner = Some((lines, Instant::now() + Duration::from_millis(BANNER_MS)));
+}
+
+// NOTICE: A framed box in the middle of the map, fading out over its last moments
+fn render_banner(tcod: &mut Tcod) {
+    let (lines, end) = match tcod.banner {
+        Some((ref lines, end)) => (lines, end),
+        None => return,
+    };
+    let left = match end.checked_duration_since(Instant::now()) {
+        Some(left) => left,
+        None => {
+            tcod.banner = None;
+            return;
+        }
+    };
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as i32 + 8;
+    let height = lines.len() as i32 + 4;
+    let mut window = Offscreen::new(width, height);
+    window.set_default_foreground(tcod.palette.color(LIGHT_YELLOW));
+    window.print_frame(0, 0, width, height, true, BackgroundFlag::Set, None::<&str>);
+    for (index, line) in lines.iter().enumerate() {
+        window.print_ex(width / 2, 2 + index as i32, BackgroundFlag::None, TextAlignment::Center, line);
+    }
+    let alpha = (left.as_millis() as f32 / BANNER_FADE_MS as f32).min(1.0);
+    blit(&window, (0, 0), (width, height), &mut tcod.root, ((VIEW_WIDTH - width) / 2, (MAP_HEIGHT - height) / 2), alpha, alpha);
+}
+
+fn render_debug_overlay(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let locale = &game.config.locale;
+    let lines = [
+        tr!(locale, "debug_fps", fps = tcod::system::get_fps()),
+        tr!(locale, "debug_entities", count = game_objects.len() + 1),
+        tr!(locale, "debug_turn", turn = game.turn),
+        tr!(locale, "debug_fov", count = game.metrics.fov_recomputes),
+        tr!(locale, "debug_render", ms = format!("{:.2}", tcod.render_time.as_secs_f64() * 1000.0)),
+        tr!(locale, "debug_tiles", count = tcod.tiles_drawn),
+        tr!(locale, "debug_ai", ms = format!("{:.2}", game.metrics.ai_time.as_secs_f64() * 1000.0)),
+    ];
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as i32 + 2;
+    let (x, y) = (VIEW_WIDTH - width, 1);
+    tcod.root.set_default_background(BLACK);
+    tcod.root.rect(x, y, width, lines.len() as i32, true, BackgroundFlag::Set);
+    tcod.root.set_default_foreground(LIGHT_GREEN);
+    for (index, line) in lines.iter().enumerate() {
+        tcod.root.print_ex(x + 1, y + index as i32, BackgroundFlag::None, TextAlignment::Left, line);
+    }
+}
+
+// NOTICE: Three tiles wide, drawn as backgrounds above the monster, or below it on the top row
+fn render_health_bar(con: &mut Offscreen, (x, y): (i32, i32), fighter: Fighter, palette: &Palette, overlay_cells: &mut Vec<(i32, i32)>) {
+    let bar_y = if y > 0 { y - 1 } else { y + 1 };
+    let filled = (3 * fighter.hp.max(0) + fighter.max_hp - 1) / fighter.max_hp;
+    for dx in -1..=1 {
+        let bar_x = x + dx;
+        if bar_x < 0 || bar_x >= con.width() || bar_y >= con.height() {
+            continue;
+        }
+        let color = if dx + 1 < filled { LIGHT_RED } else { DARKER_RED };
+        con.set_char_background(bar_x, bar_y, palette.color(color), BackgroundFlag::Set);
+        overlay_cells.push((bar_x, bar_y));
+    }
+}
+
+fn render_tooltip(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let (x, y) = match mouse_tile(tcod.mouse, game) {
+        Some(tile) => tile,
+        None => return,
+    };
+    let (map_width, map_height) = map_size(&game.map);
+    if x >= map_width || y >= map_height {
+        return;
+    }
+
+    // NOTICE: Fighters win over items lying under them, same as drawing order
+    let see_invisible = sees_invisible(game);
+    let hovered = iter::once(&game.player)
+        .chain(game.partner.iter())
+        .chain(game_objects.iter())
+        .filter(|game_object| {
+            game_object.position() == (x, y)
+                && (!game_object.invisible || see_invisible)
+                && (game.fov.is_in_fov(x, y) || (game_object.always_visible && game.map[(x, y)].explored))
+        })
+        .max_by_key(|game_object| game_object.fighter.is_some());
+    let hovered = match hovered {
+        Some(game_object) => game_object,
+        None => return,
+    };
+
+    let locale = &game.config.locale;
+    let mut lines = vec![];
+    if let Some(fighter) = hovered.fighter {
+        lines.push(tr!(locale, "tooltip_stats", power = fighter.power, defense = fighter.defense));
+    }
+    if let Some(equipment) = hovered.equipment.filter(|equipment| equipment.slot != Slot::Ring) {
+        lines.push(equipment.rarity.name(locale));
+    }
+    if let Some(equipment) = hovered.equipment.filter(|equipment| equipment.slot == Slot::Armor) {
+        lines.push(tr!(locale, "tooltip_defense_bonus", bonus = equipment.effective_defense_bonus()));
+        if equipment.max_durability > 0 {
+            lines.push(tr!(locale, "tooltip_durability", durability = equipment.durability, max_durability = equipment.max_durability));
+        }
+    }
+    if let Some(equipment) = hovered.equipment.filter(|equipment| equipment.slot == Slot::Weapon) {
+        lines.push(tr!(locale, "tooltip_power_bonus", bonus = equipment.effective_power_bonus()));
+        if equipment.max_durability > 0 {
+            lines.push(tr!(locale, "tooltip_durability", durability = equipment.durability, max_durability = equipment.max_durability));
+        }
+        if let Some(on_hit) = equipment.on_hit {
+            let key = match on_hit {
+                OnHit::Flaming => "on_hit_flaming",
+                OnHit::Vampiric => "on_hit_vampiric",
+                OnHit::Knockback => "on_hit_knockback",
+            };
+            lines.push(tr!(locale, key));
+        }
+    }
+    if hovered.item.is_some() {
+        lines.push(tr!(locale, "tooltip_weight", weight = hovered.weight(&game.config)));
+    }
+    if hovered.carries_key && hovered.fighter.is_some() {
+        lines.push(tr!(locale, "tooltip_carries_key"));
+    }
+    if hovered.movement.flying {
+        lines.push(tr!(locale, "tooltip_flying"));
+    } else if hovered.movement.swimming {
+        lines.push(tr!(locale, "tooltip_swimming"));
+    }
+    if let Some(caster) = hovered.caster {
+        lines.push(tr!(locale, "tooltip_mana", mana = caster.mana, max_mana = caster.max_mana));
+    }
+    lines.extend(hovered.statuses.iter().map(|status| status.description(locale)));
+    match hovered.ai {
+        Some(Ai::Confused { num_turns, .. }) => lines.push(tr!(locale, "tooltip_confused", turns = num_turns + 1)),
+        Some(Ai::Frozen { num_turns, .. }) => lines.push(tr!(locale, "tooltip_frozen", turns = num_turns + 1)),
+        _ => {}
+    }
+    let description = hovered.description.translate(locale);
+    if !description.is_empty() {
+        lines.push(description);
+    }
+    if let Some(equipment) = hovered.equipment {
+        lines.extend(equipment.socket_lines(locale));
+    }
+    let text = lines.join("\n");
+
+    let threat = Threat::of(hovered, game);
+    let threat_height = if threat.is_some() { 1 } else { 0 };
+    let bar_height = if hovered.fighter.is_some() { 1 } else { 0 };
+    let text_height = if text.is_empty() {
+        0
+    } else {
+        tcod.root.get_height_rect(0, 0, TOOLTIP_WIDTH - 2, SCREEN_HEIGHT, &text)
+    };
+    let height = 1 + threat_height + bar_height + text_height;
+
+    let mut window = Offscreen::new(TOOLTIP_WIDTH, height);
+    window.set_default_background(DARKEST_GREY);
+    window.clear();
+    window.set_default_foreground(tcod.palette.color(hovered.color));
+    window.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, hovered.display_name(&game.config.locale));
+    if let Some(threat) = threat {
+        window.set_default_foreground(tcod.palette.color(threat.color()));
+        window.print_ex(1, 1, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "tooltip_threat", threat = threat.name(locale)));
+    }
+    if let Some(fighter) = hovered.fighter {
+        let hp_color = hp_color(fighter.hp, fighter.max_hp, &tcod.palette);
+        render_bar(
+            &mut window,
+            1,
+            1 + threat_height,
+            TOOLTIP_WIDTH - 2,
+            &tr!(locale, "hp"),
+            fighter.hp,
+            fighter.max_hp,
+            hp_color,
+            lerp(hp_color, BLACK, 0.6),
+        );
+    }
+    window.set_default_foreground(WHITE);
+    window.print_rect(1, 1 + threat_height + bar_height, TOOLTIP_WIDTH - 2, text_height, &text);
+
+    let tooltip_x = cmp::min(x + 1, SCREEN_WIDTH - TOOLTIP_WIDTH);
+    let tooltip_y = cmp::min(y + 1, PANEL_Y - height);
+    blit(&window, (0, 0), (TOOLTIP_WIDTH, height), &mut tcod.root, (tooltip_x, tooltip_y), 1.0, 0.85);
+}
+
+// NOTICE: Threat compares how many blows each side needs to kill the other, assuming every blow lands
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Threat {
+    Trivial,
+    Dangerous,
+    Deadly,
+}
+
+impl Threat {
+    pub fn of(monster: &GameObject, game: &Game) -> Option<Threat> {
+        let player = &game.player;
+        let (monster_fighter, player_fighter) = match (monster.fighter, player.fighter) {
+            (Some(monster_fighter), Some(player_fighter)) if monster.ai.is_some() => (monster_fighter, player_fighter),
+            _ => return None,
+        };
+        let brawler = if player.has_perk(Perk::Brawler) { 1 } else { 0 };
+        let thick_skin = if player.has_perk(Perk::ThickSkin) { 1 } else { 0 };
+        let player_power = player_fighter.power
+            + equipped_weapon(&game.inventory).map_or(0, |weapon| weapon.effective_power_bonus())
+            + game.skills.level(Skill::Melee)
+            + brawler
+            + gem_bonus(&game.inventory, Gem::Onyx);
+        let player_defense = player_fighter.defense + worn_defense_bonus(&game.inventory) + thick_skin;
+
+        let dealt = player_power - monster_fighter.defense;
+        let taken = monster_fighter.power - player_defense;
+        if taken <= 0 {
+            return Some(Threat::Trivial);
+        }
+        if dealt <= 0 {
+            return Some(Threat::Deadly);
+        }
+        let blows_to_kill = (monster_fighter.hp + dealt - 1) / dealt;
+        let blows_to_die = (player_fighter.hp + taken - 1) / taken;
+        Some(if blows_to_die <= blows_to_kill {
+            Threat::Deadly
+        } else if blows_to_die <= blows_to_kill * 3 {
+            Threat::Dangerous
+        } else {
+            Threat::Trivial
+        })
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Threat::Trivial => LIGHT_GREEN,
+            Threat::Dangerous => ORANGE,
+            Threat::Deadly => LIGHT_RED,
+        }
+    }
+
+    pub fn name(self, locale: &Locale) -> String {
+        match self {
+            Threat::Trivial => tr!(locale, "threat_trivial"),
+            Threat::Dangerous => tr!(locale, "threat_dangerous"),
+            Threat::Deadly => tr!(locale, "threat_deadly"),
+        }
+    }
+}
+
+fn get_names_under_mouse(mouse: Mouse, game: &Game, game_objects: &[GameObject]) -> String {
+    let tile = mouse_tile(mouse, game);
+    let see_invisible = sees_invisible(game);
+
+    let names = iter::once(&game.player)
+        .chain(game.partner.iter())
+        .chain(game_objects.iter())
+        .filter(|game_object| Some(game_object.position()) == tile && game.fov.is_in_fov(game_object.x, game_object.y))
+        .filter(|game_object| !game_object.invisible || see_invisible)
+        .map(|obj| obj.display_name(&game.config.locale))
+        .collect::<Vec<_>>();
+
+    names.join(", ")
+}
+
+pub(crate) fn message_log(tcod: &mut Tcod, game: &Game) {
+    let locale = &game.config.locale;
+    let width = SCREEN_WIDTH - 10;
+    let height = SCREEN_HEIGHT - 6;
+    let visible_lines = (height - 5) as usize;
+    let mut shown = [true; 4];
+    // NOTICE: Offset counts lines up from the most recent message
+    let mut offset = 0;
+    let mut search = String::new();
+    // NOTICE: Index in the filtered messages of the match last jumped to
+    let mut current_match: Option<usize> = None;
+
+    while !tcod.root.window_closed() {
+        let messages: Vec<_> = game
+            .messages
+            .iter()
+            .filter(|message| {
+                MessageCategory::ALL
+                    .iter()
+                    .position(|&category| category == message.category)
+                    .is_some_and(|index| shown[index])
+            })
+            .collect();
+        let max_offset = messages.len().saturating_sub(visible_lines);
+        offset = cmp::min(offset, max_offset);
+        let query = search.to_lowercase();
+        let matches: Vec<usize> = if query.is_empty() {
+            vec![]
+        } else {
+            (0..messages.len()).filter(|&index| messages[index].text().to_lowercase().contains(&query)).collect()
+        };
+
+        let mut window = Offscreen::new(width, height);
+        let filters: Vec<String> = MessageCategory::ALL
+            .iter()
+            .enumerate()
+            .map(|(index, category)| {
+                let mark = if shown[index] { 'x' } else { ' ' };
+                format!("({}) [{}] {}", index + 1, mark, category.name(locale))
+            })
+            .collect();
+        window.set_default_foreground(WHITE);
+        window.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "message_log_title"));
+        window.print_ex(1, 1, BackgroundFlag::None, TextAlignment::Left, filters.join("  "));
+        if !search.is_empty() {
+            window.set_default_foreground(LIGHT_YELLOW);
+            let status = tr!(locale, "message_log_search", search = search, matches = matches.len());
+            window.print_ex(1, 2, BackgroundFlag::None, TextAlignment::Left, status);
+        }
+
+        let first = messages.len().saturating_sub(visible_lines + offset);
+        for (index, message) in messages.iter().enumerate().skip(first).take(visible_lines) {
+            let line = 3 + (index - first) as i32;
+            if matches.contains(&index) {
+                let background = if current_match == Some(index) { DARK_BLUE } else { DARKER_BLUE };
+                window.set_default_background(background);
+                window.rect(0, line, width, 1, false, BackgroundFlag::Set);
+            }
+            window.set_default_foreground(tcod.palette.color(message.category.color()));
+            window.print_ex(1, line, BackgroundFlag::None, TextAlignment::Left, message.text());
+        }
+        window.set_default_foreground(LIGHT_GREY);
+        window.print_ex(width / 2, height - 2, BackgroundFlag::None, TextAlignment::Center, tr!(locale, "message_log_footer"));
+        window.print_ex(width / 2, height - 1, BackgroundFlag::None, TextAlignment::Center, tr!(locale, "message_log_search_footer"));
+
+        blit(&window, (0, 0), (width, height), &mut tcod.root, (5, 3), 1.0, 0.9);
+        tcod.root.flush();
+
+        let scroll = if let Some((_, Event::Key(key))) = input::check_for_event(input::KEY_PRESS) {
+            match (key.code, key.printable.to_digit(10)) {
+                (KeyCode::Up, _) => 1,
+                (KeyCode::Down, _) => -1,
+                (KeyCode::PageUp, _) => visible_lines as i32,
+                (KeyCode::PageDown, _) => -(visible_lines as i32),
+                (_, Some(digit)) if digit >= 1 && digit as usize <= shown.len() => {
+                    shown[digit as usize - 1] = !shown[digit as usize - 1];
+                    current_match = None;
+                    0
+                }
+                (KeyCode::NoKey, _) => 0,
+                _ => match key.printable {
+                    // NOTICE: Shortcuts to show only one category, or all of them again
+                    'c' | 'i' | 'a' => {
+                        let only = match key.printable {
+                            'c' => Some(MessageCategory::Combat),
+                            'i' => Some(MessageCategory::Item),
+                            _ => None,
+                        };
+                        for (index, &category) in MessageCategory::ALL.iter().enumerate() {
+                            shown[index] = only.is_none_or(|only| only == category);
+                        }
+                        current_match = None;
+                        0
+                    }
+                    '/' => {
+                        search = text_input(&tr!(locale, "message_log_search_header"), MAX_NOTE_LENGTH, 40, &mut tcod.root)
+                            .unwrap_or_default();
+                        current_match = None;
+                        0
+                    }
+                    'n' | 'N' if !matches.is_empty() => {
+                        // NOTICE: n goes back in time to older matches, N forward to newer ones
+                        let next = if key.printable == 'n' {
+                            current_match
+                                .and_then(|current| matches.iter().rev().find(|&&index| index < current))
+                                .or_else(|| matches.last())
+                        } else {
+                            current_match
+                                .and_then(|current| matches.iter().find(|&&index| index > current))
+                                .or_else(|| matches.first())
+                        };
+                        current_match = next.copied();
+                        if let Some(index) = current_match {
+                            offset = (messages.len() - 1 - index).saturating_sub(visible_lines / 2);
+                        }
+                        0
+                    }
+                    _ => return,
+                },
+            }
+        } else {
+            match tcod.gamepad.as_mut().and_then(Gamepad::poll) {
+                Some(PadInput::Direction(_, dy)) => -dy,
+                Some(_) => return,
+                None => {
+                    thread::sleep(Duration::from_millis(MENU_POLL_MS));
+                    0
+                }
+            }
+        };
+        offset = cmp::max(offset as i32 + scroll, 0) as usize;
+    }
+}
+
+fn help_lines(locale: &Locale, wizard: bool, movement_keys: MovementKeys) -> Vec<String> {
+    let mut lines = vec![tr!(locale, "help_title"), String::new(), tr!(locale, "help_keys")];
+    for (binding, command) in key_bindings(movement_keys) {
+        lines.push(format!("  {:<12} {}", binding.label(locale), command.description(locale)));
+    }
+    lines.push(format!("  {:<12} {}", SKIP_TUTORIAL_KEY.label(locale), tr!(locale, "help_skip_tutorial")));
+    if wizard {
+        lines.push(String::new());
+        lines.push(tr!(locale, "help_wizard"));
+        for (binding, command) in WIZARD_BINDINGS {
+            lines.push(format!("  {:<12} {}", binding.label(locale), command.description(locale)));
+        }
+    }
+    lines.push(String::new());
+    lines.push(tr!(locale, "help_mouse"));
+    lines.push(format!("  {}", tr!(locale, "help_left_click")));
+    lines.push(format!("  {}", tr!(locale, "help_right_click")));
+    lines.push(format!("  {}", tr!(locale, "help_hover")));
+    lines.push(String::new());
+    lines.push(tr!(locale, "help_gamepad"));
+    lines.push(format!("  {:<12} {}", PadInput::Direction(0, 0).label(locale), tr!(locale, "help_pad_move")));
+    for &(input, command) in PAD_BINDINGS {
+        lines.push(format!("  {:<12} {}", input.label(locale), command.description(locale)));
+    }
+    lines.push(format!("  {}", tr!(locale, "help_pad_menus")));
+    lines.push(String::new());
+    lines.push(tr!(locale, "help_symbols"));
+    for key in &["help_symbol_player", "help_symbol_monsters", "help_symbol_items", "help_symbol_stairs", "help_symbol_remains"] {
+        lines.push(format!("  {}", tr!(locale, key)));
+    }
+    lines.push(String::new());
+    lines.push(tr!(locale, "help_colors"));
+    for key in &["help_color_lit", "help_color_remembered", "help_color_messages"] {
+        lines.push(format!("  {}", tr!(locale, key)));
+    }
+    lines
+}
+
+// NOTICE: The whole explored level on one screen, panned with the arrows when it doesn't fit, earlier levels are paged through
+pub(crate) fn full_map(tcod: &mut Tcod, game: &Game, game_objects: &[GameObject]) {
+    let locale = &game.config.locale;
+    let (view_width, view_height) = (SCREEN_WIDTH, SCREEN_HEIGHT - 2);
+    let (player_x, player_y) = game.player.position();
+    let mut offset = (player_x - view_width / 2, player_y - view_height / 2);
+    // NOTICE: Index in the past levels, the current level comes right after the last of them
+    let mut shown = game.past_levels.len();
+
+    while !tcod.root.window_closed() {
+        let past_level = game.past_levels.get(shown);
+        let (map, notes, dungeon_level) = match past_level {
+            Some(level) => (&level.map, &level.notes, level.dungeon_level),
+            None => (&game.map, &game.notes, game.dungeon_level),
+        };
+        let (map_width, map_height) = map_size(map);
+        let max_offset = (cmp::max(0, map_width - view_width), cmp::max(0, map_height - view_height));
+        offset = (offset.0.clamp(0, max_offset.0), offset.1.clamp(0, max_offset.1));
+
+        let mut window = Offscreen::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+        let mut put = |(x, y): (i32, i32), glyph: char, color: Color| {
+            let (view_x, view_y) = (x - offset.0, y - offset.1);
+            if view_x >= 0 && view_y >= 0 && view_x < view_width && view_y < view_height {
+                window.set_default_foreground(color);
+                window.put_char(view_x, view_y, glyph, BackgroundFlag::None);
+            }
+        };
+        for x in 0..map_width {
+            for y in 0..map_height {
+                let tile = map[(x, y)];
+                if !tile.explored {
+                    continue;
+                }
+                match tile.terrain.glyph() {
+                    Some(glyph) => put((x, y), glyph, tile.terrain.colors(&tcod.palette).1),
+                    None if tile.block_sight => put((x, y), '#', GREY),
+                    None => put((x, y), '.', DARK_GREY),
+                }
+            }
+        }
+        match past_level {
+            Some(level) => {
+                for memory in &level.remembered {
+                    put((memory.x, memory.y), memory.char, memory.color);
+                }
+            }
+            None => {
+                for game_object in game_objects.iter().filter(|go| go.is_stairs || go.npc.is_some()) {
+                    if game.map[(game_object.x, game_object.y)].explored {
+                        put(game_object.position(), game_object.char, game_object.color);
+                    }
+                }
+            }
+        }
+        for note in notes {
+            put((note.x, note.y), '!', YELLOW);
+        }
+        if past_level.is_none() {
+            put((player_x, player_y), '@', WHITE);
+        }
+
+        window.set_default_foreground(LIGHT_GREY);
+        window.print_ex(1, SCREEN_HEIGHT - 2, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "full_map_legend"));
+        window.print_ex(
+            SCREEN_WIDTH - 2,
+            SCREEN_HEIGHT - 2,
+            BackgroundFlag::None,
+            TextAlignment::Right,
+            tr!(locale, "full_map_depth", level = dungeon_level),
+        );
+        window.print_ex(1, SCREEN_HEIGHT - 1, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "full_map_footer"));
+
+        blit(&window, (0, 0), (SCREEN_WIDTH, SCREEN_HEIGHT), &mut tcod.root, (0, 0), 1.0, 1.0);
+        tcod.root.flush();
+
+        let key = tcod.root.wait_for_keypress(true);
+        let (dx, dy) = match key.code {
+            KeyCode::Up => (0, -1),
+            KeyCode::Down => (0, 1),
+            KeyCode::Left => (-1, 0),
+            KeyCode::Right => (1, 0),
+            KeyCode::PageUp => {
+                shown = shown.saturating_sub(1);
+                (0, 0)
+            }
+            KeyCode::PageDown => {
+                shown = cmp::min(shown + 1, game.past_levels.len());
+                (0, 0)
+            }
+            _ => return,
+        };
+        offset = ((offset.0 + dx * 5).clamp(0, max_offset.0), (offset.1 + dy * 5).clamp(0, max_offset.1));
+    }
+}
+
+pub(crate) fn help_screen(tcod: &mut Tcod, locale: &Locale, wizard: bool) {
+    let lines = help_lines(locale, wizard, tcod.movement_keys);
+    scroll_screen(tcod, &lines, &tr!(locale, "help_footer"));
+}
+
+pub(crate) fn scroll_screen(tcod: &mut Tcod, lines: &[String], footer: &str) {
+    scroll_screen_loading(tcod, lines.to_vec(), footer, None);
+}
+
+/// Same as `scroll_screen`, the lines `loading` fetches take the place of the last line once they came
+pub(crate) fn scroll_screen_loading(tcod: &mut Tcod, mut lines: Vec<String>, footer: &str, mut loading: Option<Pending<Vec<String>>>) {
+    let width = SCREEN_WIDTH - 10;
+    let height = SCREEN_HEIGHT - 6;
+    let visible_lines = (height - 2) as usize;
+    let mut offset = 0;
+
+    while !tcod.root.window_closed() {
+        if let Some(loaded) = loading.as_mut().and_then(|pending| pending.poll().cloned()) {
+            lines.pop();
+            lines.extend(loaded);
+            loading = None;
+        }
+        let max_offset = lines.len().saturating_sub(visible_lines);
+        let mut window = Offscreen::new(width, height);
+        window.set_default_foreground(WHITE);
+        for (index, line) in lines.iter().skip(offset).take(visible_lines).enumerate() {
+            window.print_ex(1, index as i32, BackgroundFlag::None, TextAlignment::Left, line);
+        }
+        window.set_default_foreground(LIGHT_GREY);
+        window.print_ex(width / 2, height - 1, BackgroundFlag::None, TextAlignment::Center, footer);
+
+        blit(&window, (0, 0), (width, height), &mut tcod.root, (5, 3), 1.0, 0.9);
+        tcod.root.flush();
+
+        let scroll = if let Some((_, Event::Key(key))) = input::check_for_event(input::KEY_PRESS) {
+            match key.code {
+                KeyCode::Up => -1,
+                KeyCode::Down => 1,
+                KeyCode::PageUp => -(visible_lines as i32),
+                KeyCode::PageDown => visible_lines as i32,
+                KeyCode::NoKey => 0,
+                _ => return,
+            }
+        } else {
+            match tcod.gamepad.as_mut().and_then(Gamepad::poll) {
+                Some(PadInput::Direction(_, dy)) => dy,
+                Some(_) => return,
+                None => {
+                    thread::sleep(Duration::from_millis(MENU_POLL_MS));
+                    0
+                }
+            }
+        };
+        offset = cmp::min(cmp::max(offset as i32 + scroll, 0) as usize, max_offset);
+    }
+}
+
+pub(crate) fn menu<T: AsRef<str>>(header: &str, options: &[T], width: i32, tcod: &mut Tcod) -> Option<usize> {
+    menu_at(header, options, width, None, tcod)
+}
+
+/// Same as `menu`, with the window's top left corner at `position` instead of centered
+pub(crate) fn menu_at<T: AsRef<str>>(
+    header: &str,
+    options: &[T],
+    width: i32,
+    position: Option<(i32, i32)>,
+    tcod: &mut Tcod,
+) -> Option<usize> {
+    menu_with_panel(header, options, width, position, None, tcod)
+}
+
+type MenuPanel<'a> = &'a dyn Fn(usize) -> Vec<(String, Color)>;
+
+/// Same as `menu_at`, `panel` gives the colored lines shown beside the menu for the option under the cursor
+fn menu_with_panel<T: AsRef<str>>(
+    header: &str,
+    options: &[T],
+    width: i32,
+    position: Option<(i32, i32)>,
+    panel: Option<MenuPanel>,
+    tcod: &mut Tcod,
+) -> Option<usize> {
+    let rows: Vec<MenuRow> = options
+        .iter()
+        .enumerate()
+        .map(|(index, option)| MenuRow::Option(index, option.as_ref().to_string()))
+        .collect();
+    menu_rows(header, &rows, width, position, panel, tcod)
+}
+
+pub(crate) fn menu_rows(
+    header: &str,
+    rows: &[MenuRow],
+    width: i32,
+    position: Option<(i32, i32)>,
+    panel: Option<MenuPanel>,
+    tcod: &mut Tcod,
+) -> Option<usize> {
+    let options: Vec<usize> = rows
+        .iter()
+        .filter_map(|row| match row {
+            MenuRow::Option(index, _) => Some(*index),
+            MenuRow::Heading(_) => None,
+        })
+        .collect();
+    assert!(
+        options.len() <= 9,
+        "Cannot have a menu with more than 9 options."
+    );
+
+    let header_height = tcod.root.get_height_rect(0, 0, width, SCREEN_HEIGHT, header);
+    let height = rows.len() as i32 + header_height;
+    let mut selected = 0;
+
+    let key = loop {
+        if let Some(ref title_screen) = tcod.title_screen {
+            render_title_screen(title_screen, &mut tcod.root, &tcod.palette);
+        }
+        let mut window = Offscreen::new(width, height);
+        window.set_default_foreground(WHITE);
+        window.print_rect_ex(
+            0,
+            0,
+            width,
+            height,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            header,
+        );
+
+        for (line, row) in rows.iter().enumerate() {
+            let (color, text) = match row {
+                MenuRow::Heading(text) => (LIGHT_YELLOW, text.clone()),
+                MenuRow::Option(index, text) => {
+                    let menu_letter = (b'1' + *index as u8) as char;
+                    (WHITE, format!("({}) {}", menu_letter, text))
+                }
+            };
+            window.set_default_foreground(color);
+            window.print_ex(
+                0,
+                header_height + line as i32,
+                BackgroundFlag::None,
+                TextAlignment::Left,
+                text,
+            );
+        }
+
+        // NOTICE: The cursor is only drawn when a gamepad or a side panel can make use of it
+        if (tcod.gamepad.is_some() || panel.is_some()) && !options.is_empty() {
+            let line = rows
+                .iter()
+                .position(|row| matches!(row, MenuRow::Option(index, _) if *index == options[selected]))
+                .unwrap_or(0);
+            window.set_default_background(DARK_BLUE);
+            window.rect(0, header_height + line as i32, width, 1, false, BackgroundFlag::Set);
+        }
+
+        let panel_width = if panel.is_some() { COMPARE_PANEL_WIDTH + 1 } else { 0 };
+        let (x, y) = match position {
+            Some((x, y)) => (cmp::min(x, SCREEN_WIDTH - width - panel_width), cmp::min(y, SCREEN_HEIGHT - height)),
+            None => (SCREEN_WIDTH / 2 - (width + panel_width) / 2, SCREEN_HEIGHT / 2 - height / 2),
+        };
+        blit(&window, (0, 0), (width, height), &mut tcod.root, (x, y), 1.0, 0.7);
+
+        let lines = match panel {
+            Some(panel) if !options.is_empty() => panel(options[selected]),
+            _ => vec![],
+        };
+        if !lines.is_empty() {
+            let mut side = Offscreen::new(COMPARE_PANEL_WIDTH, lines.len() as i32);
+            for (line, (text, color)) in lines.iter().enumerate() {
+                side.set_default_foreground(*color);
+                side.print_ex(0, line as i32, BackgroundFlag::None, TextAlignment::Left, text);
+            }
+            let side_size = (COMPARE_PANEL_WIDTH, lines.len() as i32);
+            blit(&side, (0, 0), side_size, &mut tcod.root, (x + width + 1, y), 1.0, 0.7);
+        }
+        tcod.root.flush();
+
+        if let Some((_, Event::Key(key))) = input::check_for_event(input::KEY_PRESS) {
+            match key.code {
+                KeyCode::Up | KeyCode::Down if panel.is_some() && !options.is_empty() => {
+                    let dy = if key.code == KeyCode::Up { -1 } else { 1 };
+                    selected = (selected as i32 + dy).rem_euclid(options.len() as i32) as usize;
+                    continue;
+                }
+                KeyCode::Enter if panel.is_some() && !options.is_empty() => return Some(options[selected]),
+                _ => break key,
+            }
+        }
+        if tcod.root.window_closed() {
+            return None;
+        }
+        match tcod.gamepad.as_mut().and_then(Gamepad::poll) {
+            Some(PadInput::Direction(_, dy)) if dy != 0 && !options.is_empty() => {
+                selected = (selected as i32 + dy).rem_euclid(options.len() as i32) as usize;
+            }
+            Some(PadInput::South) if !options.is_empty() => return Some(options[selected]),
+            Some(PadInput::South) | Some(PadInput::East) => return None,
+            // NOTICE: Neither the keyboard nor the gamepad blocks, so idle polls wait a moment before the next
+            _ => thread::sleep(Duration::from_millis(MENU_POLL_MS)),
+        }
+    };
+
+    if key.printable.is_ascii_digit() {
+        let index = (key.printable.to_digit(10).unwrap() as usize).wrapping_sub(1);
+        if options.contains(&index) {
+            Some(index)
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+pub(crate) fn msgbox(text: &str, width: i32, tcod: &mut Tcod) {
+    let options: &[&str] = &[];
+    menu(text, options, width, tcod);
+}
+
+pub(crate) fn text_input(header: &str, max_length: usize, width: i32, root: &mut Root) -> Option<String> {
+    let mut text = String::new();
+    let header_height = root.get_height_rect(0, 0, width, SCREEN_HEIGHT, header);
+    let height = header_height + 2;
+
+    loop {
+        let mut window = Offscreen::new(width, height);
+        window.set_default_foreground(WHITE);
+        window.print_rect_ex(
+            0,
+            0,
+            width,
+            height,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            header,
+        );
+        window.print_ex(
+            0,
+            header_height + 1,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            format!("> {}_", text),
+        );
+
+        let x = SCREEN_WIDTH / 2 - width / 2;
+        let y = SCREEN_HEIGHT / 2 - height / 2;
+        blit(&window, (0, 0), (width, height), root, (x, y), 1.0, 1.0);
+        root.flush();
+
+        let key = root.wait_for_keypress(true);
+        match key.code {
+            KeyCode::Enter if !text.trim().is_empty() => return Some(text.trim().to_string()),
+            KeyCode::Escape => return None,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Text => {
+                for c in key.text().chars() {
+                    if text.chars().count() < max_length && is_valid_name_char(c) {
+                        text.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// NOTICE: Opened with Escape so that a stray key press never ends a run, None resumes the game
+pub(crate) fn escape_menu(tcod: &mut Tcod, game: &mut Game, game_objects: &[GameObject]) -> Option<GameOver> {
+    while !tcod.root.window_closed() {
+        render_all(tcod, game, game_objects);
+        let locale = &game.config.locale;
+        let labels = [
+            tr!(locale, "escape_resume"),
+            tr!(locale, "escape_options"),
+            tr!(locale, "escape_save_quit"),
+            tr!(locale, "escape_abandon"),
+        ];
+        match menu(&tr!(locale, "escape_header"), &labels, 30, tcod) {
+            Some(1) => options_menu(tcod, &game.config.locale),
+            Some(2) => match autosave(game, game_objects) {
+                Ok(()) => return Some(GameOver::MainMenu),
+                Err(error) => msgbox(&tr!(game.config.locale, "autosave_failed", error = error), 30, tcod),
+            },
+            Some(3) => {
+                let confirm = menu(
+                    &tr!(game.config.locale, "abandon_confirm"),
+                    &[tr!(game.config.locale, "yes"), tr!(game.config.locale, "no")],
+                    30,
+                    tcod,
+                );
+                if confirm == Some(0) {
+                    // NOTICE: An abandoned daily run still counts, otherwise it could be rerolled
+                    if game.daily.is_some() {
+                        record_daily_score(game).ok();
+                    }
+                    delete_slot(&game.player_name);
+                    return Some(GameOver::MainMenu);
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(GameOver::Quit)
+}
+
+// NOTICE: Shown once the player died, the dead player gets no more turns and picks where to go next
+pub(crate) fn death_recap(tcod: &mut Tcod, game: &mut Game, morgue: &Result<PathBuf, Box<dyn Error>>, replay: &Replay) -> GameOver {
+    let locale = &game.config.locale;
+    let final_blow = game.final_blow.clone().unwrap_or_default();
+    let killer = game.killer.clone().unwrap_or_else(|| tr!(locale, "killer_unknown"));
+    let inventory: Vec<String> = game.inventory.iter().map(|item| item.display_name(locale)).collect();
+
+    while !tcod.root.window_closed() {
+        let mut window = Offscreen::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+        window.set_default_foreground(LIGHT_RED);
+        window.print_ex(SCREEN_WIDTH / 2, 1, BackgroundFlag::None, TextAlignment::Center, tr!(locale, "player_died"));
+        window.set_default_foreground(WHITE);
+        window.print_ex(2, 3, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "recap_killer", killer = killer));
+        window.print_rect(2, 4, SCREEN_WIDTH - 4, 2, tr!(locale, "recap_final_blow", blow = final_blow));
+        let depth = match game.arena {
+            Some(ref arena) => tr!(locale, "recap_waves", waves = arena.waves_survived, turns = game.turn),
+            None => tr!(locale, "recap_depth", level = game.dungeon_level, turns = game.turn),
+        };
+        window.print_ex(2, 6, BackgroundFlag::None, TextAlignment::Left, depth);
+
+        window.print_ex(2, 8, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "recap_hp_history"));
+        render_sparkline(&mut window, (2, 9), &game.hp_history);
+
+        window.set_default_foreground(WHITE);
+        let inventory_y = 10 + RECAP_GRAPH_HEIGHT;
+        window.print_ex(2, inventory_y, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "recap_inventory"));
+        let inventory_text = if inventory.is_empty() { tr!(locale, "inventory_empty") } else { inventory.join(", ") };
+        window.print_rect(4, inventory_y + 1, SCREEN_WIDTH - 6, 3, inventory_text);
+
+        let messages_y = inventory_y + 5;
+        window.print_ex(2, messages_y, BackgroundFlag::None, TextAlignment::Left, tr!(locale, "morgue_last_messages"));
+        let last_messages: Vec<_> = game.messages.iter().rev().take(10).collect();
+        for (index, message) in last_messages.into_iter().rev().enumerate() {
+            window.set_default_foreground(tcod.palette.color(message.category.color()));
+            window.print_ex(4, messages_y + 1 + index as i32, BackgroundFlag::None, TextAlignment::Left, message.text());
+        }
+
+        let morgue_status = match morgue {
+            Ok(path) => tr!(locale, "morgue_written", path = path.display()),
+            Err(error) => tr!(locale, "morgue_failed", error = error),
+        };
+        window.set_default_foreground(LIGHT_GREY);
+        window.print_ex(SCREEN_WIDTH / 2, SCREEN_HEIGHT - 4, BackgroundFlag::None, TextAlignment::Center, morgue_status);
+        if let Some(status) = submission_status(&mut game.leaderboard_submission, locale) {
+            window.print_ex(SCREEN_WIDTH / 2, SCREEN_HEIGHT - 5, BackgroundFlag::None, TextAlignment::Center, status);
+        }
+        window.set_default_foreground(WHITE);
+        window.print_ex(SCREEN_WIDTH / 2, SCREEN_HEIGHT - 2, BackgroundFlag::None, TextAlignment::Center, tr!(locale, "recap_footer"));
+
+        blit(&window, (0, 0), (SCREEN_WIDTH, SCREEN_HEIGHT), &mut tcod.root, (0, 0), 1.0, 1.0);
+        tcod.root.flush();
+
+        // NOTICE: Polled rather than waited for, the leaderboard's answer shows up as soon as it comes
+        let key = match input::check_for_event(input::KEY_PRESS) {
+            Some((_, Event::Key(key))) => key,
+            _ => {
+                thread::sleep(Duration::from_millis(MENU_POLL_MS));
+                continue;
+            }
+        };
+        match key.printable {
+            '1' => {
+                let lines: Vec<String> = match morgue.as_ref().map(fs::read_to_string) {
+                    Ok(Ok(text)) => text.lines().map(String::from).collect(),
+                    _ => vec![tr!(locale, "recap_no_morgue")],
+                };
+                scroll_screen(tcod, &lines, &tr!(locale, "help_footer"));
+            }
+            '2' => return GameOver::NewGame,
+            '3' => return GameOver::MainMenu,
+            '4' => return GameOver::Quit,
+            '5' => replay_viewer(tcod, replay, &game.config),
+            _ if key.code == KeyCode::Escape => return GameOver::MainMenu,
+            _ => {}
+        }
+    }
+    GameOver::Quit
+}
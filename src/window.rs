@@ -0,0 +1,146 @@
+use tcod::console::*;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use clap::ArgMatches;
+use crate::CliOptions;
+use crate::audio::Audio;
+use crate::config::{Config, DEFAULT_FONT, WindowMode};
+use crate::controls::Gamepad;
+use crate::game::{GameOver, frame_limit};
+use crate::map::{MAP_HEIGHT, MAP_WIDTH};
+use crate::menu::{MAX_RENDER_SCALE, glyph_size, init_root, main_menu};
+use crate::narration::new_narrator;
+use crate::net::{Broadcast, StateExport, host_coop, join_coop};
+use crate::save::{
+    install_panic_hook, load_autosave, load_replay, play_game_with_emergency_save, replay_viewer, restore_emergency_save,
+};
+use crate::ui::{PANEL_HEIGHT, Palette, SCREEN_WIDTH, Tcod};
+
+const ANSI_CLEAR_SCREEN: &str = "\x1b[2J\x1b[?25l";
+
+/// The command line options only the window reads
+pub(crate) struct WindowOptions {
+    replay: Option<String>,
+    host: Option<u16>,
+    join: Option<String>,
+    broadcast: Option<u16>,
+    /// Whether co-op, the broadcast & the state export take connections from other machines
+    public: bool,
+    export_state: Option<String>,
+    /// Plays in the terminal instead, in builds that have both
+    pub(crate) terminal: bool,
+}
+
+impl WindowOptions {
+    pub fn new(matches: &ArgMatches) -> Self {
+        WindowOptions {
+            replay: matches.value_of("replay").map(String::from),
+            host: matches.value_of("host").and_then(|port| port.parse().ok()),
+            join: matches.value_of("join").map(String::from),
+            broadcast: matches.value_of("broadcast").and_then(|port| port.parse().ok()),
+            public: matches.is_present("public"),
+            export_state: matches.value_of("export-state").map(String::from),
+            terminal: matches.is_present("terminal"),
+        }
+    }
+}
+
+/// Opens the tcod window and plays in it, starting with what the command line asked for
+pub(crate) fn run_window(config: Config, options: CliOptions) {
+    let broadcast = match options.window.broadcast.map(|port| Broadcast::new(port, options.window.public, ANSI_CLEAR_SCREEN)) {
+        Some(Ok(broadcast)) => Some(broadcast),
+        Some(Err(error)) => {
+            eprintln!("The broadcast could not be started: {}", error);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let state_export = match options.window.export_state.as_deref().map(|target| StateExport::new(target, options.window.public)) {
+        Some(Ok(state_export)) => Some(state_export),
+        Some(Err(error)) => {
+            eprintln!("The state export could not be started: {}", error);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    tcod::system::set_fps(frame_limit(config.fps_limit, config.vsync));
+
+    let font = if glyph_size(&config.font).is_some() {
+        config.font.clone()
+    } else {
+        eprintln!("The {} font could not be read, falling back on {}.", config.font, DEFAULT_FONT);
+        DEFAULT_FONT.to_string()
+    };
+    let render_scale = config.render_scale.clamp(1, MAX_RENDER_SCALE);
+    let root = init_root(&font, render_scale, config.window_mode, config.vsync);
+
+    let mut tcod = Tcod {
+        root,
+        con: Offscreen::new(MAP_WIDTH, MAP_HEIGHT),
+        panel: Offscreen::new(SCREEN_WIDTH, PANEL_HEIGHT),
+        key: Default::default(),
+        input_queue: VecDeque::new(),
+        held_key: None,
+        key_repeat_delay: Duration::from_millis(config.key_repeat_delay_ms),
+        key_repeat_interval: Duration::from_secs(1) / config.key_repeat_rate.max(1),
+        movement_keys: config.movement_keys,
+        mouse: Default::default(),
+        narrator: new_narrator(&config),
+        palette: Palette::new(config.palette),
+        audio: Audio::new(&config),
+        gamepad: Gamepad::new(),
+        health_bars: config.monster_health_bars,
+        skipped_prompts: config.skipped_prompts.clone(),
+        font,
+        render_scale,
+        window_mode: config.window_mode,
+        fullscreen_mode: if config.window_mode == WindowMode::Windowed { WindowMode::Fullscreen } else { config.window_mode },
+        screen_motion: config.screen_motion,
+        shake_end: None,
+        banner: None,
+        activity_stepped: Instant::now(),
+        fps_limit: config.fps_limit,
+        vsync: config.vsync,
+        debug_overlay: false,
+        render_time: Duration::default(),
+        map_cells: vec![],
+        overlay_cells: vec![],
+        tiles_drawn: 0,
+        console_log: vec![],
+        title_screen: None,
+        coop: None,
+        broadcast,
+        state_export,
+    };
+
+    install_panic_hook();
+    let mut outcome = restore_emergency_save(&mut tcod, &config);
+
+    if let Some(slot) = options.window.replay.as_ref().filter(|_| outcome != GameOver::Quit) {
+        match load_replay(slot) {
+            Some(replay) => replay_viewer(&mut tcod, &replay, &config),
+            None => eprintln!("No replay could be loaded from slot {}.", slot),
+        }
+    }
+
+    if let Some(slot) = options.load.filter(|_| outcome != GameOver::Quit) {
+        outcome = match load_autosave(&slot, &config) {
+            Some((mut game, mut game_objects)) => play_game_with_emergency_save(&mut tcod, &mut game, &mut game_objects),
+            None => {
+                eprintln!("No save could be loaded from slot {}.", slot);
+                GameOver::MainMenu
+            }
+        };
+    }
+
+    if outcome != GameOver::Quit {
+        if let Some(port) = options.window.host {
+            outcome = host_coop(&mut tcod, port, options.window.public, &config);
+        } else if let Some(ref address) = options.window.join {
+            outcome = join_coop(&mut tcod, address, &config);
+        }
+    }
+
+    main_menu(&mut tcod, &config, outcome);
+}
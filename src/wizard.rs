@@ -0,0 +1,242 @@
+use std::cmp;
+use tcod::console::*;
+use crate::config::{Component, Config};
+use crate::controls::WizardCommand;
+use crate::game::{Game, MessageCategory, go_to_level};
+use crate::items::{
+    Gem, Item, new_armor, new_component, new_consumable, new_dart, new_food, new_gem, new_ring_of_detection,
+    new_weapon,
+};
+use crate::map::{Movement, blocks_movement, compute_fov, is_blocked, map_size, mouse_tile, new_key};
+use crate::monsters::{MONSTER_SPECIES, new_monster_of};
+use crate::object::GameObject;
+use crate::portable::KeyCode;
+use crate::portable::colors::*;
+use crate::save::restore_game;
+use crate::ui::{SCREEN_WIDTH, Tcod, render_all, text_input};
+
+const CONSOLE_HEIGHT: i32 = 12;
+const CONSOLE_HISTORY: usize = 100;
+
+pub(crate) fn wizard(command: WizardCommand, tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    game.changed_outside_step = true;
+    let locale = game.config.locale.clone();
+    let message = match command {
+        WizardCommand::RevealMap => wizard_reveal(game),
+        WizardCommand::Teleport => match mouse_tile(tcod.mouse, game) {
+            Some(tile) => wizard_teleport(tile, game, game_objects),
+            None => tr!(locale, "wizard_blocked"),
+        },
+        WizardCommand::Spawn => match text_input(&tr!(locale, "wizard_spawn_prompt"), 30, 40, &mut tcod.root) {
+            Some(name) => wizard_spawn(&name, None, game, game_objects),
+            None => return,
+        },
+        WizardCommand::SetHp => match text_input(&tr!(locale, "wizard_hp_prompt"), 4, 30, &mut tcod.root).and_then(|text| text.parse().ok()) {
+            Some(hp) => wizard_set_hp(hp, game),
+            None => return,
+        },
+        WizardCommand::JumpToDepth => match text_input(&tr!(locale, "wizard_depth_prompt"), 3, 30, &mut tcod.root).and_then(|text| text.parse().ok()) {
+            Some(level) => wizard_goto(level, game, game_objects),
+            None => return,
+        },
+        WizardCommand::Undo => wizard_undo(game, game_objects),
+        WizardCommand::Console => {
+            developer_console(tcod, game, game_objects);
+            return;
+        }
+    };
+    game.messages.add(message, MessageCategory::System);
+}
+
+fn wizard_reveal(game: &mut Game) -> String {
+    for tile in game.map.tiles_mut() {
+        tile.explored = true;
+    }
+    tr!(game.config.locale, "wizard_revealed")
+}
+
+fn wizard_teleport((x, y): (i32, i32), game: &mut Game, game_objects: &mut [GameObject]) -> String {
+    let (map_width, map_height) = map_size(&game.map);
+    let movement = game.player.movement();
+    if x < 0 || y < 0 || x >= map_width || y >= map_height || is_blocked(x, y, movement, &game.map, game_objects) {
+        return tr!(game.config.locale, "wizard_blocked");
+    }
+    game.player.set_position(x, y);
+    compute_fov(game, game_objects);
+    tr!(game.config.locale, "wizard_teleported", x = x, y = y)
+}
+
+/// Spawns at `position`, or next to the player when none is given
+fn wizard_spawn(name: &str, position: Option<(i32, i32)>, game: &mut Game, game_objects: &mut Vec<GameObject>) -> String {
+    let (player_x, player_y) = game.player.position();
+    let (map_width, map_height) = map_size(&game.map);
+    let free = |&(x, y): &(i32, i32)| {
+        x >= 0 && y >= 0 && x < map_width && y < map_height && !blocks_movement(x, y, Movement::default(), game, game_objects)
+    };
+    let position = match position {
+        Some(position) => Some(position).filter(free),
+        None => (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (player_x + dx, player_y + dy)))
+            .find(|&(x, y)| free(&(x, y)) && (x, y) != (player_x, player_y)),
+    };
+    let (x, y) = match position {
+        Some(position) => position,
+        None => return tr!(game.config.locale, "wizard_blocked"),
+    };
+    match spawnable(name, x, y, &game.config) {
+        Some(game_object) => {
+            let message = tr!(game.config.locale, "wizard_spawned", name = game_object.display_name(&game.config.locale));
+            game_objects.push(game_object);
+            message
+        }
+        None => tr!(game.config.locale, "wizard_unknown", name = name),
+    }
+}
+
+fn wizard_give(name: &str, game: &mut Game) -> String {
+    match spawnable(name, 0, 0, &game.config).filter(|game_object| game_object.item.is_some()) {
+        Some(item) => {
+            let message = tr!(game.config.locale, "wizard_given", name = item.display_name(&game.config.locale));
+            game.inventory.push(item);
+            message
+        }
+        None => tr!(game.config.locale, "wizard_unknown", name = name),
+    }
+}
+
+fn wizard_set_hp(hp: i32, game: &mut Game) -> String {
+    match game.player.fighter.as_mut() {
+        Some(fighter) if hp > 0 => {
+            fighter.max_hp = cmp::max(fighter.max_hp, hp);
+            fighter.hp = hp;
+            tr!(game.config.locale, "wizard_hp_set", hp = hp)
+        }
+        _ => tr!(game.config.locale, "console_usage", usage = "hp <hit points>"),
+    }
+}
+
+/// Goes back to the state saved after the turn before the last one
+fn wizard_undo(game: &mut Game, game_objects: &mut Vec<GameObject>) -> String {
+    let restored = game.snapshots.iter().nth_back(1).map(|snapshot| restore_game(snapshot, &game.config));
+    match restored {
+        None => tr!(game.config.locale, "wizard_undo_empty"),
+        Some(Err(error)) => tr!(game.config.locale, "wizard_undo_failed", error = error),
+        Some(Ok((restored, restored_objects))) => {
+            let mut snapshots = std::mem::take(&mut game.snapshots);
+            snapshots.pop_back();
+            *game = restored;
+            *game_objects = restored_objects;
+            game.snapshots = snapshots;
+            game.changed_outside_step = true;
+            tr!(game.config.locale, "wizard_undone", turn = game.turn)
+        }
+    }
+}
+
+fn wizard_goto(level: u32, game: &mut Game, game_objects: &mut Vec<GameObject>) -> String {
+    if level == 0 {
+        return tr!(game.config.locale, "console_usage", usage = "goto <level>");
+    }
+    go_to_level(level, game, game_objects);
+    tr!(game.config.locale, "wizard_went", level = level)
+}
+
+/// Parses one developer console line and runs it, returning what to print back
+fn console_command(line: &str, game: &mut Game, game_objects: &mut Vec<GameObject>) -> String {
+    let locale = game.config.locale.clone();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    // NOTICE: Names may be typed with underscores, like the locale keys
+    let name = |words: &[&str]| words.join(" ").replace('_', " ");
+    match words.as_slice() {
+        [] => String::new(),
+        ["help"] => tr!(locale, "console_help"),
+        ["seed"] => tr!(locale, "console_seed", seed = game.seed),
+        ["reveal"] => wizard_reveal(game),
+        ["teleport", x, y] => match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => wizard_teleport((x, y), game, game_objects),
+            _ => tr!(locale, "console_usage", usage = "teleport <x> <y>"),
+        },
+        ["spawn", rest @ .., x, y] if x.parse::<i32>().is_ok() && y.parse::<i32>().is_ok() && !rest.is_empty() => {
+            let position = (x.parse().unwrap_or(0), y.parse().unwrap_or(0));
+            wizard_spawn(&name(rest), Some(position), game, game_objects)
+        }
+        ["spawn", rest @ ..] if !rest.is_empty() => wizard_spawn(&name(rest), None, game, game_objects),
+        ["give", rest @ ..] if !rest.is_empty() => wizard_give(&name(rest), game),
+        ["hp", hp] => wizard_set_hp(hp.parse().unwrap_or(0), game),
+        ["goto", level] => wizard_goto(level.parse().unwrap_or(0), game, game_objects),
+        ["undo"] => wizard_undo(game, game_objects),
+        [command, ..] => tr!(locale, "console_unknown", command = command),
+    }
+}
+
+// NOTICE: Drops down over the top of the map, closed with the backtick again or Escape
+fn developer_console(tcod: &mut Tcod, game: &mut Game, game_objects: &mut Vec<GameObject>) {
+    let mut input = String::new();
+    // NOTICE: The map below is only redrawn after a command
+    render_all(tcod, game, game_objects);
+    loop {
+        let mut window = Offscreen::new(SCREEN_WIDTH, CONSOLE_HEIGHT);
+        window.set_default_background(DARKER_BLUE);
+        window.clear();
+        window.set_default_foreground(LIGHT_GREY);
+        let visible = (CONSOLE_HEIGHT - 1) as usize;
+        let skipped = tcod.console_log.len().saturating_sub(visible);
+        for (index, line) in tcod.console_log.iter().skip(skipped).enumerate() {
+            window.print_ex(1, index as i32, BackgroundFlag::None, TextAlignment::Left, line);
+        }
+        window.set_default_foreground(WHITE);
+        window.print_ex(1, CONSOLE_HEIGHT - 1, BackgroundFlag::None, TextAlignment::Left, format!("> {}_", input));
+        blit(&window, (0, 0), (SCREEN_WIDTH, CONSOLE_HEIGHT), &mut tcod.root, (0, 0), 1.0, 0.9);
+        tcod.root.flush();
+
+        let key = tcod.root.wait_for_keypress(true);
+        match key.code {
+            KeyCode::Escape => return,
+            KeyCode::Text if key.text() == "`" => return,
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut input);
+                tcod.console_log.push(format!("> {}", line));
+                let output = console_command(&line, game, game_objects);
+                tcod.console_log.extend(output.lines().map(String::from));
+                let overflow = tcod.console_log.len().saturating_sub(CONSOLE_HISTORY);
+                tcod.console_log.drain(..overflow);
+                render_all(tcod, game, game_objects);
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Text => input.push_str(key.text()),
+            _ => {}
+        }
+    }
+}
+
+/// A monster or item matching `name`, either its displayed name or its species
+fn spawnable(name: &str, x: i32, y: i32, config: &Config) -> Option<GameObject> {
+    let locale = &config.locale;
+    let matches = |game_object: &GameObject| {
+        game_object.display_name(locale).eq_ignore_ascii_case(name) || game_object.species.eq_ignore_ascii_case(name)
+    };
+    let monsters = MONSTER_SPECIES.iter().map(|species| new_monster_of(species, x, y, config));
+    let consumables = [
+        Item::Heal,
+        Item::ScrollOfLightning,
+        Item::ScrollOfConfusion,
+        Item::ScrollOfBlizzard,
+        Item::SeeInvisible,
+        Item::Levitation,
+        Item::RepairKit,
+        Item::EnchantWeapon,
+        Item::EnchantArmor,
+    ];
+    let mut items: Vec<GameObject> = consumables.iter().map(|&item| new_consumable(x, y, item)).collect();
+    items.push(new_ring_of_detection(x, y));
+    items.push(new_food(x, y));
+    items.push(new_dart(x, y));
+    items.push(new_key(x, y));
+    items.extend([Component::Herb, Component::Bone, Component::Shard].iter().map(|&component| new_component(x, y, component)));
+    items.extend(Gem::ALL.iter().map(|&gem| new_gem(x, y, gem)));
+    items.extend(config.content.weapons.iter().map(|weapon| new_weapon(x, y, weapon)));
+    items.extend(config.content.armor.iter().map(|armor| new_armor(x, y, armor)));
+    monsters.chain(items).find(matches)
+}
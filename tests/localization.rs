@@ -0,0 +1,110 @@
+use rust_rogue::{Locale, Name};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+
+fn language_keys(language: &str) -> BTreeSet<String> {
+    let contents = fs::read_to_string(format!("assets/lang/{}.toml", language)).unwrap();
+    let strings: HashMap<String, String> = toml::from_str(&contents).unwrap();
+    strings.into_keys().collect()
+}
+
+#[test]
+fn every_language_translates_every_key() {
+    let english_keys = language_keys("en");
+    for entry in fs::read_dir("assets/lang").unwrap() {
+        let path = entry.unwrap().path();
+        let language = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let keys = language_keys(&language);
+
+        let missing: Vec<_> = english_keys.difference(&keys).collect();
+        let unknown: Vec<_> = keys.difference(&english_keys).collect();
+        assert!(missing.is_empty(), "{} is missing {:?}", language, missing);
+        assert!(unknown.is_empty(), "{} has unknown keys {:?}", language, unknown);
+    }
+}
+
+#[test]
+fn every_weapon_is_named_and_described() {
+    let english_keys = language_keys("en");
+    let contents = fs::read_to_string("assets/content/weapons.toml").unwrap();
+    let content: toml::Value = toml::from_str(&contents).unwrap();
+    for weapon in content["weapons"].as_array().unwrap() {
+        let name = weapon["name"].as_str().unwrap();
+        assert!(english_keys.contains(name), "weapon {} has no name", name);
+        assert!(english_keys.contains(&format!("{}_description", name)), "weapon {} has no description", name);
+    }
+}
+
+#[test]
+fn every_armor_is_named_and_described() {
+    let english_keys = language_keys("en");
+    let contents = fs::read_to_string("assets/content/armor.toml").unwrap();
+    let content: toml::Value = toml::from_str(&contents).unwrap();
+    for armor in content["armor"].as_array().unwrap() {
+        let name = armor["name"].as_str().unwrap();
+        assert!(english_keys.contains(name), "armor {} has no name", name);
+        assert!(english_keys.contains(&format!("{}_description", name)), "armor {} has no description", name);
+    }
+}
+
+#[test]
+fn every_monster_species_is_named() {
+    let english_keys = language_keys("en");
+    let contents = fs::read_to_string("assets/content/monsters.toml").unwrap();
+    let content: toml::Value = toml::from_str(&contents).unwrap();
+    for monster in content["monsters"].as_array().unwrap() {
+        let species = monster["species"].as_str().unwrap();
+        assert!(english_keys.contains(species), "species {} has no name", species);
+    }
+}
+
+#[test]
+fn every_unique_is_named_and_drops_a_weapon() {
+    let english_keys = language_keys("en");
+    let weapons = fs::read_to_string("assets/content/weapons.toml").unwrap();
+    let weapons: toml::Value = toml::from_str(&weapons).unwrap();
+    let weapon_names: Vec<_> = weapons["weapons"].as_array().unwrap().iter().map(|w| w["name"].as_str().unwrap()).collect();
+    let contents = fs::read_to_string("assets/content/uniques.toml").unwrap();
+    let content: toml::Value = toml::from_str(&contents).unwrap();
+    for unique in content["uniques"].as_array().unwrap() {
+        let name = unique["name"].as_str().unwrap();
+        assert!(english_keys.contains(name), "unique {} has no name", name);
+        assert!(english_keys.contains(&format!("{}_description", name)), "unique {} has no description", name);
+        assert!(weapon_names.contains(&unique["drop"].as_str().unwrap()), "unique {} drops an unknown weapon", name);
+    }
+}
+
+#[test]
+fn every_affix_is_named() {
+    let english_keys = language_keys("en");
+    let contents = fs::read_to_string("assets/content/affixes.toml").unwrap();
+    let content: toml::Value = toml::from_str(&contents).unwrap();
+    for affix in content["affixes"].as_array().unwrap() {
+        let name = affix["name"].as_str().unwrap();
+        assert!(english_keys.contains(name), "affix {} has no name", name);
+    }
+}
+
+#[test]
+fn every_recipe_and_component_is_named() {
+    let english_keys = language_keys("en");
+    let contents = fs::read_to_string("assets/content/recipes.toml").unwrap();
+    let content: toml::Value = toml::from_str(&contents).unwrap();
+    for recipe in content["recipes"].as_array().unwrap() {
+        let result = recipe["result"].as_str().unwrap();
+        assert!(english_keys.contains(result), "recipe {} has no name", result);
+        for ingredient in recipe["ingredients"].as_array().unwrap() {
+            let component = ingredient["component"].as_str().unwrap();
+            assert!(english_keys.contains(component), "component {} has no name", component);
+        }
+    }
+}
+
+#[test]
+fn names_are_translated_in_the_language_shown() {
+    let name = Name::key("remains").with("monster", Name::key("orc"));
+    assert_eq!(name.translate(&Locale::load("en").unwrap()), "remains of orc");
+    assert_eq!(name.translate(&Locale::load("fr").unwrap()), "restes d'orque");
+    let name = Name::key("remains").with("monster", Name::key("rat"));
+    assert_eq!(name.translate(&Locale::load("fr").unwrap()), "restes de rat");
+}
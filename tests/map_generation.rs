@@ -0,0 +1,79 @@
+use rust_rogue::{make_cave, make_map, new_player, seeded_rng, validate_cave, validate_map, Config, Map, Tile};
+
+const SEED_COUNT: u64 = 2000;
+
+/// Checks the rooms & corridors levels, or the cave levels when `caves` is set
+fn assert_valid_maps(config: &Config, caves: bool) {
+    for seed in 0..SEED_COUNT {
+        let mut player = new_player();
+        let mut game_objects = vec![];
+        let mut rng = seeded_rng(seed);
+        let (map, validation) = if caves {
+            let map = make_cave(&mut player, &mut game_objects, config, &mut rng);
+            let validation = validate_cave(&map, &player);
+            (map, validation)
+        } else {
+            let map = make_map(&mut player, &mut game_objects, config, &mut rng);
+            let validation = validate_map(&map, &player, &game_objects);
+            (map, validation)
+        };
+
+        assert_eq!(map.width(), config.map_width);
+        assert_eq!(map.height(), config.map_height);
+        if let Err(error) = validation {
+            let kind = if caves { "cave" } else { "map" };
+            panic!("Seed {} generated an invalid {}x{} {}: {}", seed, config.map_width, config.map_height, kind, error);
+        }
+    }
+}
+
+#[test]
+fn default_size_maps_are_valid() {
+    assert_valid_maps(&Config::default(), false);
+}
+
+#[test]
+fn default_size_caves_are_valid() {
+    assert_valid_maps(&Config::default(), true);
+}
+
+#[test]
+fn smallest_maps_are_valid() {
+    let default_config = Config::default();
+    let config = Config {
+        map_width: 12,
+        map_height: 12,
+        ..default_config
+    };
+    assert!(config.check_map_size().is_ok());
+    assert_valid_maps(&config, false);
+    assert_valid_maps(&config, true);
+}
+
+#[test]
+fn same_seed_generates_same_map() {
+    let config = Config::default();
+    let (mut first_player, mut first_objects) = (new_player(), vec![]);
+    let (mut second_player, mut second_objects) = (new_player(), vec![]);
+    let first_map = make_map(&mut first_player, &mut first_objects, &config, &mut seeded_rng(42));
+    let second_map = make_map(&mut second_player, &mut second_objects, &config, &mut seeded_rng(42));
+
+    assert_eq!(format!("{:?}", first_map), format!("{:?}", second_map));
+    assert_eq!(format!("{:?}", first_player), format!("{:?}", second_player));
+    assert_eq!(format!("{:?}", first_objects), format!("{:?}", second_objects));
+}
+
+#[test]
+fn maps_are_saved_as_columns() {
+    let config = Config::default();
+    let (mut player, mut game_objects) = (new_player(), vec![]);
+    let map = make_map(&mut player, &mut game_objects, &config, &mut seeded_rng(7));
+
+    let saved = serde_json::to_string(&map).unwrap();
+    let columns: Vec<Vec<Tile>> = serde_json::from_str(&saved).unwrap();
+    assert_eq!(columns.len(), config.map_width as usize);
+    assert!(columns.iter().all(|column| column.len() == config.map_height as usize));
+
+    let loaded: Map = serde_json::from_str(&saved).unwrap();
+    assert_eq!(format!("{:?}", loaded), format!("{:?}", map));
+}